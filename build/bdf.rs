@@ -0,0 +1,296 @@
+//! BDF bitmap fonts, laid out the way `embedded_graphics::mono_font::MonoFont` reads them.
+//!
+//! Every glyph gets a cell the size of the font bounding box, with its
+//! baseline on the same row. Glyphs are sorted by character and put 16 to
+//! a row of the font image.
+
+/// Glyphs in a row of the font image.
+pub const GLYPHS_PER_ROW: usize = 16;
+
+#[derive(Debug)]
+pub struct Font {
+    pub width: usize,
+    pub height: usize,
+    /// Rows from the top of a cell to the baseline.
+    pub baseline: usize,
+    /// Pixels of each cell, row by row, sorted by character.
+    pub glyphs: Vec<(char, Vec<bool>)>,
+}
+
+/// Bounding box, as in `FONTBOUNDINGBOX` and `BBX`: width, height and the
+/// offset of the bottom left corner from the origin on the baseline.
+#[derive(Clone, Copy, Debug)]
+struct BoundingBox {
+    width: usize,
+    height: usize,
+    x: i32,
+    y: i32,
+}
+
+impl BoundingBox {
+    fn parse(fields: &[&str]) -> Result<Self, String> {
+        let numbers: Vec<i32> = fields.iter()
+            .map(|field| field.parse().map_err(|_| format!("bad number `{}`", field)))
+            .collect::<Result<_, _>>()?;
+        match numbers[..] {
+            [width, height, x, y] if width >= 0 && height >= 0 => Ok(Self {
+                width: width as usize,
+                height: height as usize,
+                x,
+                y,
+            }),
+            _ => Err("bad bounding box".into()),
+        }
+    }
+}
+
+impl Font {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bounds = None;
+        let mut glyphs: Vec<(char, Vec<bool>)> = Vec::new();
+        let mut lines = text.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            let at = |err: String| format!("line {}: {}", number + 1, err);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.first() {
+                Some(&"FONTBOUNDINGBOX") => bounds = Some(BoundingBox::parse(&fields[1..]).map_err(at)?),
+                Some(&"STARTCHAR") => {
+                    let bounds = bounds.ok_or_else(|| at("STARTCHAR before FONTBOUNDINGBOX".into()))?;
+                    let name = fields.get(1).copied().unwrap_or_default();
+                    let glyph = parse_glyph(&mut lines, bounds)
+                        .map_err(|err| format!("glyph `{}` from line {}: {}", name, number + 1, err))?;
+                    if let Some((c, pixels)) = glyph {
+                        if glyphs.iter().any(|(other, _)| *other == c) {
+                            return Err(format!("glyph `{}` from line {}: U+{:04X} is there twice",
+                                name, number + 1, c as u32));
+                        }
+                        glyphs.push((c, pixels));
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let bounds = bounds.ok_or("no FONTBOUNDINGBOX")?;
+        if glyphs.is_empty() {
+            return Err("no glyphs".into());
+        }
+        if bounds.y > 0 || bounds.y + bounds.height as i32 <= 0 {
+            return Err("the baseline is outside the font bounding box".into());
+        }
+        glyphs.sort_by_key(|(c, _)| *c);
+
+        Ok(Self {
+            width: bounds.width,
+            height: bounds.height,
+            baseline: (bounds.y + bounds.height as i32 - 1) as usize,
+            glyphs,
+        })
+    }
+
+    /// The font image, one bit per pixel with each row padded to a whole byte,
+    /// and its width.
+    pub fn image(&self) -> (Vec<u8>, usize) {
+        let width = self.width * GLYPHS_PER_ROW;
+        let row_bytes = width.div_ceil(8);
+        let rows = self.glyphs.len().div_ceil(GLYPHS_PER_ROW) * self.height;
+        let mut image = vec![0; row_bytes * rows];
+
+        for (i, (_, pixels)) in self.glyphs.iter().enumerate() {
+            let left = i % GLYPHS_PER_ROW * self.width;
+            let top = i / GLYPHS_PER_ROW * self.height;
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if pixels[y * self.width + x] {
+                        let x = left + x;
+                        image[(top + y) * row_bytes + x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+        }
+        (image, width)
+    }
+
+    /// Characters in the order of the glyphs, for `StrGlyphMapping`. Runs of
+    /// 3 or more go in as a range, `\0` followed by the first and last.
+    pub fn mapping(&self) -> String {
+        let chars: Vec<char> = self.glyphs.iter().map(|(c, _)| *c).collect();
+        let mut mapping = String::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut end = start;
+            while chars.get(end + 1).is_some_and(|c| *c as u32 == chars[end] as u32 + 1) {
+                end += 1;
+            }
+            if end - start >= 2 {
+                mapping.extend(['\0', chars[start], chars[end]]);
+            } else {
+                mapping.extend(&chars[start..=end]);
+            }
+            start = end + 1;
+        }
+        mapping
+    }
+
+    /// Glyph drawn for characters the font lacks: `?` if there is one.
+    pub fn replacement(&self) -> usize {
+        self.glyphs.iter().position(|(c, _)| *c == '?').unwrap_or(0)
+    }
+}
+
+/// Reads a glyph up to `ENDCHAR`, into a cell the size of `font`. Glyphs
+/// without a character, and control characters, come back as `None`.
+fn parse_glyph<'a>(lines: &mut impl Iterator<Item = (usize, &'a str)>, font: BoundingBox) -> Result<Option<(char, Vec<bool>)>, String> {
+    let mut encoding = None;
+    let mut bounds = None;
+    let mut pixels = vec![false; font.width * font.height];
+
+    while let Some((_, line)) = lines.next() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.first() {
+            Some(&"ENCODING") => {
+                let code: i64 = fields.get(1).and_then(|field| field.parse().ok()).ok_or("bad ENCODING")?;
+                encoding = Some(code);
+            },
+            Some(&"BBX") => bounds = Some(BoundingBox::parse(&fields[1..])?),
+            Some(&"BITMAP") => {
+                let bounds = bounds.ok_or("BITMAP before BBX")?;
+                for row in 0..bounds.height {
+                    let (_, line) = lines.next().ok_or("the bitmap ends early")?;
+                    let line = line.trim();
+                    if line.len() * 4 < bounds.width || !line.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return Err(format!("bad bitmap row `{}`", line));
+                    }
+                    let bits: Vec<bool> = line.chars()
+                        .flat_map(|c| {
+                            let nibble = c.to_digit(16).unwrap();
+                            (0..4).map(move |bit| nibble & 8 >> bit != 0)
+                        })
+                        .collect();
+
+                    for (column, _) in bits.iter().take(bounds.width).enumerate().filter(|(_, bit)| **bit) {
+                        // From the top left corner of the cell
+                        let x = bounds.x - font.x + column as i32;
+                        let y = (font.y + font.height as i32 - 1) - (bounds.y + (bounds.height - 1 - row) as i32);
+                        if !(0..font.width as i32).contains(&x) || !(0..font.height as i32).contains(&y) {
+                            return Err(format!("pixel {},{} is outside the {}x{} font bounding box",
+                                column, row, font.width, font.height));
+                        }
+                        pixels[y as usize * font.width + x as usize] = true;
+                    }
+                }
+            },
+            Some(&"ENDCHAR") => {
+                let c = encoding.ok_or("no ENCODING")?;
+                let c = u32::try_from(c).ok().and_then(char::from_u32);
+                return Ok(c.filter(|c| !c.is_control()).map(|c| (c, pixels)));
+            },
+            _ => {},
+        }
+    }
+    Err("no ENDCHAR".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "\
+STARTFONT 2.1
+FONT -test-3x5
+FONTBOUNDINGBOX 3 5 0 -1
+CHARS 4
+STARTCHAR A
+ENCODING 65
+DWIDTH 3 0
+BBX 3 4 0 0
+BITMAP
+40
+A0
+E0
+A0
+ENDCHAR
+STARTCHAR B
+ENCODING 66
+BBX 2 4 0 0
+BITMAP
+C0
+C0
+80
+C0
+ENDCHAR
+STARTCHAR comma
+ENCODING 44
+BBX 1 2 1 -1
+BITMAP
+80
+80
+ENDCHAR
+STARTCHAR C
+ENCODING 67
+BBX 3 1 0 3
+BITMAP
+E0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    fn cell(font: &Font, c: char) -> Vec<String> {
+        let (_, pixels) = font.glyphs.iter().find(|(glyph, _)| *glyph == c).unwrap();
+        pixels.chunks(font.width)
+            .map(|row| row.iter().map(|pixel| if *pixel { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn glyphs() {
+        let font = Font::parse(FONT).unwrap();
+        assert_eq!((font.width, font.height, font.baseline), (3, 5, 3));
+        assert_eq!(font.glyphs.iter().map(|(c, _)| *c).collect::<String>(), ",ABC");
+        assert_eq!(cell(&font, 'A'), [".#.", "#.#", "###", "#.#", "..."]);
+        assert_eq!(cell(&font, ','), ["...", "...", "...", ".#.", ".#."]);
+        assert_eq!(cell(&font, 'C'), ["###", "...", "...", "...", "..."]);
+    }
+
+    #[test]
+    fn mapping() {
+        let font = Font::parse(FONT).unwrap();
+        assert_eq!(font.mapping(), ",\0AC");
+        assert_eq!(font.replacement(), 0);
+    }
+
+    #[test]
+    fn image() {
+        let font = Font::parse(FONT).unwrap();
+        let (image, width) = font.image();
+        assert_eq!(width, 48);
+        assert_eq!(image.len(), 6 * 5);
+        // The top rows of `,`, `A`, `B` and `C`
+        assert_eq!(image[0..2], [0b0000_1011, 0b0111_0000]);
+    }
+
+    #[test]
+    fn glyph_outside_the_box() {
+        let font = FONT.replace("BBX 3 1 0 3", "BBX 3 1 1 3");
+        let err = Font::parse(&font).unwrap_err();
+        assert!(err.contains("glyph `C`") && err.contains("outside"), "{}", err);
+
+        let font = FONT.replace("BBX 3 1 0 3", "BBX 3 1 0 4");
+        assert!(Font::parse(&font).is_err());
+    }
+
+    #[test]
+    fn bad_fonts() {
+        assert!(Font::parse("STARTFONT 2.1\nENDFONT\n").is_err());
+        assert!(Font::parse(&FONT.replace("ENCODING 66", "ENCODING 65")).is_err());
+        assert!(Font::parse(&FONT.replace("A0\nE0", "A0\nZ0")).is_err());
+        assert!(Font::parse(&FONT.replace("FONTBOUNDINGBOX 3 5 0 -1", "FONTBOUNDINGBOX 3 5")).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Black and white images, read from PBM or PNG files and packed like the screen.
+
+use png::{ColorType, Decoder, Transformations};
+
+/// Tallest sprite `sprite::Sprite` takes.
+pub const MAX_HEIGHT: usize = 64;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, PartialEq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    /// `true` for a dark pixel, row by row.
+    pub pixels: Vec<bool>,
+    /// `false` for a transparent pixel, if there are any.
+    pub mask: Option<Vec<bool>>,
+}
+
+impl Bitmap {
+    /// Reads a PBM or PNG image, told apart by their first bytes.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.starts_with(PNG_SIGNATURE) {
+            Self::from_png(data)
+        } else {
+            parse_pbm(data)
+        }
+    }
+
+    /// Black and white pixels are fine, and fully transparent ones go to the mask.
+    fn from_png(data: &[u8]) -> Result<Self, String> {
+        let mut decoder = Decoder::new(data);
+        // 8 bits a channel, with the palette and `tRNS` turned into colours and alpha
+        decoder.set_transformations(Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|err| err.to_string())?;
+        let (width, height) = (info.width as usize, info.height as usize);
+
+        let mut pixels = Vec::with_capacity(width * height);
+        let mut mask = Vec::with_capacity(width * height);
+        for (y, row) in buffer.chunks(info.line_size).take(height).enumerate() {
+            for x in 0..width {
+                let [r, g, b, a] = match info.color_type {
+                    ColorType::Grayscale => [row[x], row[x], row[x], 255],
+                    ColorType::GrayscaleAlpha => [row[2 * x], row[2 * x], row[2 * x], row[2 * x + 1]],
+                    ColorType::Rgb => [row[3 * x], row[3 * x + 1], row[3 * x + 2], 255],
+                    ColorType::Rgba => [row[4 * x], row[4 * x + 1], row[4 * x + 2], row[4 * x + 3]],
+                    // The transformations expand palettes
+                    ColorType::Indexed => unreachable!(),
+                };
+                match (r, g, b, a) {
+                    (_, _, _, 0) => {
+                        pixels.push(false);
+                        mask.push(false);
+                    },
+                    (0, 0, 0, 255) | (255, 255, 255, 255) => {
+                        pixels.push(r == 0);
+                        mask.push(true);
+                    },
+                    _ => return Err(format!(
+                        "pixel {},{} is #{:02x}{:02x}{:02x} with alpha {}, only black, white and transparent pixels can be used",
+                        x, y, r, g, b, a)),
+                }
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            mask: mask.contains(&false).then_some(mask),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Checks the image fits in a sprite.
+    pub fn check_sprite(&self) -> Result<(), String> {
+        if self.height > MAX_HEIGHT {
+            return Err(format!("{} pixels high, sprites can be at most {}", self.height, MAX_HEIGHT));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a plain (`P1`) or binary (`P4`) PBM image.
+pub fn parse_pbm(data: &[u8]) -> Result<Bitmap, String> {
+    let mut pos = 0;
+    // Header fields are separated by whitespace, with `#` comments up to the end of the line
+    let mut field = || -> Result<String, String> {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|byte| *byte != b'\n') {
+                        pos += 1;
+                    }
+                },
+                Some(byte) if byte.is_ascii_whitespace() => pos += 1,
+                Some(_) => break,
+                None => return Err("the header ends early".into()),
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&data[start..pos]).into_owned())
+    };
+
+    let magic = field()?;
+    if magic != "P1" && magic != "P4" {
+        return Err("neither a PBM nor a PNG image".into());
+    }
+    let width: usize = field()?.parse().map_err(|_| "bad width")?;
+    let height: usize = field()?.parse().map_err(|_| "bad height")?;
+    if width == 0 || height == 0 {
+        return Err("empty image".into());
+    }
+
+    let pixels = if magic == "P1" {
+        let pixels: Vec<bool> = data[pos..].iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .map(|byte| match byte {
+                b'0' => Ok(false),
+                b'1' => Ok(true),
+                _ => Err(format!("bad pixel `{}`", *byte as char)),
+            })
+            .collect::<Result<_, _>>()?;
+        if pixels.len() != width * height {
+            return Err(format!("{} pixels instead of {}", pixels.len(), width * height));
+        }
+        pixels
+    } else {
+        // A single whitespace byte ends the header
+        let rows = data.get(pos + 1..).unwrap_or_default();
+        let row_bytes = width.div_ceil(8);
+        if rows.len() != row_bytes * height {
+            return Err(format!("{} bytes of pixels instead of {}", rows.len(), row_bytes * height));
+        }
+        (0..width * height)
+            .map(|i| rows[i / width * row_bytes + i % width / 8] & 0x80 >> (i % width % 8) != 0)
+            .collect()
+    };
+
+    Ok(Bitmap { width, height, pixels, mask: None })
+}
+
+/// Pages of 8 rows, a byte per column with the top row in the highest bit, like the screen.
+pub fn to_pages(width: usize, height: usize, pixels: &[bool]) -> Vec<u8> {
+    let mut pages = vec![0; width * height.div_ceil(8)];
+    for y in 0..height {
+        for x in 0..width {
+            if pixels[y * width + x] {
+                pages[y / 8 * width + x] |= 0x80 >> (y % 8);
+            }
+        }
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_pbm() {
+        let bitmap = parse_pbm(b"P1\n# comment\n3 2\n1 0 1\n0 1 0\n").unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (3, 2));
+        assert_eq!(bitmap.pixels, [true, false, true, false, true, false]);
+        assert_eq!(bitmap.mask, None);
+    }
+
+    #[test]
+    fn binary_pbm() {
+        let bitmap = parse_pbm(b"P4\n10 2\n\xc0\x40\x00\x80").unwrap();
+        assert!(bitmap.pixel(0, 0) && bitmap.pixel(1, 0) && bitmap.pixel(9, 0));
+        assert!(!bitmap.pixel(2, 0) && bitmap.pixel(8, 1));
+        assert_eq!(bitmap.pixels.iter().filter(|pixel| **pixel).count(), 4);
+    }
+
+    #[test]
+    fn bad_pbm() {
+        assert!(parse_pbm(b"P2\n1 1\n1\n").is_err());
+        assert!(parse_pbm(b"P1\n2 2\n1 0 1\n").is_err());
+        assert!(parse_pbm(b"P1\n1 1\n2\n").is_err());
+        assert!(parse_pbm(b"P4\n8 2\n\xff").is_err());
+        assert!(parse_pbm(b"P1\n0 1\n").is_err());
+    }
+
+    /// An 8-bit PNG of one row.
+    fn encode(color: ColorType, width: u32, row: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, width, 1);
+        encoder.set_color(color);
+        encoder.write_header().unwrap().write_image_data(row).unwrap();
+        data
+    }
+
+    #[test]
+    fn png_with_transparency() {
+        // Gray and alpha: white, black, transparent
+        let bitmap = Bitmap::parse(&encode(ColorType::GrayscaleAlpha, 3, &[255, 255, 0, 255, 0, 0])).unwrap();
+        assert_eq!(bitmap.pixels, [false, true, false]);
+        assert_eq!(bitmap.mask, Some(vec![true, true, false]));
+    }
+
+    #[test]
+    fn png_rgb() {
+        let bitmap = Bitmap::parse(&encode(ColorType::Rgb, 2, &[0, 0, 0, 255, 255, 255])).unwrap();
+        assert_eq!(bitmap.pixels, [true, false]);
+        assert_eq!(bitmap.mask, None);
+    }
+
+    #[test]
+    fn png_palette() {
+        // Black, and a transparent white from `tRNS`
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 1);
+        encoder.set_color(ColorType::Indexed);
+        encoder.set_palette(vec![0, 0, 0, 255, 255, 255]);
+        encoder.set_trns(vec![255, 0]);
+        encoder.write_header().unwrap().write_image_data(&[0, 1]).unwrap();
+        let bitmap = Bitmap::parse(&data).unwrap();
+        assert_eq!(bitmap.pixels, [true, false]);
+        assert_eq!(bitmap.mask, Some(vec![true, false]));
+    }
+
+    #[test]
+    fn png_gray() {
+        let err = Bitmap::parse(&encode(ColorType::Grayscale, 2, &[0, 128])).unwrap_err();
+        assert!(err.starts_with("pixel 1,0 is #808080"), "{}", err);
+    }
+
+    #[test]
+    fn bad_png() {
+        let data = encode(ColorType::Grayscale, 2, &[0, 255]);
+        assert!(Bitmap::parse(&data[..data.len() - 20]).is_err());
+    }
+
+    #[test]
+    fn too_tall() {
+        let bitmap = parse_pbm(format!("P1 1 65 {}", "1 ".repeat(65)).as_bytes()).unwrap();
+        assert!(bitmap.check_sprite().is_err());
+    }
+
+    #[test]
+    fn pages() {
+        // A 2x10 image: a dark top row, and the left pixel of the last one
+        let mut pixels = vec![false; 20];
+        pixels[0] = true;
+        pixels[1] = true;
+        pixels[18] = true;
+        assert_eq!(to_pages(2, 10, &pixels), [0x80, 0x80, 0x40, 0x00]);
+    }
+}
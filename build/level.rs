@@ -0,0 +1,111 @@
+//! Level maps drawn as text, packed into nibbles.
+//!
+//! Each line is a row and each character a cell: `.` for an empty one, or
+//! `1` to `9` and `A` to `F` for the kinds a game gives them. Lines starting
+//! with `#` are comments, blank ones are skipped.
+
+#[derive(Debug)]
+pub struct Level {
+    pub width: usize,
+    pub height: usize,
+    /// Row by row, 0 for empty.
+    pub cells: Vec<u8>,
+}
+
+impl Level {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut width = None;
+        let mut cells = Vec::new();
+        let mut height = 0;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let row: Vec<u8> = line.chars().enumerate()
+                .map(|(column, c)| match c {
+                    '.' => Ok(0),
+                    '1'..='9' | 'A'..='F' => Ok(c.to_digit(16).unwrap() as u8),
+                    _ => Err(format!("line {}, column {}: `{}` is not a cell", number + 1, column + 1, c)),
+                })
+                .collect::<Result<_, _>>()?;
+            match width {
+                None => width = Some(row.len()),
+                Some(width) if width != row.len() => {
+                    return Err(format!("line {}: {} cells wide instead of {} like the first row",
+                        number + 1, row.len(), width));
+                },
+                _ => {},
+            }
+            cells.extend(row);
+            height += 1;
+        }
+
+        let width = width.ok_or("no rows")?;
+        Ok(Self { width, height, cells })
+    }
+
+    /// Two cells a byte, the first in the high nibble.
+    pub fn packed(&self) -> Vec<u8> {
+        self.cells.chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let level = Level::parse("# bricks\n1.2\n\nF.A\n").unwrap();
+        assert_eq!((level.width, level.height), (3, 2));
+        assert_eq!(level.cells, [1, 0, 2, 15, 0, 10]);
+        assert_eq!(level.packed(), [0x10, 0x2f, 0x0a]);
+    }
+
+    #[test]
+    fn odd_cells() {
+        let level = Level::parse("12\n3.\n4.\n").unwrap();
+        assert_eq!(level.packed(), [0x12, 0x30, 0x40]);
+        // The last byte is padded with an empty cell
+        let level = Level::parse("123\n").unwrap();
+        assert_eq!(level.packed(), [0x12, 0x30]);
+    }
+
+    #[test]
+    fn comments_and_trailing_spaces() {
+        let level = Level::parse("#\n  \n1.  \n# 123\n.1\r\n").unwrap();
+        assert_eq!((level.width, level.height), (2, 2));
+        assert_eq!(level.cells, [1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn bad_characters() {
+        for (text, expected) in [
+            ("1.x\n", "line 1, column 3: `x` is not a cell"),
+            ("11\n0.\n", "line 2, column 1: `0` is not a cell"),
+            ("# a\n\n.a\n", "line 3, column 2: `a` is not a cell"),
+            ("1 1\n", "line 1, column 2: ` ` is not a cell"),
+            ("\t1\n", "line 1, column 1: `\t` is not a cell"),
+        ] {
+            assert_eq!(Level::parse(text).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn ragged_rows() {
+        assert_eq!(Level::parse("11\n1\n").unwrap_err(), "line 2: 1 cells wide instead of 2 like the first row");
+        assert_eq!(Level::parse("1\n# wider\n111\n").unwrap_err(), "line 3: 3 cells wide instead of 1 like the first row");
+        assert!(Level::parse("111\n111\n1111\n").is_err());
+    }
+
+    #[test]
+    fn no_rows() {
+        assert_eq!(Level::parse("").unwrap_err(), "no rows");
+        assert_eq!(Level::parse("# nothing\n\n").unwrap_err(), "no rows");
+    }
+}
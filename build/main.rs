@@ -0,0 +1,189 @@
+//! This build script copies the `memory.x` file from the crate root into
+//! a directory where the linker can always find it at build time.
+//! For many projects this is optional, as the linker always searches the
+//! project root directory -- wherever `Cargo.toml` is. However, if you
+//! are using a workspace or have a more complicated build setup, this
+//! build script becomes required. Additionally, by requesting that
+//! Cargo re-run the build script whenever `memory.x` is changed,
+//! updating `memory.x` ensures a rebuild of the application with the
+//! new memory settings.
+//!
+//! It passes the git commit and the build date on to the about page, as
+//! the `GIT_HASH` and `BUILD_DATE` environment variables. `SOURCE_DATE_EPOCH`
+//! overrides the date, for builds that have to come out the same.
+//!
+//! It also converts the assets into Rust, for `src/assets.rs` to include:
+//!
+//! - `assets/sprites/name.pbm` or `name.png` becomes a `sprite::Sprite`
+//!   called `NAME`. PNG images can only use black, white and transparent
+//!   pixels, the transparent ones make the mask. Otherwise
+//!   `name.mask.pbm` or `name.mask.png` is the mask, if there is one.
+//! - `assets/fonts/name.bdf` becomes an `embedded_graphics` `MonoFont`,
+//!   in `assets::fonts`.
+//! - `assets/levels/name.txt` becomes a `level::Level`, in `assets::levels`.
+//!
+//! An asset that can't be converted fails the build, naming the file and
+//! what is wrong with it.
+
+mod bdf;
+mod image;
+mod level;
+mod version;
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bdf::Font;
+use image::{Bitmap, to_pages};
+use level::Level;
+
+const SPRITES_DIR: &str = "assets/sprites";
+const FONTS_DIR: &str = "assets/fonts";
+const LEVELS_DIR: &str = "assets/levels";
+
+fn main() {
+    // Put `memory.x` in our output directory and ensure it's
+    // on the linker search path.
+    let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    File::create(out.join("memory.x"))
+        .unwrap()
+        .write_all(include_bytes!("../memory.x"))
+        .unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+
+    // By default, Cargo will re-run a build script whenever
+    // any file in the project changes. By specifying `memory.x`
+    // here, we ensure the build script is only re-run when
+    // `memory.x` is changed.
+    println!("cargo:rerun-if-changed=memory.x");
+
+    println!("cargo:rustc-env=GIT_HASH={}", version::git_hash());
+    for file in version::git_files(Path::new(".git")) {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+    let built = env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+    println!("cargo:rustc-env=BUILD_DATE={}", version::date(built));
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    fs::write(out.join("sprites.rs"), sprites(Path::new(SPRITES_DIR))).unwrap();
+    fs::write(out.join("fonts.rs"), fonts(Path::new(FONTS_DIR))).unwrap();
+    fs::write(out.join("levels.rs"), levels(Path::new(LEVELS_DIR))).unwrap();
+    for dir in [SPRITES_DIR, FONTS_DIR, LEVELS_DIR] {
+        println!("cargo:rerun-if-changed={}", dir);
+    }
+}
+
+/// Files in `dir` ending in `.ext`, sorted for the same output on every
+/// machine. A missing directory has none.
+fn files(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == ext))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn read(path: &Path) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|err| panic!("can't read {}: {}", path.display(), err))
+}
+
+/// Name of the constant made from `path`.
+fn const_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    stem.to_uppercase().replace(['-', ' ', '.'], "_")
+}
+
+fn read_bitmap(path: &Path) -> Bitmap {
+    Bitmap::parse(&read(path)).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+fn sprites(dir: &Path) -> String {
+    let mut paths = files(dir, "pbm");
+    paths.extend(files(dir, "png"));
+    paths.retain(|path| !path.file_stem().unwrap().to_string_lossy().ends_with(".mask"));
+    paths.sort();
+
+    let mut code = String::new();
+    for (i, path) in paths.iter().enumerate() {
+        if let Some(other) = paths[..i].iter().find(|other| const_name(other) == const_name(path)) {
+            panic!("{}: {} is called {} too", path.display(), other.display(), const_name(path));
+        }
+        let image = read_bitmap(path);
+        image.check_sprite().unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        let mask_path = ["pbm", "png"].iter()
+            .map(|ext| path.with_file_name(format!("{}.mask.{}", stem, ext)))
+            .find(|path| path.exists());
+        let mask = match (&image.mask, mask_path) {
+            (Some(_), Some(mask_path)) => {
+                panic!("{}: has transparent pixels and {} too", path.display(), mask_path.display());
+            },
+            (Some(mask), None) => Some(mask.clone()),
+            (None, Some(mask_path)) => {
+                let mask = read_bitmap(&mask_path);
+                if (mask.width, mask.height) != (image.width, image.height) {
+                    panic!("{}: {}x{} instead of {}x{} like the image", mask_path.display(),
+                        mask.width, mask.height, image.width, image.height);
+                }
+                if mask.mask.is_some() {
+                    panic!("{}: masks can't have transparent pixels", mask_path.display());
+                }
+                Some(mask.pixels)
+            },
+            (None, None) => None,
+        };
+        let mask = mask.map(|mask| format!("Some(&{:?})", to_pages(image.width, image.height, &mask)));
+
+        writeln!(code, "pub const {}: crate::sprite::Sprite = crate::sprite::Sprite::new({}, {}, &{:?}, {});",
+            const_name(path), image.width, image.height, to_pages(image.width, image.height, &image.pixels),
+            mask.as_deref().unwrap_or("None")).unwrap();
+    }
+    code
+}
+
+fn fonts(dir: &Path) -> String {
+    let mut code = String::new();
+    for path in files(dir, "bdf") {
+        let text = String::from_utf8(read(&path))
+            .unwrap_or_else(|_| panic!("{}: not UTF-8 text", path.display()));
+        let font = Font::parse(&text).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        let (image, width) = font.image();
+
+        writeln!(code, "pub const {}: embedded_graphics::mono_font::MonoFont = embedded_graphics::mono_font::MonoFont {{", const_name(&path)).unwrap();
+        writeln!(code, "    image: embedded_graphics::image::ImageRaw::new_binary(&{:?}, {}),", image, width).unwrap();
+        writeln!(code, "    glyph_mapping: &embedded_graphics::mono_font::mapping::StrGlyphMapping::new({:?}, {}),",
+            font.mapping(), font.replacement()).unwrap();
+        writeln!(code, "    character_size: embedded_graphics::geometry::Size::new({}, {}),", font.width, font.height).unwrap();
+        writeln!(code, "    character_spacing: 0,").unwrap();
+        writeln!(code, "    baseline: {},", font.baseline).unwrap();
+        writeln!(code, "    underline: embedded_graphics::mono_font::DecorationDimensions::new({}, 1),",
+            (font.baseline + 2).min(font.height - 1)).unwrap();
+        writeln!(code, "    strikethrough: embedded_graphics::mono_font::DecorationDimensions::new({}, 1),", font.height / 2).unwrap();
+        writeln!(code, "}};").unwrap();
+    }
+    code
+}
+
+fn levels(dir: &Path) -> String {
+    let mut code = String::new();
+    for path in files(dir, "txt") {
+        let text = String::from_utf8(read(&path))
+            .unwrap_or_else(|_| panic!("{}: not UTF-8 text", path.display()));
+        let level = Level::parse(&text).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        writeln!(code, "pub const {}: crate::level::Level = crate::level::Level::new({}, {}, &{:?});",
+            const_name(&path), level.width, level.height, level.packed()).unwrap();
+    }
+    code
+}
+
@@ -0,0 +1,67 @@
+//! Where the firmware came from, for the about page: the git commit and the build date.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Abbreviated hash of the commit checked out, `unknown` outside a git repository.
+pub fn git_hash() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short=8", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+/// Files that change with the commit checked out, for `cargo:rerun-if-changed`.
+pub fn git_files(git_dir: &Path) -> Vec<String> {
+    let head = git_dir.join("HEAD");
+    let mut files = vec![head.clone(), git_dir.join("packed-refs")];
+    // On a branch, its ref moves with every commit
+    if let Ok(text) = std::fs::read_to_string(&head) {
+        if let Some(branch) = text.trim().strip_prefix("ref: ") {
+            files.push(git_dir.join(branch));
+        }
+    }
+    // Missing ones would make cargo run the build script every time
+    files.iter().filter(|file| file.exists()).map(|file| file.display().to_string()).collect()
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC.
+pub fn date(unix_secs: u64) -> String {
+    let (year, month, day) = civil_from_days((unix_secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Year, month and day of the day `days` after 1970-01-01, see
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day comes last
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(951_868_800), "2000-03-01");
+        assert_eq!(date(1_704_067_199), "2023-12-31");
+        assert_eq!(date(1_792_368_000), "2026-10-19");
+    }
+}
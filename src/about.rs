@@ -0,0 +1,57 @@
+//! "O programie" page: which firmware this is, and on which board.
+//!
+//! The version comes from `Cargo.toml`, the commit and the build date from
+//! `build.rs`. Uptime counts on while the page is open.
+
+use core::fmt::Write;
+
+use crate::console::Console;
+use crate::display::Display;
+use crate::inputs::{self, Input};
+use crate::lang;
+use crate::power::IdleTimer;
+use crate::tasks;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_HASH: &str = env!("GIT_HASH");
+/// `YYYY-MM-DD`, in UTC.
+pub const BUILD_DATE: &str = env!("BUILD_DATE");
+
+/// Fills `console` with the page, `serial` being the unique ID of the board if there is one.
+pub fn render(console: &mut Console, serial: Option<u64>, uptime_ms: u64) {
+    let strings = lang::strings();
+    let seconds = uptime_ms / 1000;
+    console.clear();
+    writeln!(console, "{}", strings.about).ok();
+    writeln!(console, "v{} {}", VERSION, GIT_HASH).ok();
+    writeln!(console, "{} {}", strings.built, BUILD_DATE).ok();
+    writeln!(console, "{}", strings.serial).ok();
+    match serial {
+        Some(serial) => writeln!(console, "{:016X}", serial).ok(),
+        None => writeln!(console, "-").ok(),
+    };
+    write!(console, "{} {}:{:02}:{:02}", strings.uptime, seconds / 3600, seconds / 60 % 60, seconds % 60).ok();
+}
+
+pub struct AboutPage;
+
+impl AboutPage {
+    /// Shows the page until a button is pressed.
+    pub async fn run<D: Display, I: Input>(pcd: &mut D, inputs: &mut I, serial: Option<u64>) {
+        let mut console = Console::new();
+        let mut idle_timer = IdleTimer::new();
+        loop {
+            let now_ms = tasks::now_ms();
+            render(&mut console, serial, now_ms);
+            console.draw(pcd);
+
+            // Again once the uptime gets to the next second
+            let timeout_ms = (1000 - now_ms % 1000) as u32;
+            inputs::next_press(inputs, Some(timeout_ms.min(idle_timer.remaining_ms()))).await;
+            idle_timer.update(pcd, inputs).await;
+            if inputs.any_pressed() {
+                return;
+            }
+        }
+    }
+}
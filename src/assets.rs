@@ -0,0 +1,13 @@
+//! Sprites, fonts and levels from `assets`, converted by `build.rs`.
+
+include!(concat!(env!("OUT_DIR"), "/sprites.rs"));
+
+/// `MonoFont`s from `assets/fonts`.
+pub mod fonts {
+    include!(concat!(env!("OUT_DIR"), "/fonts.rs"));
+}
+
+/// Levels from `assets/levels`.
+pub mod levels {
+    include!(concat!(env!("OUT_DIR"), "/levels.rs"));
+}
@@ -0,0 +1,63 @@
+use super::sequencer::Tone;
+
+const MENU_MOVE: [Tone; 1] = [
+    Tone::new(1760, 20),
+];
+
+const CONFIRM: [Tone; 2] = [
+    Tone::new(1319, 40),
+    Tone::new(1760, 60),
+];
+
+const APPLE: [Tone; 3] = [
+    Tone::new(1047, 30),
+    Tone::new(1568, 30),
+    Tone::new(2093, 50),
+];
+
+const BOUNCE: [Tone; 1] = [
+    Tone::new(880, 15),
+];
+
+const BRICK: [Tone; 2] = [
+    Tone::new(1568, 15),
+    Tone::new(1175, 25),
+];
+
+const POWER_UP: [Tone; 4] = [
+    Tone::new(784, 30),
+    Tone::new(1047, 30),
+    Tone::new(1319, 30),
+    Tone::new(1568, 60),
+];
+
+const LINES: [Tone; 3] = [
+    Tone::new(1319, 30),
+    Tone::new(1047, 30),
+    Tone::new(1568, 60),
+];
+
+#[derive(Clone, Copy)]
+pub enum Effect {
+    MenuMove,
+    Confirm,
+    Apple,
+    Bounce,
+    Brick,
+    PowerUp,
+    Lines,
+}
+
+impl Effect {
+    pub fn tones(self) -> &'static [Tone] {
+        match self {
+            Effect::MenuMove => &MENU_MOVE,
+            Effect::Confirm  => &CONFIRM,
+            Effect::Apple    => &APPLE,
+            Effect::Bounce   => &BOUNCE,
+            Effect::Brick    => &BRICK,
+            Effect::PowerUp  => &POWER_UP,
+            Effect::Lines    => &LINES,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+//! Music in RTTTL, see `rtttl::parse`.
+
+pub const MENU_THEME: &str = "menu:d=8,o=5,b=160:c,e,g,c6,4p,b,g,e,4p,a,f,d,a,4p,g,e,c,2p,c,e,g,c6,4p,d6,b,g,4p,e6,c6,a,g,f,e,2d,2p";
+
+pub const GAME_OVER: &str = "gameover:d=4,o=5,b=160:8g,8p,8e,8p,8c,8p,2c4";
@@ -0,0 +1,103 @@
+//! Sound for the piezo buzzer. Games queue effects and music here, and
+//! whoever drives the buzzer calls `tick` every few milliseconds to learn
+//! what it should be playing, so the game loop never waits for it.
+
+pub mod effects;
+pub mod melodies;
+pub mod rtttl;
+pub mod sequencer;
+
+use core::cell::RefCell;
+
+use bare_metal::Mutex;
+
+use effects::Effect;
+use rtttl::RtttlError;
+use sequencer::Sequencer;
+
+pub const MAX_VOLUME: u8 = 3;
+
+/// What the buzzer should be doing, a `freq` of 0 is silence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Output {
+    pub freq:   u16,
+    pub volume: u8,
+}
+
+struct Player {
+    sequencer: Sequencer,
+    /// Time left of the current tone.
+    remaining: u16,
+    output: Output,
+    changed: bool,
+}
+
+static PLAYER: Mutex<RefCell<Player>> = Mutex::new(RefCell::new(Player {
+    sequencer: Sequencer::new(),
+    remaining: 0,
+    output: Output { freq: 0, volume: MAX_VOLUME },
+    changed: false,
+}));
+
+fn with_player<R>(f: impl FnOnce(&mut Player) -> R) -> R {
+    critical_section::with(|cs| f(&mut PLAYER.borrow(cs).borrow_mut()))
+}
+
+pub fn play(effect: Effect) {
+    with_player(|player| {
+        player.sequencer.play_effect(effect.tones());
+        // Cut the current tone short
+        player.remaining = 0;
+    });
+}
+
+/// Plays an RTTTL melody in the background, see `melodies`.
+pub fn play_melody(rtttl: &str, looped: bool) -> Result<(), RtttlError> {
+    let melody = rtttl::parse(rtttl)?;
+    with_player(|player| player.sequencer.play_music(&melody, looped));
+    Ok(())
+}
+
+pub fn stop_music() {
+    with_player(|player| player.sequencer.stop_music());
+}
+
+pub fn set_volume(volume: u8) {
+    with_player(|player| {
+        player.output.volume = volume.min(MAX_VOLUME);
+        player.changed = true;
+    });
+}
+
+pub fn volume() -> u8 {
+    with_player(|player| player.output.volume)
+}
+
+/// Moves the time on by `ms`, returns the new output if it has to change.
+pub fn tick(ms: u16) -> Option<Output> {
+    with_player(|player| {
+        player.remaining = player.remaining.saturating_sub(ms);
+        if player.remaining == 0 {
+            match player.sequencer.next_tone() {
+                Some(tone) => {
+                    player.output.freq = tone.freq;
+                    player.remaining = tone.ms;
+                    player.changed = true;
+                },
+                None => {
+                    if player.output.freq != 0 {
+                        player.output.freq = 0;
+                        player.changed = true;
+                    }
+                }
+            }
+        }
+
+        if player.changed {
+            player.changed = false;
+            Some(player.output)
+        } else {
+            None
+        }
+    })
+}
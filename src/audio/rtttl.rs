@@ -0,0 +1,274 @@
+//! Parser for RTTTL ringtones, `name:d=4,o=5,b=120:8c6,8p,4e.6,...`
+
+use heapless::Vec;
+
+use super::sequencer::Tone;
+
+pub const MAX_NOTES: usize = 64;
+
+pub type Melody = Vec<Tone, MAX_NOTES>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RtttlError {
+    /// Not three `:` separated sections.
+    MissingSection,
+    /// Unknown key or bad value in the defaults section.
+    BadDefault,
+    /// Note at the given index could not be parsed.
+    BadNote(usize),
+    /// More than `MAX_NOTES` notes.
+    TooLong,
+}
+
+/// Frequencies of the 8th octave, from C to B.
+const OCTAVE_8: [u16; 12] = [
+    4186, 4435, 4699, 4978, 5274, 5588, 5920, 6272, 6645, 7040, 7459, 7902,
+];
+
+const MIN_OCTAVE: u8 = 3;
+const MAX_OCTAVE: u8 = 8;
+
+struct Defaults {
+    duration: u8,
+    octave:   u8,
+    bpm:      u16,
+}
+
+fn is_duration(value: u16) -> bool {
+    matches!(value, 1 | 2 | 4 | 8 | 16 | 32)
+}
+
+/// Reads a decimal number from the front of `text`.
+fn number(text: &mut &[u8]) -> Option<u16> {
+    let len = text.iter().take_while(|c| c.is_ascii_digit()).count();
+    if len == 0 || len > 4 {
+        return None;
+    }
+
+    let value = text[..len].iter().fold(0, |acc, c| acc * 10 + (c - b'0') as u16);
+    *text = &text[len..];
+    Some(value)
+}
+
+fn parse_defaults(section: &str) -> Result<Defaults, RtttlError> {
+    let mut defaults = Defaults {
+        duration: 4,
+        octave:   6,
+        bpm:      63,
+    };
+
+    for entry in section.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (key, mut value) = match entry.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim().as_bytes()),
+            None => return Err(RtttlError::BadDefault),
+        };
+        let value = match number(&mut value) {
+            Some(number) if value.is_empty() => number,
+            _ => return Err(RtttlError::BadDefault),
+        };
+
+        match key {
+            "d" if is_duration(value) => defaults.duration = value as u8,
+            "o" if (MIN_OCTAVE as u16..=MAX_OCTAVE as u16).contains(&value) => defaults.octave = value as u8,
+            "b" if value > 0 => defaults.bpm = value,
+            _ => return Err(RtttlError::BadDefault),
+        }
+    }
+
+    Ok(defaults)
+}
+
+fn parse_note(note: &str, defaults: &Defaults) -> Option<Tone> {
+    let mut text = note.as_bytes();
+
+    let duration = match number(&mut text) {
+        Some(duration) if is_duration(duration) => duration,
+        Some(_) => return None,
+        None => defaults.duration as u16,
+    };
+
+    let semitone = match text.first()?.to_ascii_lowercase() {
+        b'c' => Some(0),
+        b'd' => Some(2),
+        b'e' => Some(4),
+        b'f' => Some(5),
+        b'g' => Some(7),
+        b'a' => Some(9),
+        b'b' | b'h' => Some(11),
+        b'p' => None,
+        _ => return None,
+    };
+    text = &text[1..];
+
+    let sharp = text.first() == Some(&b'#');
+    if sharp {
+        text = &text[1..];
+    }
+
+    // The dot is allowed both before and after the octave
+    let mut dotted = false;
+    if text.first() == Some(&b'.') {
+        dotted = true;
+        text = &text[1..];
+    }
+
+    let octave = match number(&mut text) {
+        Some(octave) if (MIN_OCTAVE as u16..=MAX_OCTAVE as u16).contains(&octave) => octave as u8,
+        Some(_) => return None,
+        None => defaults.octave,
+    };
+
+    if text.first() == Some(&b'.') {
+        dotted = true;
+        text = &text[1..];
+    }
+
+    if !text.is_empty() {
+        return None;
+    }
+
+    // A whole note lasts four beats
+    let mut ms = 4 * 60_000 / defaults.bpm as u32 / duration as u32;
+    if dotted {
+        ms += ms / 2;
+    }
+    let ms = ms.min(u16::MAX as u32) as u16;
+
+    Some(match semitone {
+        Some(semitone) => {
+            // B# is the C of the next octave, the top one has no next
+            let (semitone, octave) = match semitone + sharp as usize {
+                12 => (0, (octave + 1).min(MAX_OCTAVE)),
+                semitone => (semitone, octave),
+            };
+            Tone::new(OCTAVE_8[semitone] >> (MAX_OCTAVE - octave), ms)
+        },
+        None => Tone::rest(ms),
+    })
+}
+
+pub fn parse(rtttl: &str) -> Result<Melody, RtttlError> {
+    let mut sections = rtttl.splitn(3, ':');
+    let (_name, defaults, notes) = match (sections.next(), sections.next(), sections.next()) {
+        (Some(name), Some(defaults), Some(notes)) => (name, defaults, notes),
+        _ => return Err(RtttlError::MissingSection),
+    };
+
+    let defaults = parse_defaults(defaults)?;
+
+    let mut melody = Melody::new();
+    for (index, note) in notes.split(',').map(str::trim).enumerate() {
+        let tone = parse_note(note, &defaults).ok_or(RtttlError::BadNote(index))?;
+        melody.push(tone).map_err(|_| RtttlError::TooLong)?;
+    }
+
+    Ok(melody)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use crate::audio::melodies::{GAME_OVER, MENU_THEME};
+
+    fn tones(rtttl: &str) -> Vec<(u16, u16)> {
+        parse(rtttl).unwrap().iter().map(|tone| (tone.freq, tone.ms)).collect()
+    }
+
+    #[test]
+    fn missing_sections() {
+        assert_eq!(parse(""), Err(RtttlError::MissingSection));
+        assert_eq!(parse("name"), Err(RtttlError::MissingSection));
+        assert_eq!(parse("name:d=4,o=5,b=120"), Err(RtttlError::MissingSection));
+        assert_eq!(parse("name;d=4;o=5;b=120;c"), Err(RtttlError::MissingSection));
+    }
+
+    #[test]
+    fn bad_defaults() {
+        for defaults in ["d=3", "d=64", "d=", "o=2", "o=9", "b=0", "b=x", "b=12345", "x=1", "d", "d=4x", "d=4;o=5"] {
+            assert_eq!(parse(&["x:", defaults, ":c"].concat()), Err(RtttlError::BadDefault), "{}", defaults);
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        // Unless given, a quarter note of the 6th octave at 63 bpm
+        assert_eq!(tones("x::c"), [(1046, 952)]);
+        assert_eq!(tones("x: b=120 , o=4 ,d=8 :c"), [(261, 250)]);
+        assert_eq!(tones("x:d=1,o=8,b=60:c"), [(4186, 4000)]);
+    }
+
+    #[test]
+    fn notes() {
+        assert_eq!(
+            tones("x:d=4,o=5,b=120:c,c#,d,d#,e,f,f#,g,g#,a,a#,b,h,p"),
+            [
+                (523, 500), (554, 500), (587, 500), (622, 500), (659, 500), (698, 500), (740, 500),
+                (784, 500), (830, 500), (880, 500), (932, 500), (987, 500), (987, 500), (0, 500),
+            ]
+        );
+        // Durations, octaves, dots before and after the octave and upper case
+        assert_eq!(
+            tones("x:d=4,o=5,b=120:1c,2c,8c,16c,32c,c3,c8,c.,c.6,c6.,8p.,A,B#,b#8"),
+            [
+                (523, 2000), (523, 1000), (523, 250), (523, 125), (523, 62), (130, 500), (4186, 500),
+                (523, 750), (1046, 750), (1046, 750), (0, 375), (880, 500), (1046, 500), (4186, 500),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_notes() {
+        assert_eq!(parse("x::c,x,d"), Err(RtttlError::BadNote(1)));
+        assert_eq!(parse("x::i"), Err(RtttlError::BadNote(0)));
+        assert_eq!(parse("x::c,"), Err(RtttlError::BadNote(1)));
+        assert_eq!(parse("x::4"), Err(RtttlError::BadNote(0)));
+        assert_eq!(parse("x::c#b"), Err(RtttlError::BadNote(0)));
+        assert_eq!(parse("x::c5x"), Err(RtttlError::BadNote(0)));
+    }
+
+    #[test]
+    fn bad_durations_and_octaves() {
+        for note in ["3c", "64c", "0c", "12345c", "c2", "c9", "c10"] {
+            assert_eq!(parse(&["x::e,", note].concat()), Err(RtttlError::BadNote(1)), "{}", note);
+        }
+    }
+
+    #[test]
+    fn too_long() {
+        let mut rtttl = Vec::from(&b"x::c"[..]);
+        for _ in 1..MAX_NOTES {
+            rtttl.extend_from_slice(b",c");
+        }
+        let rtttl = core::str::from_utf8(&rtttl).unwrap();
+        assert_eq!(parse(rtttl).unwrap().len(), MAX_NOTES);
+        assert_eq!(parse(&[rtttl, ",c"].concat()), Err(RtttlError::TooLong));
+    }
+
+    #[test]
+    fn melodies() {
+        let (c, d, e, f, g, a, b) = (523, 587, 659, 698, 784, 880, 987);
+        let (c6, d6, e6) = (1046, 1174, 1318);
+        let (eighth, quarter, half) = (187, 375, 750);
+        let p = 0;
+        assert_eq!(
+            tones(MENU_THEME),
+            [
+                (c, eighth), (e, eighth), (g, eighth), (c6, eighth), (p, quarter),
+                (b, eighth), (g, eighth), (e, eighth), (p, quarter),
+                (a, eighth), (f, eighth), (d, eighth), (a, eighth), (p, quarter),
+                (g, eighth), (e, eighth), (c, eighth), (p, half),
+                (c, eighth), (e, eighth), (g, eighth), (c6, eighth), (p, quarter),
+                (d6, eighth), (b, eighth), (g, eighth), (p, quarter),
+                (e6, eighth), (c6, eighth), (a, eighth), (g, eighth), (f, eighth), (e, eighth),
+                (d, half), (p, half),
+            ]
+        );
+        assert_eq!(
+            tones(GAME_OVER),
+            [(g, eighth), (p, eighth), (e, eighth), (p, eighth), (c, eighth), (p, eighth), (261, half)]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use heapless::Vec;
+
+use super::rtttl::MAX_NOTES;
+
+const MAX_TONES: usize = MAX_NOTES;
+
+/// One note, a `freq` of 0 is a rest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    pub freq: u16,
+    pub ms:   u16,
+}
+
+impl Tone {
+    pub const fn new(freq: u16, ms: u16) -> Self {
+        Self { freq, ms }
+    }
+
+    pub const fn rest(ms: u16) -> Self {
+        Self { freq: 0, ms }
+    }
+}
+
+struct Track {
+    tones: Vec<Tone, MAX_TONES>,
+    index: usize,
+    looped: bool,
+}
+
+impl Track {
+    const fn new() -> Self {
+        Self {
+            tones: Vec::new(),
+            index: 0,
+            looped: false,
+        }
+    }
+
+    fn load(&mut self, tones: &[Tone], looped: bool) {
+        self.tones.clear();
+        // Longer sequences get cut short
+        let len = tones.len().min(MAX_TONES);
+        self.tones.extend_from_slice(&tones[..len]).unwrap();
+        self.index = 0;
+        self.looped = looped;
+    }
+
+    fn next(&mut self) -> Option<Tone> {
+        if self.index == self.tones.len() {
+            if !self.looped || self.tones.is_empty() {
+                return None;
+            }
+            self.index = 0;
+        }
+
+        let tone = self.tones[self.index];
+        self.index += 1;
+        Some(tone)
+    }
+}
+
+/// Decides which tone plays next, knows nothing about the hardware.
+///
+/// Sound effects take over from the music, which carries on once they are done.
+pub struct Sequencer {
+    music:  Track,
+    effect: Track,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequencer {
+    pub const fn new() -> Self {
+        Self {
+            music:  Track::new(),
+            effect: Track::new(),
+        }
+    }
+
+    pub fn play_effect(&mut self, tones: &[Tone]) {
+        self.effect.load(tones, false);
+    }
+
+    pub fn play_music(&mut self, tones: &[Tone], looped: bool) {
+        self.music.load(tones, looped);
+    }
+
+    pub fn stop_music(&mut self) {
+        self.music.load(&[], false);
+    }
+
+    /// Tone to play once the current one ends, `None` means silence.
+    pub fn next_tone(&mut self) -> Option<Tone> {
+        self.effect.next().or_else(|| self.music.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use crate::audio::{self, effects::Effect};
+
+    const TICK_MS: u16 = 5;
+
+    /// What the buzzer is told to play over `ms`, as `(freq, ms, volume)`,
+    /// the first one carrying on from before unless something changes right away.
+    fn listen(ms: u32) -> Vec<(u16, u32, u8)> {
+        let mut heard: Vec<(u16, u32, u8)> = Vec::new();
+        for _ in 0..ms / TICK_MS as u32 {
+            if let Some(output) = audio::tick(TICK_MS) {
+                heard.push((output.freq, 0, output.volume));
+            }
+            if let Some(last) = heard.last_mut() {
+                last.1 += TICK_MS as u32;
+            }
+        }
+        heard
+    }
+
+    #[test]
+    fn effects_over_music() {
+        let mut sequencer = Sequencer::new();
+        assert_eq!(sequencer.next_tone(), None);
+
+        sequencer.play_music(&[Tone::new(440, 100), Tone::rest(50)], true);
+        sequencer.play_effect(&[Tone::new(1000, 10)]);
+        let tones: Vec<Tone> = (0..5).map_while(|_| sequencer.next_tone()).collect();
+        assert_eq!(tones, [Tone::new(1000, 10), Tone::new(440, 100), Tone::rest(50), Tone::new(440, 100), Tone::rest(50)]);
+
+        sequencer.stop_music();
+        sequencer.play_effect(&[Tone::new(1000, 10), Tone::new(2000, 20)]);
+        assert_eq!(sequencer.next_tone(), Some(Tone::new(1000, 10)));
+        assert_eq!(sequencer.next_tone(), Some(Tone::new(2000, 20)));
+        assert_eq!(sequencer.next_tone(), None);
+    }
+
+    #[test]
+    fn long_sequences_are_cut() {
+        let mut sequencer = Sequencer::new();
+        sequencer.play_music(&[Tone::new(440, 1); MAX_TONES + 10], false);
+        assert_eq!((0..).map_while(|_| sequencer.next_tone()).count(), MAX_TONES);
+    }
+
+    // The only test of the shared player, others running alongside would get in the way
+    #[test]
+    fn ticks() {
+        audio::set_volume(audio::MAX_VOLUME);
+        let full = audio::MAX_VOLUME;
+
+        // An effect, then silence
+        audio::play(Effect::Apple);
+        assert_eq!(listen(200), [(1047, 30, full), (1568, 30, full), (2093, 50, full), (0, 90, full)]);
+
+        // A looping melody, cut short by an effect and carrying on with its next note
+        audio::play_melody("x:d=4,o=5,b=120:8c,8e,4p", true).unwrap();
+        assert_eq!(
+            listen(1200),
+            [(523, 250, full), (659, 250, full), (0, 500, full), (523, 200, full)]
+        );
+        audio::play(Effect::MenuMove);
+        assert_eq!(listen(500), [(1760, 20, full), (659, 250, full), (0, 230, full)]);
+
+        // Silenced, the tones go on the same
+        audio::set_volume(0);
+        assert_eq!(listen(300), [(0, 270, 0), (523, 30, 0)]);
+        assert_eq!(listen(300), [(659, 80, 0)]);
+        audio::set_volume(1);
+        assert_eq!(listen(10), [(659, 10, 1)]);
+
+        audio::stop_music();
+        // The note playing ends first
+        assert_eq!(listen(1000), [(0, 840, 1)]);
+        assert_eq!(listen(100), []);
+    }
+}
@@ -0,0 +1,17 @@
+//! Brightness of the screen backlight, picked in the menu and kept in the settings.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+pub const MAX_LEVEL: u8 = 3;
+
+static LEVEL: AtomicU8 = AtomicU8::new(MAX_LEVEL);
+
+/// From 0 for off to `MAX_LEVEL`.
+pub fn level() -> u8 {
+    LEVEL.load(Ordering::Relaxed)
+}
+
+/// The firmware fades to the new level, this only records it.
+pub fn set_level(level: u8) {
+    LEVEL.store(level.min(MAX_LEVEL), Ordering::Relaxed);
+}
@@ -0,0 +1,111 @@
+//! Battery charge, as measured by whoever can read the supply voltage.
+//!
+//! Thresholds are for a single Li-ion/LiPo cell on VSYS.
+
+use core::convert::Infallible;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embedded_graphics::Drawable;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive, Size};
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+pub const FULL_MV: u32 = 4200;
+pub const EMPTY_MV: u32 = 3300;
+/// Games start warning below this.
+pub const LOW_MV: u32 = 3500;
+/// Settings get saved and the console shuts down below this.
+pub const CRITICAL_MV: u32 = 3300;
+/// Width of the icon drawn by `draw_icon`, with its tip.
+pub const ICON_WIDTH: u32 = 12;
+pub const ICON_HEIGHT: u32 = 7;
+
+/// Filtered voltage in millivolts, 0 until the first measurement.
+static MILLIVOLTS: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    /// Never measured, like in the simulator.
+    Unknown,
+    Ok,
+    Low,
+    Critical,
+}
+
+/// Exponential moving average, smooths out the ADC noise and load spikes.
+#[derive(Default)]
+pub struct Filter {
+    /// Millivolts times `1 << SHIFT`, 0 before the first sample.
+    value: u32,
+}
+
+impl Filter {
+    /// Each sample moves the average by 1/2^SHIFT of the difference.
+    const SHIFT: u32 = 3;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sample, returns the average in millivolts.
+    pub fn push(&mut self, millivolts: u32) -> u32 {
+        let sample = millivolts << Self::SHIFT;
+        if self.value == 0 {
+            self.value = sample;
+        } else {
+            self.value = self.value - (self.value >> Self::SHIFT) + (sample >> Self::SHIFT);
+        }
+        self.value >> Self::SHIFT
+    }
+}
+
+pub fn set_millivolts(millivolts: u32) {
+    MILLIVOLTS.store(millivolts, Ordering::Relaxed);
+}
+
+pub fn millivolts() -> Option<u32> {
+    match MILLIVOLTS.load(Ordering::Relaxed) {
+        0 => None,
+        millivolts => Some(millivolts),
+    }
+}
+
+pub fn level() -> Level {
+    match millivolts() {
+        None => Level::Unknown,
+        Some(millivolts) if millivolts < CRITICAL_MV => Level::Critical,
+        Some(millivolts) if millivolts < LOW_MV => Level::Low,
+        Some(_) => Level::Ok,
+    }
+}
+
+/// Charge left between `EMPTY_MV` and `FULL_MV`, in percent.
+pub fn percent() -> Option<u32> {
+    millivolts().map(|millivolts| {
+        (millivolts.clamp(EMPTY_MV, FULL_MV) - EMPTY_MV) * 100 / (FULL_MV - EMPTY_MV)
+    })
+}
+
+/// Draws a battery filled up to the charge, nothing if it is unknown.
+/// Also onto a `Rotated` view of the screen.
+pub fn draw_icon<D: DrawTarget<Color = BinaryColor, Error = Infallible>>(pcd: &mut D, top_left: Point) {
+    let Some(percent) = percent() else {
+        return;
+    };
+
+    let body = Size::new(ICON_WIDTH - 2, ICON_HEIGHT);
+    Rectangle::new(top_left, body)
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(pcd).unwrap();
+    Rectangle::new(top_left + Point::new(body.width as i32, 2), Size::new(2, ICON_HEIGHT - 4))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(pcd).unwrap();
+
+    // Rounded up, so it only looks empty when it is
+    let inside = body.width - 4;
+    let filled = (inside * percent).div_ceil(100);
+    Rectangle::new(top_left + Point::new(2, 2), Size::new(filled, ICON_HEIGHT - 4))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(pcd).unwrap();
+}
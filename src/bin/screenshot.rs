@@ -0,0 +1,71 @@
+//! Takes a screenshot of the console over its USB serial port.
+//!
+//! ```text
+//! screenshot <port> [file]
+//! ```
+//!
+//! Asks the console on `port` (usually `/dev/ttyACM0`) for a screenshot and
+//! saves it as a PBM image, `screenshot.pbm` unless `file` is given. With `-`
+//! as the port the frames are read from the standard input instead, nothing
+//! is asked for. The wire format is described in `rp2040_game::screenshot`.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::process::{self, Command};
+
+use rp2040_game::screenshot::{self, Receiver};
+
+/// Debug console command asking for a screenshot.
+const REQUEST: &[u8] = b"screenshot\r";
+
+fn receive(port: &mut impl Read) -> io::Result<[u8; screenshot::PBM_SIZE]> {
+    let mut receiver = Receiver::new();
+    let mut chunk = [0; 64];
+    loop {
+        let count = port.read(&mut chunk)?;
+        if count == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no screenshot received"));
+        }
+
+        for &byte in &chunk[..count] {
+            if let Some(frame) = receiver.push(byte) {
+                return Ok(screenshot::encode(&frame));
+            }
+        }
+    }
+}
+
+fn take(port: &str) -> io::Result<[u8; screenshot::PBM_SIZE]> {
+    if port == "-" {
+        return receive(&mut io::stdin().lock());
+    }
+
+    // The tty layer must not touch the bytes of the image
+    let stty = Command::new("stty").args(["-F", port, "raw", "-echo"]).status()?;
+    if !stty.success() {
+        return Err(io::Error::other(format!("stty failed on {}", port)));
+    }
+
+    let mut serial = OpenOptions::new().read(true).write(true).open(port)?;
+    serial.write_all(REQUEST)?;
+    receive(&mut serial)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("usage: {} <port> [file]", args[0]);
+        process::exit(2);
+    }
+    let file = args.get(2).map_or("screenshot.pbm", String::as_str);
+
+    let pbm = take(&args[1]).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        process::exit(1);
+    });
+    fs::write(file, pbm).unwrap_or_else(|err| {
+        eprintln!("{}: {}", file, err);
+        process::exit(1);
+    });
+    println!("{}", file);
+}
@@ -0,0 +1,283 @@
+//! Runs the menus and games on a PC, with the screen and buttons simulated.
+//!
+//! ```text
+//! sim <script> <output directory>
+//! ```
+//!
+//! The script has one command per line, `#` starts a comment:
+//!
+//! ```text
+//! seed 1234       use a fixed seed for the games
+//! lang en         show the labels in English, or Polish with `pl`
+//! wait 500        let 500 ms pass
+//! press down      press and release a button: select, down, up, right or 0-3
+//! ```
+//!
+//! Every new frame sent to the screen is saved in the output directory as
+//! `frame_NNNNN.pbm`, and every change of the buzzer goes to `audio.txt` as
+//! `<ms> <frequency> <volume>`. The session ends shortly after the script does.
+
+use std::cell::{Cell, RefCell};
+use std::fmt::Write as _;
+use std::fs;
+use std::future::{poll_fn, Future};
+use std::path::{Path, PathBuf};
+use std::pin::{pin, Pin};
+use std::process;
+use std::task::Poll;
+
+use embedded_graphics::{prelude::{OriginDimensions, Size}, pixelcolor::BinaryColor, draw_target::DrawTarget, Pixel};
+use rp2040_game::audio;
+use rp2040_game::display::{draw_pixels, Display, FrameBuffer, BUFFER_SIZE, HEIGHT, WIDTH};
+use rp2040_game::inputs::{self, Buttons};
+use rp2040_game::lang::{self, Language};
+use rp2040_game::main_menu::{MainMenu, System};
+use rp2040_game::power;
+use rp2040_game::rand;
+use rp2040_game::screenshot;
+use rp2040_game::shell;
+use rp2040_game::tasks::{self, Platform, Ticker, Timer};
+
+/// How long the session runs on after the last scripted press.
+const TAIL_MS: u64 = 2_000;
+/// Resolution of the audio timeline.
+const AUDIO_TICK_MS: u16 = 5;
+
+struct SingleThreaded;
+critical_section::custom_impl!(SingleThreaded);
+
+// The simulator never leaves its one thread, there is nothing to lock
+unsafe impl critical_section::Impl for SingleThreaded {
+    unsafe fn acquire() -> u8 {
+        0
+    }
+
+    unsafe fn release(_token: u8) {}
+}
+
+pub struct Script {
+    seed: Option<u32>,
+    language: Language,
+    /// Time of each press and the button pressed.
+    presses: Vec<(u64, usize)>,
+    end: u64,
+}
+
+pub fn parse_script(text: &str) -> Result<Script, String> {
+    let mut script = Script {
+        seed: None,
+        language: Language::Polish,
+        presses: Vec::new(),
+        end: 0,
+    };
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = || format!("line {}: bad command `{}`", number + 1, line);
+        let (command, argument) = line.split_once(' ').ok_or_else(error)?;
+        let argument = argument.trim();
+        match command {
+            "seed" => script.seed = Some(argument.parse().map_err(|_| error())?),
+            "lang" => script.language = match argument {
+                "pl" => Language::Polish,
+                "en" => Language::English,
+                _ => return Err(error()),
+            },
+            "wait" => script.end += argument.parse::<u64>().map_err(|_| error())?,
+            "press" => {
+                let button = shell::button(argument).ok_or_else(error)?;
+                script.presses.push((script.end, button));
+            },
+            _ => return Err(error()),
+        }
+    }
+
+    Ok(script)
+}
+
+/// Simulated time, jumping straight to the next timer whenever the tasks wait.
+struct SimClock {
+    now_ms: u64,
+}
+
+impl Platform for SimClock {
+    fn now_ms(&mut self) -> u64 {
+        self.now_ms
+    }
+
+    fn idle(&mut self, until_ms: Option<u64>) {
+        // The end of the session is always ahead
+        self.now_ms = self.now_ms.max(until_ms.expect("nothing left to wait for"));
+    }
+}
+
+/// Where the results go.
+struct Session {
+    audio_log: RefCell<String>,
+    out_dir: PathBuf,
+    finished: Cell<bool>,
+}
+
+impl Session {
+    fn finish(&self) {
+        fs::write(self.out_dir.join("audio.txt"), self.audio_log.borrow().as_bytes())
+            .expect("can't write audio.txt");
+        self.finished.set(true);
+    }
+
+    /// Runs `task` until the session is finished.
+    async fn until_finished(&self, task: impl Future<Output = ()>) {
+        let mut task = pin!(task);
+        poll_fn(|cx| {
+            if self.finished.get() {
+                return Poll::Ready(());
+            }
+            task.as_mut().poll(cx)
+        }).await
+    }
+}
+
+/// Presses the buttons when the script says so.
+async fn press_buttons(presses: Vec<(u64, usize)>) {
+    for (time, button) in presses {
+        Timer::at_ms(time).await;
+        inputs::press(button);
+    }
+}
+
+/// Stands in for the buzzer, writing every change to the audio log.
+async fn play_audio(session: &Session) {
+    let mut ticker = Ticker::every(AUDIO_TICK_MS as u32);
+    loop {
+        ticker.tick().await;
+        if power::is_asleep() {
+            tasks::until(|| (!power::is_asleep()).then_some(())).await;
+        }
+
+        if let Some(output) = audio::tick(AUDIO_TICK_MS) {
+            writeln!(session.audio_log.borrow_mut(), "{} {} {}", tasks::now_ms(), output.freq, output.volume).unwrap();
+        }
+    }
+}
+
+/// In-memory screen saving every frame it is asked to draw.
+struct SimDisplay {
+    buffer: FrameBuffer,
+    last: Option<FrameBuffer>,
+    frames: usize,
+    out_dir: PathBuf,
+}
+
+impl Display for SimDisplay {
+    fn buffer(&self) -> &FrameBuffer {
+        &self.buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.buffer
+    }
+
+    fn draw(&mut self) {
+        // Menus redraw the same screen a lot, keep only the changes
+        if self.last == Some(self.buffer) {
+            return;
+        }
+
+        let path = self.out_dir.join(format!("frame_{:05}.pbm", self.frames));
+        fs::write(&path, screenshot::encode(&self.buffer)).expect("can't write frame");
+        self.last = Some(self.buffer);
+        self.frames += 1;
+    }
+}
+
+impl OriginDimensions for SimDisplay {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for SimDisplay {
+    type Color = BinaryColor;
+
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        draw_pixels(&mut self.buffer, pixels);
+        Ok(())
+    }
+}
+
+/// Plays `script`, saving the frames and the audio log in `out_dir`.
+pub fn run_script(script: Script, out_dir: &Path) {
+    fs::create_dir_all(out_dir).expect("can't create the output directory");
+
+    rand::set_fixed_seed(script.seed);
+    lang::set_language(script.language);
+
+    let session = Session {
+        audio_log: RefCell::default(),
+        out_dir: out_dir.to_path_buf(),
+        finished: Cell::new(false),
+    };
+    let mut display = SimDisplay {
+        buffer: [0; BUFFER_SIZE],
+        last: None,
+        frames: 0,
+        out_dir: out_dir.to_path_buf(),
+    };
+
+    let end_ms = script.end + TAIL_MS;
+    let game = pin!(session.until_finished(play(&mut display, &session)));
+    let buttons = pin!(session.until_finished(press_buttons(script.presses)));
+    let audio = pin!(session.until_finished(play_audio(&session)));
+    let end = pin!(session.until_finished(async {
+        Timer::at_ms(end_ms).await;
+        session.finish()
+    }));
+    let mut all: [Pin<&mut dyn Future<Output = ()>>; 4] = [game, buttons, audio, end];
+    tasks::run(&mut SimClock { now_ms: 0 }, &mut all);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <script> <output directory>", args[0]);
+        process::exit(2);
+    }
+
+    let text = fs::read_to_string(&args[1]).unwrap_or_else(|err| {
+        eprintln!("can't read {}: {}", args[1], err);
+        process::exit(1);
+    });
+    let script = parse_script(&text).unwrap_or_else(|err| {
+        eprintln!("{}: {}", args[1], err);
+        process::exit(1);
+    });
+
+    run_script(script, Path::new(&args[2]));
+}
+
+/// What the main menu needs of the PC.
+struct Sim<'a> {
+    session: &'a Session,
+}
+
+impl System for Sim<'_> {
+    /// Ends the session instead of sleeping.
+    fn quit<D: Display>(&mut self, pcd: &mut D) -> bool {
+        Display::clear(pcd);
+        self.session.finish();
+        false
+    }
+}
+
+/// The main menu, as in the firmware.
+async fn play(display: &mut SimDisplay, session: &Session) {
+    MainMenu::run(display, &mut Buttons::new(), &mut Sim { session }).await;
+}
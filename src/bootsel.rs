@@ -0,0 +1,50 @@
+//! USB interfaces offered by the RP2040 bootloader after rebooting into BOOTSEL.
+//!
+//! The mass storage drive takes UF2 files, PICOBOOT is what `picotool` talks to.
+//! One of the two always stays on, without both the board could only be
+//! flashed again by holding the BOOTSEL button while plugging it in.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+static MASS_STORAGE: AtomicBool = AtomicBool::new(true);
+static PICOBOOT: AtomicBool = AtomicBool::new(true);
+
+/// Bits of the `disable_interface_mask` of the `reset_to_usb_boot` ROM function.
+const DISABLE_MASS_STORAGE: u32 = 1;
+const DISABLE_PICOBOOT: u32 = 2;
+
+pub fn mass_storage() -> bool {
+    MASS_STORAGE.load(Ordering::Relaxed)
+}
+
+pub fn picoboot() -> bool {
+    PICOBOOT.load(Ordering::Relaxed)
+}
+
+/// Turning off the last interface turns the other one back on.
+pub fn set_mass_storage(on: bool) {
+    MASS_STORAGE.store(on, Ordering::Relaxed);
+    if !on {
+        PICOBOOT.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Turning off the last interface turns the other one back on.
+pub fn set_picoboot(on: bool) {
+    PICOBOOT.store(on, Ordering::Relaxed);
+    if !on {
+        MASS_STORAGE.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The `disable_interface_mask` to reboot with.
+pub fn disable_mask() -> u32 {
+    let mut mask = 0;
+    if !mass_storage() {
+        mask |= DISABLE_MASS_STORAGE;
+    }
+    if !picoboot() {
+        mask |= DISABLE_PICOBOOT;
+    }
+    mask
+}
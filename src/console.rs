@@ -0,0 +1,265 @@
+//! Scrolling text console filling the screen, written to with `write!`.
+//!
+//! Rows are 8 pixels high, 6 of them with 16 columns of
+//! `fonts::FONT_5X7`. Text
+//! wraps at the end of a row and the console scrolls up once the last row
+//! is full. Besides printable characters it understands `\n`, `\r`, `\t`,
+//! backspace (`\x08`) and form feed (`\x0c`, clears everything).
+
+use core::fmt::{self, Write};
+
+use embedded_graphics::Drawable;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive};
+use embedded_graphics::primitives::{Line, PrimitiveStyle};
+use embedded_graphics::text::{Baseline, Text};
+
+use crate::display::Display;
+use crate::fonts::FONT_5X7;
+
+pub const COLUMNS: usize = 16;
+pub const ROWS: usize = 6;
+const CELL_WIDTH: i32 = 5;
+const CELL_HEIGHT: i32 = 8;
+/// Centers the columns, they leave 4 pixels of the 84.
+const MARGIN: i32 = 2;
+const TAB_WIDTH: usize = 4;
+
+pub struct Console {
+    cells: [[char; COLUMNS]; ROWS],
+    column: usize,
+    row: usize,
+    cursor_visible: bool,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            cells: [[' '; COLUMNS]; ROWS],
+            column: 0,
+            row: 0,
+            cursor_visible: false,
+        }
+    }
+
+    /// Blanks everything and moves the cursor home.
+    pub fn clear(&mut self) {
+        self.cells = [[' '; COLUMNS]; ROWS];
+        self.column = 0;
+        self.row = 0;
+    }
+
+    /// Column and row the next character goes to.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.column, self.row)
+    }
+
+    /// Moves the cursor, kept on the screen.
+    pub fn set_cursor(&mut self, column: usize, row: usize) {
+        self.column = column.min(COLUMNS - 1);
+        self.row = row.min(ROWS - 1);
+    }
+
+    /// Underlines the cell at the cursor in `render`.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+    }
+
+    /// Blanks the row with the cursor, from the cursor on.
+    pub fn clear_line(&mut self) {
+        self.cells[self.row][self.column.min(COLUMNS)..].fill(' ');
+    }
+
+    /// Moves every row up by one, blanking the last.
+    pub fn scroll(&mut self) {
+        self.cells.copy_within(1.., 0);
+        self.cells[ROWS - 1] = [' '; COLUMNS];
+    }
+
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row == ROWS - 1 {
+            self.scroll();
+        } else {
+            self.row += 1;
+        }
+    }
+
+    pub fn put(&mut self, c: char) {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => self.column = 0,
+            '\t' => {
+                let column = (self.column / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.column = column.min(COLUMNS);
+            },
+            '\x08' => self.column = self.column.saturating_sub(1),
+            '\x0c' => self.clear(),
+            c if c.is_control() => {},
+            c => {
+                // Wrapping only once there is more to show keeps a full
+                // row followed by `\n` from leaving an empty one
+                if self.column >= COLUMNS {
+                    self.new_line();
+                }
+                self.cells[self.row][self.column] = c;
+                self.column += 1;
+            },
+        }
+    }
+
+    /// Draws the console into the buffer, `Display::draw` sends it.
+    pub fn render<D: Display>(&self, pcd: &mut D) {
+        pcd.clear_buffer();
+        let style = MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (column, c) in cells.iter().enumerate() {
+                if *c == ' ' {
+                    continue;
+                }
+                let mut bytes = [0; 4];
+                Text::with_baseline(c.encode_utf8(&mut bytes), cell_origin(column, row), style, Baseline::Top)
+                    .draw(pcd).unwrap();
+            }
+        }
+
+        if self.cursor_visible {
+            let start = cell_origin(self.column.min(COLUMNS - 1), self.row) + Point::new(0, CELL_HEIGHT - 1);
+            Line::new(start, start + Point::new(CELL_WIDTH - 2, 0))
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                .draw(pcd).unwrap();
+        }
+    }
+
+    pub fn draw<D: Display>(&self, pcd: &mut D) {
+        self.render(pcd);
+        pcd.draw();
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for Console {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        text.chars().for_each(|c| self.put(c));
+        Ok(())
+    }
+}
+
+fn cell_origin(column: usize, row: usize) -> Point {
+    Point::new(MARGIN + column as i32 * CELL_WIDTH, row as i32 * CELL_HEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::string::String;
+    use std::vec::Vec;
+
+    use embedded_graphics::prelude::Size;
+    use embedded_graphics::primitives::Rectangle;
+
+    use crate::display::{TestDisplay, HEIGHT, WIDTH};
+
+    /// Rows as text, without the blanks at their ends.
+    fn rows(console: &Console) -> Vec<String> {
+        console.cells.iter()
+            .map(|cells| cells.iter().collect::<String>().trim_end().into())
+            .collect()
+    }
+
+    fn written(text: &str) -> Console {
+        let mut console = Console::new();
+        write!(console, "{}", text).unwrap();
+        console
+    }
+
+    #[test]
+    fn new_lines() {
+        let console = written("one\ntwo\n\nfour");
+        assert_eq!(rows(&console), ["one", "two", "", "four", "", ""]);
+        assert_eq!(console.cursor(), (4, 3));
+    }
+
+    #[test]
+    fn wraps_long_lines() {
+        let console = written("0123456789abcdefXYZ");
+        assert_eq!(rows(&console), ["0123456789abcdef", "XYZ", "", "", "", ""]);
+        assert_eq!(console.cursor(), (3, 1));
+
+        // A full row followed by a new line leaves no empty row
+        let console = written("0123456789abcdef\nnext");
+        assert_eq!(rows(&console), ["0123456789abcdef", "next", "", "", "", ""]);
+    }
+
+    #[test]
+    fn scrolls() {
+        let console = written("1\n2\n3\n4\n5\n6\n7\n8");
+        assert_eq!(rows(&console), ["3", "4", "5", "6", "7", "8"]);
+        assert_eq!(console.cursor(), (1, ROWS - 1));
+
+        // Wrapping on the last row scrolls too
+        let console = written("1\n2\n3\n4\n5\n0123456789abcdefg");
+        assert_eq!(rows(&console), ["2", "3", "4", "5", "0123456789abcdef", "g"]);
+    }
+
+    #[test]
+    fn control_characters() {
+        assert_eq!(rows(&written("abc\rX"))[0], "Xbc");
+        assert_eq!(rows(&written("a\tb\t\tc"))[0], "a   b       c");
+        assert_eq!(rows(&written("abc\x08\x08X"))[0], "aXc");
+        assert_eq!(rows(&written("\x08a"))[0], "a");
+        assert_eq!(rows(&written("a\x07\x1bb"))[0], "ab");
+
+        let console = written("gone\nfor\ngood\x0cnew");
+        assert_eq!(rows(&console), ["new", "", "", "", "", ""]);
+        assert_eq!(console.cursor(), (3, 0));
+    }
+
+    #[test]
+    fn polish_characters() {
+        assert_eq!(rows(&written("zażółć"))[0], "zażółć");
+    }
+
+    #[test]
+    fn cursor_and_clear_line() {
+        let mut console = written("hello\nworld");
+        console.set_cursor(2, 0);
+        console.clear_line();
+        assert_eq!(rows(&console), ["he", "world", "", "", "", ""]);
+
+        // Kept on the screen
+        console.set_cursor(100, 100);
+        assert_eq!(console.cursor(), (COLUMNS - 1, ROWS - 1));
+    }
+
+    #[test]
+    fn renders_cells() {
+        let mut console = written("\n\n #");
+        let mut display = TestDisplay::default();
+        console.draw(&mut display);
+        assert_eq!(display.frames, 1);
+
+        // Only the cell of the `#`, second column of the third row, is drawn
+        let cell = Rectangle::new(cell_origin(1, 2), Size::new(CELL_WIDTH as u32, CELL_HEIGHT as u32));
+        let lit: Vec<Point> = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| display.pixel(*x, *y))
+            .map(|(x, y)| Point::new(x as i32, y as i32))
+            .collect();
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|point| cell.contains(*point)), "{:?}", lit);
+
+        // The cursor underlines the cell after it
+        console.set_cursor_visible(true);
+        console.render(&mut display);
+        let under = cell_origin(2, 2) + Point::new(0, CELL_HEIGHT - 1);
+        assert!(display.pixel(under.x as usize, under.y as usize));
+    }
+}
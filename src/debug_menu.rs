@@ -0,0 +1,57 @@
+use crate::bootsel;
+use crate::display::Display;
+use crate::inputs::Input;
+use crate::lang;
+use crate::menu::{Menu, MenuOption};
+use crate::rand::{fixed_seed, set_fixed_seed};
+
+/// Seed used by the fixed seed mode, gives the same apples every run.
+const DEBUG_SEED: u32 = 25023540;
+
+#[derive(Clone, Copy)]
+enum DebugSelected {
+    FixedSeed, MassStorage, Picoboot, Quit
+}
+
+pub struct DebugMenu;
+
+impl DebugMenu {
+    pub async fn run<D: Display, I: Input>(pcd: &mut D, inputs: &mut I) {
+        loop {
+            let strings = lang::strings();
+            let seed_text = strings.fixed_seed[fixed_seed().is_some() as usize];
+            // Interfaces of the bootloader after a long press of the reboot button
+            let mass_storage_text = strings.mass_storage[bootsel::mass_storage() as usize];
+            let picoboot_text = strings.picoboot[bootsel::picoboot() as usize];
+
+            let mut debug_menu = Menu::new(
+                strings.debug,
+                [
+                    MenuOption::new(DebugSelected::FixedSeed, seed_text),
+                    MenuOption::new(DebugSelected::MassStorage, mass_storage_text),
+                    MenuOption::new(DebugSelected::Picoboot, picoboot_text),
+                    MenuOption::new(DebugSelected::Quit, strings.quit),
+                ]
+                );
+
+            match debug_menu.run(pcd, inputs).await {
+                DebugSelected::FixedSeed => {
+                    if fixed_seed().is_some() {
+                        set_fixed_seed(None);
+                    } else {
+                        set_fixed_seed(Some(DEBUG_SEED));
+                    }
+                },
+                DebugSelected::MassStorage => {
+                    bootsel::set_mass_storage(!bootsel::mass_storage());
+                },
+                DebugSelected::Picoboot => {
+                    bootsel::set_picoboot(!bootsel::picoboot());
+                },
+                DebugSelected::Quit => {
+                    return;
+                }
+            }
+        }
+    }
+}
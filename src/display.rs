@@ -0,0 +1,149 @@
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, draw_target::DrawTarget, Pixel};
+
+use crate::gray::GrayCanvas;
+
+pub const WIDTH: usize = 84;
+pub const HEIGHT: usize = 48;
+pub const BUFFER_SIZE: usize = WIDTH*HEIGHT/8;
+
+/// Screen contents in the PCD8544 layout, see `pixel_index`.
+pub type FrameBuffer = [u8; BUFFER_SIZE];
+
+/// Byte index and bit mask of a pixel in a `FrameBuffer`.
+///
+/// The panel is mounted upside down, so the buffer starts at the bottom right corner.
+pub fn pixel_index(x: usize, y: usize) -> (usize, u8) {
+    let index = ((HEIGHT - 1 - y) >> 3) * WIDTH + (WIDTH - 1 - x);
+    let offset = (HEIGHT - 1 - y) & 0x07;
+    (index, 0x01 << offset)
+}
+
+/// Sets `pixels` in `buffer`, for implementing `DrawTarget::draw_iter`.
+pub fn draw_pixels<I>(buffer: &mut FrameBuffer, pixels: I)
+where
+    I: IntoIterator<Item = Pixel<BinaryColor>> {
+    for Pixel(coord, color) in pixels.into_iter() {
+        if coord.y < 0 ||  coord.y > 47 {
+            panic!();
+        }
+        if coord.x < 0 || coord.x >= WIDTH as i32 {
+            continue;
+        }
+        let (index, mask) = pixel_index(coord.x as usize, coord.y as usize);
+        if color.is_on() {
+            buffer[index] |= mask;
+        } else {
+            buffer[index] &= !mask;
+        }
+    }
+}
+
+/// Power state of the screen and its backlight, see `Display::set_power`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Power {
+    On,
+    /// Still showing the frame, with the backlight turned down.
+    Dimmed,
+    /// Blank and drawing next to nothing, the buffer is kept for `On`.
+    Off,
+}
+
+/// Screen the menus and games draw on, a frame is built in the buffer and then sent with `draw`.
+pub trait Display: DrawTarget<Color = BinaryColor, Error = Infallible> {
+    fn buffer(&self) -> &FrameBuffer;
+
+    fn buffer_mut(&mut self) -> &mut FrameBuffer;
+
+    /// Sends the buffer to the screen.
+    fn draw(&mut self);
+
+    fn clear(&mut self) {
+        self.clear_buffer();
+        self.draw();
+    }
+
+    /// Like `clear`, but leaves the screen alone until the next `draw`.
+    fn clear_buffer(&mut self) {
+        *self.buffer_mut() = [0; BUFFER_SIZE];
+    }
+
+    fn inverse(&mut self) {
+        self.buffer_mut().iter_mut().for_each(|x| *x = !*x);
+    }
+
+    /// Shows `canvas` in shades of gray until the next `draw`, for screens
+    /// that can cycle its planes. The others show the pixels at least half
+    /// dark in black, the buffer is left alone either way.
+    fn draw_gray<const PLANES: usize>(&mut self, canvas: &GrayCanvas<PLANES>) {
+        let buffer = *self.buffer();
+        *self.buffer_mut() = *canvas.plane(PLANES - 1);
+        self.draw();
+        *self.buffer_mut() = buffer;
+    }
+
+    /// Switching back `On` shows the buffer again, for screens that can sleep.
+    fn set_power(&mut self, _power: Power) {}
+}
+
+/// Screen for the unit tests, a buffer counting the frames sent.
+#[cfg(test)]
+pub struct TestDisplay {
+    pub buffer: FrameBuffer,
+    pub frames: usize,
+    /// The buffer as of the last `draw`.
+    pub sent: FrameBuffer,
+}
+
+#[cfg(test)]
+impl Default for TestDisplay {
+    fn default() -> Self {
+        Self { buffer: [0; BUFFER_SIZE], frames: 0, sent: [0; BUFFER_SIZE] }
+    }
+}
+
+#[cfg(test)]
+impl TestDisplay {
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        let (index, mask) = pixel_index(x, y);
+        self.buffer[index] & mask != 0
+    }
+}
+
+#[cfg(test)]
+impl Display for TestDisplay {
+    fn buffer(&self) -> &FrameBuffer {
+        &self.buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.buffer
+    }
+
+    fn draw(&mut self) {
+        self.frames += 1;
+        self.sent = self.buffer;
+    }
+}
+
+#[cfg(test)]
+impl embedded_graphics::geometry::OriginDimensions for TestDisplay {
+    fn size(&self) -> embedded_graphics::geometry::Size {
+        embedded_graphics::geometry::Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+#[cfg(test)]
+impl DrawTarget for TestDisplay {
+    type Color = BinaryColor;
+
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        draw_pixels(&mut self.buffer, pixels);
+        Ok(())
+    }
+}
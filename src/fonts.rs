@@ -0,0 +1,6 @@
+//! Fonts with the Polish letters, from the ISO 8859-2 set of `embedded_graphics`.
+//!
+//! Everything showing `lang` labels draws with these, the ASCII ones would
+//! leave a `?` for every `ą`, `ł` or `ź`.
+
+pub use embedded_graphics::mono_font::iso_8859_2::{FONT_4X6, FONT_5X7, FONT_6X10};
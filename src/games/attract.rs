@@ -0,0 +1,38 @@
+use crate::audio;
+use crate::inputs::Input;
+use crate::display::Display;
+
+use super::snake::SnakeGame;
+use super::snake_ai::SnakeAi;
+
+/// Demo games played before giving up on anyone watching.
+const GAMES: u32 = 5;
+
+/// Demo shown while the console sits idle, ends on any button press.
+pub struct AttractMode;
+
+impl AttractMode {
+    /// Returns `false` if nobody pressed anything during the whole demo,
+    /// the console might as well go to sleep then.
+    pub async fn run<D: Display, I: Input>(pcd: &mut D, inputs: &mut I) -> bool {
+        // The demo plays silently
+        let volume = audio::volume();
+        audio::set_volume(0);
+
+        let mut ai = SnakeAi::new(inputs);
+        for _ in 0..GAMES {
+            {
+                let mut snake_game = SnakeGame::new(pcd, &mut ai);
+                snake_game.run().await;
+            }
+
+            if ai.interrupted() {
+                audio::set_volume(volume);
+                return true;
+            }
+        }
+
+        audio::set_volume(volume);
+        false
+    }
+}
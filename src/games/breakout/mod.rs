@@ -0,0 +1,454 @@
+//! Brick breaker: keep the ball bouncing off the paddle to break the bricks.
+//!
+//! The first and last buttons move the paddle, the middle ones launch the
+//! ball. Layouts come from `assets/levels/breakout-*.txt`, a cell being the
+//! hits its brick takes and `F` one that never breaks. Now and then a broken
+//! brick drops a power-up: a wide paddle, more balls or a slow ball.
+
+pub mod physics;
+
+use embedded_graphics::{Drawable, Pixel};
+use embedded_graphics::mono_font::{MonoTextStyle, MonoTextStyleBuilder};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive, Size};
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Alignment, Text};
+use heapless::{String, Vec};
+use core::fmt::Write;
+
+use crate::assets::{self, levels};
+use crate::audio::{self, effects::Effect};
+use crate::battery;
+use crate::display::{Display, HEIGHT, WIDTH};
+use crate::fonts::FONT_5X7;
+use crate::inputs::Input;
+use crate::lang;
+use crate::level::Level;
+use crate::rand::Rand;
+use crate::sfx::particles::Particles;
+use crate::sfx::transition::{Transition, TransitionKind};
+use crate::sprite::Flip;
+use crate::tasks::Ticker;
+
+use physics::{Ball, Bricks, BrickHit, Paddle, PowerUp, PowerUpKind, Rect};
+use physics::{COLUMNS, ROWS, SUBPIXELS, TOP, UNBREAKABLE};
+
+const FRAME_MS: u32 = 20;
+const LIVES: u32 = 3;
+const MAX_BALLS: usize = 3;
+const MAX_POWER_UPS: usize = 4;
+const PADDLE_WIDTH: i32 = 14;
+const WIDE_PADDLE_WIDTH: i32 = 22;
+/// How far a press moves the paddle, which slides there `PADDLE_SPEED` a frame.
+const PADDLE_STEP: i32 = 12 * SUBPIXELS;
+const PADDLE_SPEED: i32 = 3 * SUBPIXELS;
+const BALL_SPEED: i32 = SUBPIXELS;
+/// Added each time the layouts start over.
+const ROUND_SPEEDUP: i32 = SUBPIXELS / 4;
+const MAX_BALL_SPEED: i32 = 2 * SUBPIXELS;
+const SLOW_BALL_SPEED: i32 = SUBPIXELS * 5 / 8;
+/// Frames a wide paddle or a slow ball lasts.
+const POWER_UP_FRAMES: u32 = 500;
+/// One broken brick out of this many drops a power-up.
+const DROP_ODDS: u32 = 6;
+/// Frames the ball waits on the paddle before going by itself.
+const LAUNCH_FRAMES: u32 = 150;
+/// Blink period of the low battery warning.
+const WARNING_FRAMES: u32 = 30;
+
+const LAYOUTS: [Level; 3] = [levels::BREAKOUT_1, levels::BREAKOUT_2, levels::BREAKOUT_3];
+const POWER_UPS: [PowerUpKind; 3] = [PowerUpKind::Wide, PowerUpKind::Multi, PowerUpKind::Slow];
+
+fn rectangle(rect: Rect) -> Rectangle {
+    Rectangle::new(Point::new(rect.x, rect.y), Size::new(rect.width as u32, rect.height as u32))
+}
+
+/// A brick for each cell of `level`, taking as many hits as the cell says.
+fn bricks(level: &Level) -> Bricks {
+    let mut bricks = Bricks::new();
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            bricks.set(column, row, level.cell(column, row));
+        }
+    }
+    bricks
+}
+
+/// The more hits a brick takes the darker it is, those that never break are gray.
+fn draw_brick<D: Display>(pcd: &mut D, rect: Rect, hits: u8) {
+    let on = BinaryColor::On;
+    match hits {
+        UNBREAKABLE => {
+            let pixels = (0..rect.height)
+                .flat_map(|y| (0..rect.width).map(move |x| Point::new(x, y)))
+                .filter(|point| (point.x + point.y) % 2 == 0)
+                .map(|point| Pixel(Point::new(rect.x, rect.y) + point, on));
+            pcd.draw_iter(pixels).unwrap();
+        },
+        1 => {
+            rectangle(rect).into_styled(PrimitiveStyle::with_stroke(on, 1)).draw(pcd).unwrap();
+        },
+        2 => {
+            rectangle(rect).into_styled(PrimitiveStyle::with_fill(on)).draw(pcd).unwrap();
+        },
+        _ => {
+            // Solid with a rivet
+            rectangle(rect).into_styled(PrimitiveStyle::with_fill(on)).draw(pcd).unwrap();
+            let rivet = Point::new(rect.x + rect.width / 2, rect.y + rect.height / 2);
+            Pixel(rivet, BinaryColor::Off).draw(pcd).unwrap();
+        },
+    }
+}
+
+pub struct BreakoutGame<'a, D: Display, I: Input> {
+    pcd:    &'a mut D,
+    inputs: &'a mut I,
+    rand:   Rand,
+    particles: Particles<16>,
+    bricks: Bricks,
+    paddle: Paddle,
+    /// Where the paddle is sliding to.
+    target: i32,
+    balls: Vec<Ball, MAX_BALLS>,
+    power_ups: Vec<PowerUp, MAX_POWER_UPS>,
+    /// Frames the ball has been waiting on the paddle, `None` once it's away.
+    waiting: Option<u32>,
+    layout: usize,
+    /// Times all the layouts were cleared.
+    round: u32,
+    score: u32,
+    lives: u32,
+    wide_frames: u32,
+    slow_frames: u32,
+    frame: u32,
+}
+
+impl<'a, D: Display, I: Input> BreakoutGame<'a, D, I> {
+    pub fn new(pcd: &'a mut D, inputs: &'a mut I) -> Self {
+        Self {
+            pcd,
+            inputs,
+            rand: Rand::from_entropy(),
+            particles: Particles::new(),
+            bricks: Bricks::new(),
+            paddle: Paddle::new(PADDLE_WIDTH),
+            target: 0,
+            balls: Vec::new(),
+            power_ups: Vec::new(),
+            waiting: None,
+            layout: 0,
+            round: 0,
+            score: 0,
+            lives: LIVES,
+            wide_frames: 0,
+            slow_frames: 0,
+            frame: 0,
+        }
+    }
+
+    /// Of every ball in play, and of those to come.
+    fn speed(&self) -> i32 {
+        if self.slow_frames > 0 {
+            SLOW_BALL_SPEED
+        } else {
+            (BALL_SPEED + self.round as i32 * ROUND_SPEEDUP).min(MAX_BALL_SPEED)
+        }
+    }
+
+    fn load_layout(&mut self) {
+        self.bricks = bricks(&LAYOUTS[self.layout]);
+        self.serve();
+    }
+
+    /// A new ball on the paddle, without the power-ups of the last one.
+    fn serve(&mut self) {
+        self.wide_frames = 0;
+        self.slow_frames = 0;
+        self.power_ups.clear();
+        self.set_paddle_width(PADDLE_WIDTH);
+
+        let mut ball = Ball::new(0, 0, 0, 0);
+        self.paddle.carry(&mut ball);
+        self.balls.clear();
+        self.balls.push(ball).unwrap();
+        self.waiting = Some(0);
+    }
+
+    fn set_paddle_width(&mut self, width: i32) {
+        self.paddle.set_width(width);
+        self.target = self.paddle.x;
+    }
+
+    pub async fn run(&mut self) {
+        audio::stop_music();
+        self.load_layout();
+
+        let from = *self.pcd.buffer();
+        self.render();
+        let mut slide = Transition::new(TransitionKind::Slide, &from, self.pcd.buffer(), 15);
+        let mut ticker = Ticker::every(FRAME_MS);
+        while slide.step(self.pcd) {
+            self.pcd.draw();
+            ticker.tick().await;
+        }
+
+        loop {
+            if !self.update().await {
+                return;
+            }
+
+            self.particles.step();
+            self.render();
+            self.pcd.draw();
+
+            ticker.tick().await;
+        }
+    }
+
+    async fn update(&mut self) -> bool {
+        self.inputs.update();
+        let inputs = *self.inputs.is_pressed();
+        let (min, max) = self.paddle.range();
+        if inputs[0] {
+            self.target = (self.target - PADDLE_STEP).max(min);
+        }
+        if inputs[3] {
+            self.target = (self.target + PADDLE_STEP).min(max);
+        }
+        self.paddle.slide((self.target - self.paddle.x).clamp(-PADDLE_SPEED, PADDLE_SPEED));
+
+        if let Some(waiting) = self.waiting {
+            self.paddle.carry(&mut self.balls[0]);
+            if inputs[1] || inputs[2] || waiting >= LAUNCH_FRAMES {
+                let speed = self.speed();
+                self.paddle.bounce(&mut self.balls[0], speed);
+                self.waiting = None;
+            } else {
+                self.waiting = Some(waiting + 1);
+            }
+        }
+
+        self.move_balls();
+        self.move_power_ups();
+        self.wear_off_power_ups();
+
+        if self.balls.is_empty() {
+            self.lives -= 1;
+            if self.lives == 0 {
+                super::game_over(self.pcd, self.inputs).await;
+                return false;
+            }
+            self.serve();
+        }
+
+        if self.bricks.cleared() {
+            audio::play(Effect::Confirm);
+            self.layout += 1;
+            if self.layout == LAYOUTS.len() {
+                self.layout = 0;
+                self.round += 1;
+            }
+            self.load_layout();
+        }
+
+        true
+    }
+
+    fn move_balls(&mut self) {
+        if self.waiting.is_some() {
+            return;
+        }
+
+        let speed = self.speed();
+        let mut index = 0;
+        while index < self.balls.len() {
+            let collisions = physics::step(&mut self.balls[index], &self.paddle, &mut self.bricks, speed);
+            if !collisions.bricks.is_empty() {
+                audio::play(Effect::Brick);
+            } else if collisions.wall || collisions.paddle {
+                audio::play(Effect::Bounce);
+            }
+            for hit in collisions.bricks {
+                self.hit_brick(hit);
+            }
+
+            if collisions.lost {
+                self.balls.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    fn hit_brick(&mut self, hit: BrickHit) {
+        self.score += 1;
+        if hit.hits > 0 {
+            return;
+        }
+
+        let brick = Bricks::rect(hit.column, hit.row);
+        let center = Point::new(brick.x + brick.width / 2, brick.y + brick.height / 2);
+        self.particles.burst(center, 6, &mut self.rand);
+        if self.rand.range(0, DROP_ODDS) == 0 && !self.power_ups.is_full() {
+            let kind = *self.rand.choose(&POWER_UPS).unwrap();
+            self.power_ups.push(PowerUp::new(kind, hit.column, hit.row)).unwrap();
+        }
+    }
+
+    fn move_power_ups(&mut self) {
+        let mut index = 0;
+        while index < self.power_ups.len() {
+            match self.power_ups[index].fall(&self.paddle) {
+                Some(caught) => {
+                    let power_up = self.power_ups.swap_remove(index);
+                    if caught {
+                        self.power_up(power_up.kind);
+                    }
+                },
+                None => index += 1,
+            }
+        }
+    }
+
+    fn power_up(&mut self, kind: PowerUpKind) {
+        audio::play(Effect::PowerUp);
+        match kind {
+            PowerUpKind::Wide => {
+                self.wide_frames = POWER_UP_FRAMES;
+                self.set_paddle_width(WIDE_PADDLE_WIDTH);
+            },
+            PowerUpKind::Multi => {
+                // Out of the first ball, if it's away already
+                if self.waiting.is_none() {
+                    for ball in self.balls[0].split() {
+                        if self.balls.push(ball).is_err() {
+                            break;
+                        }
+                    }
+                }
+            },
+            PowerUpKind::Slow => {
+                self.slow_frames = POWER_UP_FRAMES;
+                for ball in self.balls.iter_mut() {
+                    ball.set_speed(SLOW_BALL_SPEED);
+                }
+            },
+        }
+    }
+
+    fn wear_off_power_ups(&mut self) {
+        if self.wide_frames > 0 {
+            self.wide_frames -= 1;
+            if self.wide_frames == 0 {
+                self.set_paddle_width(PADDLE_WIDTH);
+            }
+        }
+
+        if self.slow_frames > 0 {
+            self.slow_frames -= 1;
+            if self.slow_frames == 0 {
+                let speed = self.speed();
+                for ball in self.balls.iter_mut() {
+                    ball.set_speed(speed);
+                }
+            }
+        }
+    }
+
+    /// Draws the game into the buffer, `Display::draw` sends it.
+    fn render(&mut self) {
+        let on = BinaryColor::On;
+        self.pcd.clear_buffer();
+
+        // Walls, open at the bottom
+        let (right, bottom) = (WIDTH as i32 - 1, HEIGHT as i32 - 1);
+        let wall = PrimitiveStyle::with_stroke(on, 1);
+        Line::new(Point::new(0, TOP - 1), Point::new(right, TOP - 1)).into_styled(wall).draw(self.pcd).unwrap();
+        Line::new(Point::new(0, TOP - 1), Point::new(0, bottom)).into_styled(wall).draw(self.pcd).unwrap();
+        Line::new(Point::new(right, TOP - 1), Point::new(right, bottom)).into_styled(wall).draw(self.pcd).unwrap();
+
+        // Score, and a dot for each life
+        let text = MonoTextStyle::new(&FONT_5X7, on);
+        Text::new(&String::<10>::from(self.score), Point::new(1, 6), text).draw(self.pcd).unwrap();
+        for life in 0..self.lives as i32 {
+            Rectangle::new(Point::new(right - 4 * (life + 1), 2), Size::new(3, 3))
+                .into_styled(PrimitiveStyle::with_fill(on))
+                .draw(self.pcd).unwrap();
+        }
+
+        // Bricks
+        for row in 0..ROWS {
+            for column in 0..COLUMNS {
+                let hits = self.bricks.get(column, row);
+                if hits > 0 {
+                    draw_brick(self.pcd, Bricks::rect(column, row), hits);
+                }
+            }
+        }
+
+        // Paddle, balls and power-ups
+        rectangle(self.paddle.rect()).into_styled(PrimitiveStyle::with_fill(on)).draw(self.pcd).unwrap();
+        for ball in &self.balls {
+            rectangle(ball.rect()).into_styled(PrimitiveStyle::with_fill(on)).draw(self.pcd).unwrap();
+        }
+        for power_up in &self.power_ups {
+            let sprite = match power_up.kind {
+                PowerUpKind::Wide  => assets::POWER_WIDE,
+                PowerUpKind::Multi => assets::POWER_MULTI,
+                PowerUpKind::Slow  => assets::POWER_SLOW,
+            };
+            let rect = power_up.rect();
+            sprite.draw(self.pcd, Point::new(rect.x, rect.y), Flip::NONE);
+        }
+
+        // Level number, while the ball waits
+        if self.waiting.is_some() {
+            let number = self.round as usize * LAYOUTS.len() + self.layout + 1;
+            let mut label = String::<16>::new();
+            write!(label, "{} {}", lang::strings().level, number).unwrap();
+            let style = MonoTextStyleBuilder::new()
+                .font(&FONT_5X7)
+                .text_color(on)
+                .background_color(BinaryColor::Off)
+                .build();
+            Text::with_alignment(&label, Point::new(WIDTH as i32 / 2, 41), style, Alignment::Center)
+                .draw(self.pcd).unwrap();
+        }
+
+        // Effects
+        self.particles.draw(self.pcd);
+
+        // Low battery warning, between the score and the lives
+        self.frame = self.frame.wrapping_add(1);
+        let low = matches!(battery::level(), battery::Level::Low | battery::Level::Critical);
+        if low && self.frame % WARNING_FRAMES < WARNING_FRAMES / 2 {
+            battery::draw_icon(self.pcd, Point::new((WIDTH as u32 - battery::ICON_WIDTH) as i32 / 2, 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_become_bricks() {
+        // 1.2  and  F.A
+        let bricks = bricks(&Level::new(3, 2, &[0x10, 0x2f, 0x0a]));
+        assert_eq!([bricks.get(0, 0), bricks.get(1, 0), bricks.get(2, 0)], [1, 0, 2]);
+        assert_eq!([bricks.get(0, 1), bricks.get(1, 1), bricks.get(2, 1)], [UNBREAKABLE, 0, 10]);
+        // Past the level there are none
+        assert_eq!(bricks.get(3, 0), 0);
+        assert_eq!(bricks.get(0, ROWS - 1), 0);
+    }
+
+    #[test]
+    fn layouts_fit_the_grid() {
+        for level in LAYOUTS {
+            // Cells past the grid would be silently left out
+            assert_eq!(level.width, COLUMNS);
+            assert!(level.height <= ROWS);
+            assert!(!bricks(&level).cleared());
+        }
+    }
+}
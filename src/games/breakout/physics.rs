@@ -0,0 +1,545 @@
+//! Ball, paddle and bricks of `breakout`, moved and collided in fixed point.
+//!
+//! Positions are in 1/`SUBPIXELS` of a pixel and speeds in 1/`SUBPIXELS` of
+//! a pixel per frame. Nothing here draws or reads buttons, so it builds on
+//! its own and `tests/breakout.rs` runs it on the host.
+
+use heapless::Vec;
+
+pub const SUBPIXELS: i32 = 256;
+
+/// Inside of the walls, in pixels, the right end excluded.
+pub const LEFT: i32 = 1;
+pub const RIGHT: i32 = 83;
+pub const TOP: i32 = 9;
+/// Bottom of the screen, a ball going past it is lost.
+pub const BOTTOM: i32 = 48;
+
+pub const BALL_SIZE: i32 = 2;
+
+pub const COLUMNS: usize = 10;
+pub const ROWS: usize = 6;
+/// Top left corner of the first brick.
+pub const BRICKS_X: i32 = 2;
+pub const BRICKS_Y: i32 = 12;
+/// A brick and the gap after it.
+pub const BRICK_WIDTH: i32 = 8;
+pub const BRICK_HEIGHT: i32 = 4;
+/// Hits of a brick that never breaks.
+pub const UNBREAKABLE: u8 = 0xf;
+
+pub const PADDLE_Y: i32 = 44;
+pub const PADDLE_HEIGHT: i32 = 2;
+
+pub const POWER_UP_SIZE: i32 = 7;
+pub const POWER_UP_SPEED: i32 = SUBPIXELS / 2;
+
+/// Where the paddle sends the ball, from its left end to its right one:
+/// 60, 40 and 20 degrees off vertical, `SUBPIXELS` long.
+const PADDLE_DIRECTIONS: [(i32, i32); 6] = [
+    (-222, -128),
+    (-165, -196),
+    (-88, -241),
+    (88, -241),
+    (165, -196),
+    (222, -128),
+];
+
+/// In pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width &&
+        self.y < other.y + other.height && other.y < self.y + self.height
+    }
+}
+
+/// Square root, rounded down.
+fn isqrt(value: i32) -> i32 {
+    if value <= 0 {
+        return 0;
+    }
+    // Newton's method, starting above the root
+    let mut root = value;
+    let mut next = (root + 1) / 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+    root
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ball {
+    /// Top left corner.
+    pub x: i32,
+    pub y: i32,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+impl Ball {
+    pub const fn new(x: i32, y: i32, dx: i32, dy: i32) -> Self {
+        Self { x, y, dx, dy }
+    }
+
+    /// Top left pixel.
+    pub fn pixel(&self) -> (i32, i32) {
+        (self.x.div_euclid(SUBPIXELS), self.y.div_euclid(SUBPIXELS))
+    }
+
+    pub fn rect(&self) -> Rect {
+        let (x, y) = self.pixel();
+        Rect::new(x, y, BALL_SIZE, BALL_SIZE)
+    }
+
+    pub fn speed(&self) -> i32 {
+        isqrt(self.dx * self.dx + self.dy * self.dy)
+    }
+
+    /// Keeps the direction, a ball standing still stays that way.
+    pub fn set_speed(&mut self, speed: i32) {
+        let old = self.speed();
+        if old == 0 {
+            return;
+        }
+        self.dx = self.dx * speed / old;
+        self.dy = self.dy * speed / old;
+    }
+
+    /// Two more balls going 40 degrees off vertical either side, still
+    /// up or down like this one.
+    pub fn split(&self) -> [Ball; 2] {
+        let speed = self.speed();
+        let (dx, dy) = PADDLE_DIRECTIONS[4];
+        let dy = if self.dy > 0 { -dy } else { dy };
+        [
+            Ball::new(self.x, self.y, -dx * speed / SUBPIXELS, dy * speed / SUBPIXELS),
+            Ball::new(self.x, self.y, dx * speed / SUBPIXELS, dy * speed / SUBPIXELS),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Paddle {
+    /// Left end.
+    pub x: i32,
+    /// In pixels.
+    pub width: i32,
+}
+
+impl Paddle {
+    /// In the middle.
+    pub const fn new(width: i32) -> Self {
+        Self {
+            x: (LEFT + RIGHT - width) * SUBPIXELS / 2,
+            width,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x.div_euclid(SUBPIXELS), PADDLE_Y, self.width, PADDLE_HEIGHT)
+    }
+
+    /// Furthest `x` can go either way.
+    pub fn range(&self) -> (i32, i32) {
+        (LEFT * SUBPIXELS, (RIGHT - self.width) * SUBPIXELS)
+    }
+
+    /// Moves the paddle by `dx`, stopping at the walls.
+    pub fn slide(&mut self, dx: i32) {
+        let (min, max) = self.range();
+        self.x = (self.x + dx).clamp(min, max);
+    }
+
+    /// Grows or shrinks the paddle around its middle.
+    pub fn set_width(&mut self, width: i32) {
+        self.x += (self.width - width) * SUBPIXELS / 2;
+        self.width = width;
+        self.slide(0);
+    }
+
+    /// Puts `ball` on top of the middle of the paddle.
+    pub fn carry(&self, ball: &mut Ball) {
+        ball.x = self.x + (self.width - BALL_SIZE) * SUBPIXELS / 2;
+        ball.y = (PADDLE_Y - BALL_SIZE) * SUBPIXELS;
+    }
+
+    /// Sends `ball` back up at the same speed, flatter the further from
+    /// the middle of the paddle it lands.
+    pub fn bounce(&self, ball: &mut Ball, speed: i32) {
+        let center = ball.rect().x + BALL_SIZE / 2 - self.rect().x;
+        let zones = PADDLE_DIRECTIONS.len() as i32;
+        let zone = (center * zones / self.width).clamp(0, zones - 1);
+        let (dx, dy) = PADDLE_DIRECTIONS[zone as usize];
+        ball.dx = dx * speed / SUBPIXELS;
+        ball.dy = dy * speed / SUBPIXELS;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BrickHit {
+    pub column: usize,
+    pub row: usize,
+    /// Hits the brick still takes, 0 once it broke.
+    pub hits: u8,
+}
+
+/// Hits each brick takes before it breaks, 0 where there is none.
+#[derive(Clone, Debug, Default)]
+pub struct Bricks {
+    hits: [[u8; COLUMNS]; ROWS],
+}
+
+impl Bricks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> u8 {
+        self.hits[row][column]
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, hits: u8) {
+        self.hits[row][column] = hits;
+    }
+
+    /// Whether every brick that can break is gone.
+    pub fn cleared(&self) -> bool {
+        self.hits.iter().flatten().all(|hits| *hits == 0 || *hits == UNBREAKABLE)
+    }
+
+    /// Pixels of the brick at `column`, `row`, without the gap.
+    pub fn rect(column: usize, row: usize) -> Rect {
+        Rect::new(
+            BRICKS_X + column as i32 * BRICK_WIDTH,
+            BRICKS_Y + row as i32 * BRICK_HEIGHT,
+            BRICK_WIDTH - 1,
+            BRICK_HEIGHT - 1,
+        )
+    }
+
+    /// Bricks overlapping `rect`.
+    fn touching(&self, rect: &Rect) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rect = *rect;
+        (0..ROWS)
+            .flat_map(|row| (0..COLUMNS).map(move |column| (column, row)))
+            .filter(move |(column, row)| self.hits[*row][*column] != 0 && Bricks::rect(*column, *row).overlaps(&rect))
+    }
+}
+
+/// What a ball ran into during a frame.
+#[derive(Debug, Default)]
+pub struct Collisions {
+    /// A wall or a brick that can't break.
+    pub wall: bool,
+    pub paddle: bool,
+    pub bricks: Vec<BrickHit, 4>,
+    /// Fell past the paddle.
+    pub lost: bool,
+}
+
+/// Whether `ball` overlaps a wall or a brick, hitting the bricks it
+/// overlaps that weren't hit already this frame.
+fn collide(ball: &Ball, bricks: &mut Bricks, collisions: &mut Collisions) -> bool {
+    let rect = ball.rect();
+    let mut solid = rect.x < LEFT || rect.x + BALL_SIZE > RIGHT || rect.y < TOP;
+    if solid {
+        collisions.wall = true;
+    }
+
+    let mut touching: Vec<(usize, usize), 4> = Vec::new();
+    for brick in bricks.touching(&rect) {
+        // A 2 pixel ball can't touch more than 4 bricks
+        touching.push(brick).unwrap();
+    }
+    for (column, row) in touching {
+        solid = true;
+        let hits = &mut bricks.hits[row][column];
+        if *hits == UNBREAKABLE {
+            collisions.wall = true;
+        } else if !collisions.bricks.iter().any(|hit| (hit.column, hit.row) == (column, row)) {
+            *hits -= 1;
+            collisions.bricks.push(BrickHit { column, row, hits: *hits }).ok();
+        }
+    }
+    solid
+}
+
+/// Moves `ball` through a frame, bouncing it off the walls, the bricks
+/// and `paddle`, which sends it back at `speed`.
+pub fn step(ball: &mut Ball, paddle: &Paddle, bricks: &mut Bricks, speed: i32) -> Collisions {
+    let mut collisions = Collisions::default();
+    // Steps of at most a pixel, so the ball can't go through anything
+    let steps = ((ball.dx.abs().max(ball.dy.abs()) + SUBPIXELS - 1) / SUBPIXELS).max(1);
+    for i in 0..steps {
+        // One axis at a time, the one it got blocked on turns around
+        let dx = ball.dx * (i + 1) / steps - ball.dx * i / steps;
+        ball.x += dx;
+        if collide(ball, bricks, &mut collisions) {
+            ball.x -= dx;
+            ball.dx = -ball.dx;
+        }
+
+        let dy = ball.dy * (i + 1) / steps - ball.dy * i / steps;
+        let bottom = ball.rect().y + BALL_SIZE;
+        ball.y += dy;
+        if collide(ball, bricks, &mut collisions) {
+            ball.y -= dy;
+            ball.dy = -ball.dy;
+        } else if ball.dy > 0 && bottom <= PADDLE_Y && ball.rect().overlaps(&paddle.rect()) {
+            // Only from above, past its top the paddle has missed
+            ball.y = (PADDLE_Y - BALL_SIZE) * SUBPIXELS;
+            paddle.bounce(ball, speed);
+            collisions.paddle = true;
+            return collisions;
+        }
+
+        if ball.rect().y + BALL_SIZE > BOTTOM {
+            collisions.lost = true;
+            return collisions;
+        }
+    }
+    collisions
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerUpKind {
+    Wide,
+    Multi,
+    Slow,
+}
+
+/// Dropped by a brick, falling towards the paddle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    /// Top left corner, `y` in subpixels.
+    pub x: i32,
+    pub y: i32,
+}
+
+impl PowerUp {
+    /// Dropped from the middle of the brick at `column`, `row`.
+    pub fn new(kind: PowerUpKind, column: usize, row: usize) -> Self {
+        let brick = Bricks::rect(column, row);
+        Self {
+            kind,
+            x: brick.x + (brick.width - POWER_UP_SIZE) / 2,
+            y: (brick.y + (brick.height - POWER_UP_SIZE) / 2) * SUBPIXELS,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y.div_euclid(SUBPIXELS), POWER_UP_SIZE, POWER_UP_SIZE)
+    }
+
+    /// Falls for a frame: `Some(true)` once `paddle` catches it, `Some(false)`
+    /// once it's gone past.
+    pub fn fall(&mut self, paddle: &Paddle) -> Option<bool> {
+        self.y += POWER_UP_SPEED;
+        if self.rect().overlaps(&paddle.rect()) {
+            Some(true)
+        } else if self.rect().y >= BOTTOM {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: i32 = SUBPIXELS;
+
+    #[test]
+    fn square_roots() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(S * S), S);
+        assert_eq!(isqrt(2 * S * S), 362);
+    }
+
+    #[test]
+    fn paddle_directions_are_unit_long() {
+        for (dx, dy) in PADDLE_DIRECTIONS {
+            let length = isqrt(dx * dx + dy * dy);
+            assert!((S - 1..=S + 1).contains(&length), "{}", length);
+        }
+    }
+
+    #[test]
+    fn bounces_off_walls() {
+        let paddle = Paddle::new(10);
+        let mut bricks = Bricks::new();
+
+        let mut ball = Ball::new(LEFT * S, 30 * S, -S, S / 2);
+        let collisions = step(&mut ball, &paddle, &mut bricks, S);
+        assert!(collisions.wall);
+        assert_eq!((ball.dx, ball.dy), (S, S / 2));
+        assert_eq!(ball.x, LEFT * S);
+        assert_eq!(ball.y, 30 * S + S / 2);
+
+        let mut ball = Ball::new((RIGHT - BALL_SIZE) * S, 20 * S, S, -S);
+        assert!(step(&mut ball, &paddle, &mut bricks, S).wall);
+        assert_eq!((ball.dx, ball.dy), (-S, -S));
+
+        let mut ball = Ball::new(40 * S, TOP * S, S / 4, -S);
+        assert!(step(&mut ball, &paddle, &mut bricks, S).wall);
+        assert_eq!((ball.dx, ball.dy), (S / 4, S));
+        assert_eq!(ball.y, TOP * S);
+    }
+
+    #[test]
+    fn bounces_into_corner() {
+        let mut ball = Ball::new(LEFT * S, TOP * S, -S, -S);
+        step(&mut ball, &Paddle::new(10), &mut Bricks::new(), S);
+        assert_eq!((ball.dx, ball.dy), (S, S));
+        assert_eq!((ball.x, ball.y), (LEFT * S, TOP * S));
+    }
+
+    #[test]
+    fn brick_below_bounces_down() {
+        let mut bricks = Bricks::new();
+        bricks.set(3, 5, 2);
+        let brick = Bricks::rect(3, 5);
+        // Right under the brick, going up
+        let mut ball = Ball::new((brick.x + 2) * S, (brick.y + brick.height) * S, S / 2, -S);
+        let collisions = step(&mut ball, &Paddle::new(10), &mut bricks, S);
+        assert_eq!(collisions.bricks[..], [BrickHit { column: 3, row: 5, hits: 1 }]);
+        assert!(!collisions.wall);
+        assert_eq!((ball.dx, ball.dy), (S / 2, S));
+        assert_eq!(bricks.get(3, 5), 1);
+
+        // Not hit twice in the same frame
+        let mut ball = Ball::new((brick.x + 2) * S, (brick.y + brick.height) * S, 0, -3 * S);
+        let collisions = step(&mut ball, &Paddle::new(10), &mut bricks, S);
+        assert_eq!(collisions.bricks.len(), 1);
+        assert_eq!(bricks.get(3, 5), 0);
+        assert!(bricks.cleared());
+    }
+
+    #[test]
+    fn brick_side_bounces_back() {
+        let mut bricks = Bricks::new();
+        bricks.set(4, 2, 1);
+        let brick = Bricks::rect(4, 2);
+        // Coming from the left, level with the brick
+        let mut ball = Ball::new((brick.x - BALL_SIZE) * S, brick.y * S, S, 0);
+        let collisions = step(&mut ball, &Paddle::new(10), &mut bricks, S);
+        assert_eq!(collisions.bricks[..], [BrickHit { column: 4, row: 2, hits: 0 }]);
+        assert_eq!((ball.dx, ball.dy), (-S, 0));
+        assert_eq!(ball.x, (brick.x - BALL_SIZE) * S);
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel() {
+        let mut bricks = Bricks::new();
+        bricks.set(0, 0, 1);
+        let brick = Bricks::rect(0, 0);
+        // Four pixels a frame, straight up into a three pixel brick
+        let mut ball = Ball::new(brick.x * S, (brick.y + brick.height + 1) * S, 0, -4 * S);
+        let collisions = step(&mut ball, &Paddle::new(10), &mut bricks, S);
+        assert_eq!(collisions.bricks.len(), 1);
+        assert!(ball.dy > 0);
+    }
+
+    #[test]
+    fn unbreakable_bricks_stay() {
+        let mut bricks = Bricks::new();
+        bricks.set(0, 0, UNBREAKABLE);
+        bricks.set(1, 0, 1);
+        let brick = Bricks::rect(0, 0);
+        let mut ball = Ball::new(brick.x * S, (brick.y + brick.height) * S, 0, -S);
+        let collisions = step(&mut ball, &Paddle::new(10), &mut bricks, S);
+        assert!(collisions.wall && collisions.bricks.is_empty());
+        assert_eq!(bricks.get(0, 0), UNBREAKABLE);
+        assert!(!bricks.cleared());
+        bricks.set(1, 0, 0);
+        assert!(bricks.cleared());
+    }
+
+    #[test]
+    fn paddle_angles() {
+        let paddle = Paddle::new(12);
+        let left = paddle.rect().x;
+        let mut bricks = Bricks::new();
+        for (offset, direction) in [(0, 0), (5, 2), (6, 3), (11, 5)] {
+            // Ball resting on the paddle, its middle at `offset`
+            let mut ball = Ball::new((left + offset - BALL_SIZE / 2) * S, (PADDLE_Y - BALL_SIZE) * S, 0, S);
+            let collisions = step(&mut ball, &paddle, &mut bricks, 2 * S);
+            assert!(collisions.paddle, "{}", offset);
+            let (dx, dy) = PADDLE_DIRECTIONS[direction];
+            assert_eq!((ball.dx, ball.dy), (2 * dx, 2 * dy), "{}", offset);
+            assert_eq!(ball.y, (PADDLE_Y - BALL_SIZE) * S);
+        }
+    }
+
+    #[test]
+    fn missed_ball_is_lost() {
+        let paddle = Paddle::new(12);
+        let mut ball = Ball::new(LEFT * S, (BOTTOM - BALL_SIZE) * S, 0, S);
+        let mut bricks = Bricks::new();
+        assert!(step(&mut ball, &paddle, &mut bricks, S).lost);
+
+        // Below the top of the paddle, it's too late to bounce
+        let mut ball = Ball::new(paddle.x, (PADDLE_Y - 1) * S, 0, S);
+        let collisions = step(&mut ball, &paddle, &mut bricks, S);
+        assert!(!collisions.paddle);
+    }
+
+    #[test]
+    fn paddle_stays_between_walls() {
+        let mut paddle = Paddle::new(12);
+        paddle.slide(-100 * S);
+        assert_eq!(paddle.rect().x, LEFT);
+        paddle.slide(200 * S);
+        assert_eq!(paddle.rect().x + paddle.width, RIGHT);
+        paddle.set_width(20);
+        assert_eq!(paddle.rect().x + paddle.width, RIGHT);
+        paddle.set_width(12);
+        assert_eq!(paddle.rect().x, RIGHT - 20 + 4);
+    }
+
+    #[test]
+    fn speeds() {
+        let mut ball = Ball::new(0, 0, 3 * S, -4 * S);
+        assert_eq!(ball.speed(), 5 * S);
+        ball.set_speed(S);
+        assert_eq!((ball.dx, ball.dy), (3 * S / 5, -4 * S / 5));
+
+        for split in ball.split() {
+            assert!(split.dy < 0);
+            assert!((S - 2..=S + 2).contains(&split.speed()));
+        }
+        assert!(ball.split()[0].dx < 0 && ball.split()[1].dx > 0);
+    }
+
+    #[test]
+    fn power_ups_fall_onto_paddle() {
+        let paddle = Paddle::new(12);
+        let column = (paddle.rect().x - BRICKS_X) as usize / BRICK_WIDTH as usize;
+        let mut power_up = PowerUp::new(PowerUpKind::Wide, column, 0);
+        let caught = (0..200).find_map(|_| power_up.fall(&paddle));
+        assert_eq!(caught, Some(true));
+
+        let mut power_up = PowerUp::new(PowerUpKind::Slow, COLUMNS - 1, 0);
+        let caught = (0..200).find_map(|_| power_up.fall(&Paddle::new(12)));
+        assert_eq!(caught, Some(false));
+    }
+}
@@ -1,33 +1,44 @@
-use cortex_m::delay::Delay;
-
-use crate::inputs::Inputs;
 use crate::menu::{Menu, MenuOption};
-use crate::pcd8544::PCD8544;
+use crate::display::Display;
+use crate::lang;
 
-use super::snake::SnakeGame;
+use super::breakout::BreakoutGame;
+use super::snake::{SnakeGame, SnakeInput};
+use super::tetris::TetrisGame;
 
 #[derive(Clone, Copy)]
 enum GameSelected {
-    Snake, PingPong, Quit
+    Snake, Breakout, Tetris, PingPong, Quit
 }
 
 pub struct GamesMenu;
 
 impl GamesMenu {
-    pub fn run(pcd: &mut PCD8544, inputs: &mut Inputs, delay: &mut Delay) {
+    pub async fn run<D: Display, I: SnakeInput>(pcd: &mut D, inputs: &mut I) {
+        let strings = lang::strings();
         let mut game_menu = Menu::new(
-            "Graj",
+            strings.play,
             [
-                MenuOption::new(GameSelected::Snake, "Wensz"),
-                MenuOption::new(GameSelected::PingPong, "PingPong"),
-                MenuOption::new(GameSelected::Quit, "Wyjdz"),
+                MenuOption::new(GameSelected::Snake, strings.snake),
+                MenuOption::new(GameSelected::Breakout, strings.breakout),
+                MenuOption::new(GameSelected::Tetris, strings.tetris),
+                MenuOption::new(GameSelected::PingPong, strings.ping_pong),
+                MenuOption::new(GameSelected::Quit, strings.quit),
             ]
             );
 
-        match game_menu.run(pcd, inputs, delay) {
+        match game_menu.run(pcd, inputs).await {
             GameSelected::Snake => {
-                let mut snake_game = SnakeGame::new(pcd, inputs, delay);
-                snake_game.run();
+                let mut snake_game = SnakeGame::new(pcd, inputs);
+                snake_game.run().await;
+            },
+            GameSelected::Breakout => {
+                let mut breakout_game = BreakoutGame::new(pcd, inputs);
+                breakout_game.run().await;
+            },
+            GameSelected::Tetris => {
+                let mut tetris_game = TetrisGame::new(pcd, inputs);
+                tetris_game.run().await;
             },
             GameSelected::PingPong => {
 
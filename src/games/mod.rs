@@ -1,2 +1,49 @@
+//! The games, and the menu picking one.
+
+pub mod attract;
+pub mod breakout;
 pub mod games_menu;
 pub mod snake;
+pub mod snake_ai;
+pub mod tetris;
+
+use crate::audio::{self, melodies};
+use crate::display::Display;
+use crate::inputs::Input;
+use crate::power::IdleTimer;
+use crate::sfx::transition::{Transition, TransitionKind};
+use crate::tasks::Ticker;
+
+const GAME_OVER_FRAME_MS: u32 = 20;
+
+/// Shakes the last frame of a game and fades it to inverted, then waits for
+/// a press, or for `inputs` to be interrupted.
+pub async fn game_over<D: Display, I: Input>(pcd: &mut D, inputs: &mut I) {
+    audio::play_melody(melodies::GAME_OVER, false).unwrap();
+    let lost = *pcd.buffer();
+    pcd.inverse();
+    let inverted = *pcd.buffer();
+    let mut effects = [
+        Transition::new(TransitionKind::Shake, &lost, &lost, 12),
+        Transition::new(TransitionKind::Dissolve, &lost, &inverted, 15),
+    ];
+    let mut effect = 0;
+    let mut idle_timer = IdleTimer::new();
+    let mut ticker = Ticker::every(GAME_OVER_FRAME_MS);
+    loop {
+        if effect < effects.len() {
+            if !effects[effect].step(pcd) {
+                effect += 1;
+            }
+            pcd.draw();
+        }
+
+        inputs.update();
+        if inputs.interrupted() || inputs.any_pressed() {
+            return;
+        }
+        idle_timer.update(pcd, inputs).await;
+
+        ticker.tick().await;
+    }
+}
@@ -1,34 +1,75 @@
-use cortex_m::delay::Delay;
 use embedded_graphics::Drawable;
 use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::mono_font::iso_8859_14::FONT_5X7;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::{Point, Size, Primitive};
 use embedded_graphics::primitives::{Rectangle, PrimitiveStyle};
 use embedded_graphics::text::Text;
 use heapless::{String, Vec};
 
-use crate::inputs::Inputs;
-use crate::pcd8544::PCD8544;
+use crate::assets;
+use crate::audio::{self, effects::Effect};
+use crate::battery::{self, Level};
+use crate::display::Display;
+use crate::fonts::FONT_5X7;
+use crate::inputs::{Buttons, Input};
 use crate::rand::Rand;
-use crate::sfx::inverse_blink::inverse_blink;
+use crate::sfx::particles::Particles;
+use crate::sfx::transition::{Transition, TransitionKind};
+use crate::sprite::Flip;
+use crate::tasks::Ticker;
 
+/// Length at which the snake has won, the game ends there.
 const MAX_SIZE: usize = 100;
+const FRAME_MS: u32 = 30;
+/// Frames between two moves of the snake.
+const TICK_FRAMES: u8 = 10;
+/// Blink period of the low battery warning.
+const WARNING_FRAMES: u32 = 30;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum Direction {
+pub enum Direction {
     Left,
     Down,
     Up,
     Right,
 }
 
+impl Direction {
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Left  => Direction::Right,
+            Direction::Down  => Direction::Up,
+            Direction::Up    => Direction::Down,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    pub fn offset(self) -> Point {
+        match self {
+            Direction::Left  => Point::new(-1, 0),
+            Direction::Down  => Point::new(0, 1),
+            Direction::Up    => Point::new(0, -1),
+            Direction::Right => Point::new(1, 0),
+        }
+    }
+
+    /// Index of the button that turns the snake this way.
+    pub fn button(self) -> usize {
+        match self {
+            Direction::Left  => 0,
+            Direction::Down  => 1,
+            Direction::Up    => 2,
+            Direction::Right => 3,
+        }
+    }
+}
+
 const SCALE:  usize = 2;
-const GRID_X: usize = 84/SCALE;
-const GRID_Y: usize = 48/SCALE;
+pub const GRID_X: usize = 84/SCALE;
+pub const GRID_Y: usize = 48/SCALE;
 
 #[derive(Clone, Copy, Debug)]
-struct Tile {
+pub struct Tile {
     pub pos: Point,
     pub dir: Direction,
 }
@@ -55,11 +96,9 @@ impl Snake {
         self.tail[0].dir = dir;
     }
 
-    pub fn draw(&self, pcd: &mut PCD8544) {
+    pub fn draw<D: Display>(&self, pcd: &mut D) {
         for tile in &self.tail {
-            Rectangle::new(tile.pos * SCALE as i32, Size::new(SCALE as u32, SCALE as u32))
-                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-                .draw(pcd).unwrap();
+            assets::CELL.draw(pcd, tile.pos * SCALE as i32, Flip::NONE);
         }
     }
 
@@ -104,10 +143,12 @@ impl Snake {
             core::mem::swap(&mut self.tail[i].dir, &mut next_dir);
         }
 
-        if let Some(mut stale) = self.stale { // assume stale can't be > 1
+        if let Some(mut stale) = self.stale.take() { // assume stale can't be > 1
             stale.dir = next_dir;
-            self.tail.push(stale).unwrap();
-            self.stale = None;
+            // As long as it gets, the game is over all the same
+            if self.tail.push(stale).is_err() {
+                return false;
+            }
         }
 
         true
@@ -140,46 +181,74 @@ impl Apple {
         }
     }
 
-    pub fn draw(&mut self, pcd: &mut PCD8544) {
-        if self.tick == 2 {
-            Rectangle::new(self.pos * SCALE as i32, Size::new(SCALE as u32, SCALE as u32))
-                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
-                .draw(pcd).unwrap();
+    pub fn draw<D: Display>(&mut self, pcd: &mut D) {
+        // Shown for one move out of three
+        if self.tick >= 2*TICK_FRAMES {
+            assets::CELL.draw(pcd, self.pos * SCALE as i32, Flip::NONE);
+        }
+
+        self.tick += 1;
+        if self.tick == 3*TICK_FRAMES {
             self.tick = 0;
-        } else {
-            self.tick += 1;
         }
     }
 }
 
-pub struct SnakeGame<'a> {
-    pcd:    &'a mut PCD8544,
-    inputs: &'a mut Inputs,
-    delay:  &'a mut Delay,
+/// What a player gets to see of the game before each move.
+pub struct Board<'a> {
+    pub tail:  &'a [Tile],
+    pub apple: Point,
+}
+
+impl<'a> Board<'a> {
+    pub fn head(&self) -> Tile {
+        self.tail[0]
+    }
+
+    /// Whether moving the head onto `pos` ends the game.
+    pub fn is_blocked(&self, pos: Point) -> bool {
+        pos.x < 1 || pos.x > GRID_X as i32 - 2 ||
+        pos.y < 1 || pos.y > GRID_Y as i32 - 2 ||
+        self.tail.iter().any(|tile| tile.pos == pos)
+    }
+}
+
+pub trait SnakeInput: Input {
+    /// Called every tick, before the inputs are updated.
+    fn observe(&mut self, _board: &Board) {}
+}
+
+impl SnakeInput for Buttons {}
+
+pub struct SnakeGame<'a, D: Display, I: SnakeInput> {
+    pcd:    &'a mut D,
+    inputs: &'a mut I,
     snake: Snake,
     apple: Apple,
     rand:  Rand,
+    particles: Particles<16>,
+    frame: u32,
 }
 
-impl<'a> SnakeGame<'a> {
+impl<'a, D: Display, I: SnakeInput> SnakeGame<'a, D, I> {
     pub fn new(
-        pcd:    &'a mut PCD8544,
-        inputs: &'a mut Inputs,
-        delay:  &'a mut Delay
+        pcd:    &'a mut D,
+        inputs: &'a mut I
         ) -> Self {
         Self{
             pcd,
             inputs,
-            delay,
             snake: Snake::new(),
             apple: Apple::new(),
-            rand:  Rand::new(25023540),
+            rand:  Rand::from_entropy(),
+            particles: Particles::new(),
+            frame: 0,
         }
     }
 
     fn make_apple(&mut self) {
-        self.apple.pos.x = ((self.rand.next() as usize % (GRID_X-5)) + 2) as i32;
-        self.apple.pos.y = ((self.rand.next() as usize % (GRID_Y-5)) + 2) as i32;
+        self.apple.pos.x = self.rand.range(2, GRID_X as u32 - 3) as i32;
+        self.apple.pos.y = self.rand.range(2, GRID_Y as u32 - 3) as i32;
         if self.apple.pos.x % 2 == 1 {
             self.apple.pos.x += 1;
         }
@@ -189,20 +258,39 @@ impl<'a> SnakeGame<'a> {
         }
     }
 
-    pub fn run(&mut self) {
-        self.pcd.clear();
+    pub async fn run(&mut self) {
+        audio::stop_music();
         self.make_apple();
+
+        let from = *self.pcd.buffer();
+        self.render();
+        let mut slide = Transition::new(TransitionKind::Slide, &from, self.pcd.buffer(), 15);
+        let mut ticker = Ticker::every(FRAME_MS);
+        while slide.step(self.pcd) {
+            self.pcd.draw();
+            ticker.tick().await;
+        }
+
+        let mut frame = 0;
         loop {
-            if !self.update() {
+            if frame == 0 && (self.inputs.interrupted() || !self.update().await) {
                 return;
             }
-            self.draw();
+            frame = (frame + 1) % TICK_FRAMES;
+
+            self.particles.step();
+            self.render();
+            self.pcd.draw();
 
-            self.delay.delay_ms(300);
+            ticker.tick().await;
         }
     }
 
-    fn update(&mut self) -> bool {
+    async fn update(&mut self) -> bool {
+        self.inputs.observe(&Board {
+            tail:  &self.snake.tail,
+            apple: self.apple.pos,
+        });
         self.inputs.update();
         let inputs = self.inputs.is_pressed();
         if inputs[0] && self.snake.get_dir() != Direction::Right {
@@ -216,30 +304,24 @@ impl<'a> SnakeGame<'a> {
         }
 
         if self.snake.eat_apple(self.apple.pos) {
-            inverse_blink(self.pcd, self.delay, 500, 1);
+            audio::play(Effect::Apple);
+            let center = self.apple.pos * SCALE as i32 + Point::new(1, 1);
+            self.particles.burst(center, 12, &mut self.rand);
             self.make_apple();
         }
 
         if !self.snake.update() {
-            self.pcd.inverse();
-            self.pcd.draw();
-            loop {
-                self.inputs.update();
-                match self.inputs.is_pressed().iter().find(|x| **x) {
-                    Some(_) => { return false; },
-                    None => {}
-                }
-
-                self.delay.delay_ms(20);
-            }
+            super::game_over(self.pcd, self.inputs).await;
+            return false;
         }
 
         true
     }
 
-    fn draw(&mut self) {
+    /// Draws the game into the buffer, `Display::draw` sends it.
+    fn render(&mut self) {
         // Clear
-        self.pcd.clear();
+        self.pcd.clear_buffer();
 
         // Border
         Rectangle::new(Point::new(0,0), Size::new(84, 48))
@@ -253,18 +335,79 @@ impl<'a> SnakeGame<'a> {
         self.apple.draw(self.pcd);
 
         // Score
-        let x_size = if self.snake.tail.len() > 9 {
-            10
-        } else {
-            5
-        };
+        let score = String::<3>::from(self.snake.tail.len() as u32);
+        let x_size = 5 * score.len() as u32;
 
         Rectangle::new(Point::new(0,45-7), Size::new(x_size+3, 7+3))
             .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
             .draw(self.pcd).unwrap();
-        Text::new(&String::<3>::from(self.snake.tail.len() as u32), Point::new(2,45), MonoTextStyle::new(&FONT_5X7, BinaryColor::On))
+        Text::new(&score, Point::new(2,45), MonoTextStyle::new(&FONT_5X7, BinaryColor::On))
             .draw(self.pcd).unwrap();
 
-        self.pcd.draw();
+        // Effects
+        self.particles.draw(self.pcd);
+
+        // Low battery warning
+        self.frame = self.frame.wrapping_add(1);
+        let low = matches!(battery::level(), Level::Low | Level::Critical);
+        if low && self.frame % WARNING_FRAMES < WARNING_FRAMES / 2 {
+            battery::draw_icon(self.pcd, Point::new((84 - battery::ICON_WIDTH - 2) as i32, 2));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A snake of `len` tiles winding down from the top of the board,
+    /// its head at the top heading left.
+    fn winding_snake(len: usize) -> Snake {
+        let mut cells = std::vec::Vec::new();
+        for y in 1..GRID_Y as i32 - 1 {
+            let row = (1..GRID_X as i32 - 1).map(|x| Point::new(x, y));
+            if y == 1 {
+                cells.extend(row.skip(GRID_X / 2));
+            } else if y % 2 == 1 {
+                cells.extend(row);
+            } else {
+                cells.extend(row.rev());
+            }
+        }
+
+        let mut snake = Snake::new();
+        snake.tail.clear();
+        for (i, pos) in cells.iter().take(len).enumerate() {
+            // Each tile follows the one before it
+            let dir = if i == 0 {
+                Direction::Left
+            } else {
+                [Direction::Left, Direction::Down, Direction::Up, Direction::Right].into_iter()
+                    .find(|dir| *pos + dir.offset() == cells[i - 1])
+                    .unwrap()
+            };
+            snake.tail.push(Tile { pos: *pos, dir }).unwrap();
+        }
+        snake
+    }
+
+    #[test]
+    fn grows_after_an_apple() {
+        let mut snake = winding_snake(MAX_SIZE - 1);
+        let head = snake.tail[0].pos;
+        assert!(snake.eat_apple(head));
+        assert!(snake.update());
+        assert_eq!(snake.tail.len(), MAX_SIZE);
+        assert_eq!(snake.tail[0].pos, head + Point::new(-1, 0));
+    }
+
+    #[test]
+    fn full_length_ends_the_game() {
+        let mut snake = winding_snake(MAX_SIZE);
+        assert!(snake.update());
+        let head = snake.tail[0].pos;
+        assert!(snake.eat_apple(head));
+        assert!(!snake.update());
+        assert_eq!(snake.tail.len(), MAX_SIZE);
     }
 }
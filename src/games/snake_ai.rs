@@ -0,0 +1,309 @@
+//! Computer player for the snake, playing the attract mode.
+//!
+//! It takes the shortest way to the apple, as long as the snake has room
+//! left once it gets there. Otherwise it follows a Hamiltonian cycle, a
+//! loop through every cell of the board, which can't box in a snake lying
+//! along it. Only when the cycle is blocked too does it head for the most
+//! room.
+
+use embedded_graphics::prelude::Point;
+
+use crate::inputs::Input;
+
+use super::snake::{Board, Direction, SnakeInput, GRID_X, GRID_Y};
+
+const CELLS: usize = GRID_X*GRID_Y;
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Left, Direction::Down, Direction::Up, Direction::Right,
+];
+/// Updates spent on the game over screen before the AI dismisses it.
+const GAME_OVER_UPDATES: u32 = 50;
+
+// `cycle_step` closes the loop on a row going left
+const _: () = assert!((GRID_Y - 2).is_multiple_of(2));
+
+fn index(pos: Point) -> usize {
+    pos.y as usize * GRID_X + pos.x as usize
+}
+
+fn point(index: usize) -> Point {
+    Point::new((index % GRID_X) as i32, (index / GRID_X) as i32)
+}
+
+/// Next step around the cycle through every cell inside the border: right
+/// along the top row, then back and forth below it leaving the first
+/// column out, and up that column.
+fn cycle_step(pos: Point) -> Direction {
+    let (right, bottom) = (GRID_X as i32 - 2, GRID_Y as i32 - 2);
+    if pos.y == 1 {
+        if pos.x < right { Direction::Right } else { Direction::Down }
+    } else if pos.x == 1 {
+        Direction::Up
+    } else if pos.y % 2 == 0 {
+        // Rows going left, the last one on into the first column
+        if pos.x > 2 || pos.y == bottom { Direction::Left } else { Direction::Down }
+    } else if pos.x < right {
+        Direction::Right
+    } else {
+        Direction::Down
+    }
+}
+
+/// Computer player for `SnakeGame`, presses the same buttons a human would.
+///
+/// Wraps another input source and stops the game as soon as
+/// any of its buttons is pressed.
+pub struct SnakeAi<'a, I: Input> {
+    buttons: &'a mut I,
+    plan: Option<Direction>,
+    pressed: [bool; 4],
+    idle: u32,
+    interrupted: bool,
+}
+
+impl<'a, I: Input> SnakeAi<'a, I> {
+    pub fn new(buttons: &'a mut I) -> Self {
+        Self {
+            buttons,
+            plan: None,
+            pressed: [false; 4],
+            idle: 0,
+            interrupted: false,
+        }
+    }
+
+    /// Breadth-first search from the head to the apple, returns the first
+    /// step of the shortest path that doesn't lead into a dead end.
+    fn path_to_apple(board: &Board) -> Option<Direction> {
+        let head = board.head();
+        let mut first = [None; CELLS];
+        let mut visited = [false; CELLS];
+        let mut queue = [0u16; CELLS];
+        let (mut read, mut write) = (0, 0);
+
+        visited[index(head.pos)] = true;
+        for dir in DIRECTIONS {
+            let next = head.pos + dir.offset();
+            if dir == head.dir.opposite() || board.is_blocked(next) || !Self::has_room(board, next) {
+                continue;
+            }
+            visited[index(next)] = true;
+            first[index(next)] = Some(dir);
+            queue[write] = index(next) as u16;
+            write += 1;
+        }
+
+        while read < write {
+            let current = queue[read] as usize;
+            read += 1;
+            if point(current) == board.apple {
+                return first[current];
+            }
+
+            for dir in DIRECTIONS {
+                let next = point(current) + dir.offset();
+                if board.is_blocked(next) || visited[index(next)] {
+                    continue;
+                }
+                visited[index(next)] = true;
+                first[index(next)] = first[current];
+                queue[write] = index(next) as u16;
+                write += 1;
+            }
+        }
+
+        None
+    }
+
+    /// Counts the cells reachable from `start`.
+    fn free_space(board: &Board, start: Point) -> usize {
+        let mut visited = [false; CELLS];
+        let mut stack = [0u16; CELLS];
+        let mut len = 1;
+        let mut count = 0;
+
+        stack[0] = index(start) as u16;
+        visited[index(start)] = true;
+        while len > 0 {
+            len -= 1;
+            let current = point(stack[len] as usize);
+            count += 1;
+
+            for dir in DIRECTIONS {
+                let next = current + dir.offset();
+                if board.is_blocked(next) || visited[index(next)] {
+                    continue;
+                }
+                visited[index(next)] = true;
+                stack[len] = index(next) as u16;
+                len += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Whether the snake still fits in the space around `next`.
+    fn has_room(board: &Board, next: Point) -> bool {
+        Self::free_space(board, next) >= board.tail.len()
+    }
+
+    /// No safe way to the apple, keep going around the cycle.
+    fn follow_cycle(board: &Board) -> Option<Direction> {
+        let head = board.head();
+        let dir = cycle_step(head.pos);
+        let next = head.pos + dir.offset();
+        (dir != head.dir.opposite() && !board.is_blocked(next) && Self::has_room(board, next)).then_some(dir)
+    }
+
+    /// Not even along the cycle, stall in the direction with the most room left.
+    fn survive(board: &Board) -> Option<Direction> {
+        let head = board.head();
+        DIRECTIONS.iter()
+            .filter(|dir| **dir != head.dir.opposite())
+            .map(|dir| (*dir, head.pos + dir.offset()))
+            .filter(|(_, next)| !board.is_blocked(*next))
+            .max_by_key(|(_, next)| Self::free_space(board, *next))
+            .map(|(dir, _)| dir)
+    }
+}
+
+impl<'a, I: Input> Input for SnakeAi<'a, I> {
+    fn update(&mut self) {
+        self.buttons.update();
+        if self.buttons.any_pressed() {
+            self.interrupted = true;
+        }
+
+        self.pressed = [false; 4];
+        match self.plan.take() {
+            Some(dir) => {
+                self.pressed[dir.button()] = true;
+                self.idle = 0;
+            },
+            None => {
+                // Not asked for a move, so the game must be over
+                self.idle += 1;
+                if self.idle >= GAME_OVER_UPDATES {
+                    self.pressed[0] = true;
+                    self.idle = 0;
+                }
+            }
+        }
+    }
+
+    fn is_pressed(&self) -> &[bool; 4] {
+        &self.pressed
+    }
+
+    fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+}
+
+impl<'a, I: Input> SnakeInput for SnakeAi<'a, I> {
+    fn observe(&mut self, board: &Board) {
+        self.plan = Self::path_to_apple(board)
+            .or_else(|| Self::follow_cycle(board))
+            .or_else(|| Self::survive(board))
+            .or(Some(board.head().dir));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use crate::games::snake::Tile;
+    use crate::rand::Rand;
+
+    /// Buttons nobody presses.
+    struct Untouched([bool; 4]);
+
+    impl Input for Untouched {
+        fn update(&mut self) {}
+
+        fn is_pressed(&self) -> &[bool; 4] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn cycle_visits_every_cell() {
+        let start = Point::new(1, 1);
+        let mut pos = start;
+        let mut visited = [false; CELLS];
+        for _ in 0..(GRID_X - 2) * (GRID_Y - 2) {
+            assert!(!visited[index(pos)], "{:?} twice", pos);
+            visited[index(pos)] = true;
+            pos += cycle_step(pos).offset();
+            assert!((1..GRID_X as i32 - 1).contains(&pos.x) && (1..GRID_Y as i32 - 1).contains(&pos.y), "{:?}", pos);
+        }
+        assert_eq!(pos, start);
+    }
+
+    /// Moves the snake like `SnakeGame` does, with the AI at the buttons,
+    /// for `updates` moves or until it is `length` tiles long. Returns the
+    /// apples eaten.
+    fn play(seed: u32, length: usize, updates: usize) -> usize {
+        let mut rand = Rand::new(seed);
+        let mut untouched = Untouched([false; 4]);
+        let mut ai = SnakeAi::new(&mut untouched);
+        let mut snake = Vec::from([Point::new(GRID_X as i32 / 2 - 1, GRID_Y as i32 / 2)]);
+        let mut dir = Direction::Right;
+        // On even cells like `SnakeGame::make_apple` puts them
+        let mut apple = || {
+            let x = rand.range(2, GRID_X as u32 - 3) as i32;
+            let y = rand.range(2, GRID_Y as u32 - 3) as i32;
+            Point::new(x + x % 2, y + y % 2)
+        };
+        let mut apple_pos = apple();
+        let mut eaten = 0;
+
+        for update in 0..updates {
+            if snake.len() >= length {
+                break;
+            }
+            let tail: Vec<Tile> = snake.iter().map(|pos| Tile { pos: *pos, dir }).collect();
+            let board = Board { tail: &tail, apple: apple_pos };
+            ai.observe(&board);
+            ai.update();
+
+            let pressed: Vec<usize> = (0..4).filter(|button| ai.is_pressed()[*button]).collect();
+            assert_eq!(pressed.len(), 1, "update {}", update);
+            let next_dir = DIRECTIONS.into_iter().find(|next| next.button() == pressed[0]).unwrap();
+            assert_ne!(next_dir, dir.opposite(), "reversed at update {}", update);
+            dir = next_dir;
+
+            let head = snake[0] + dir.offset();
+            assert!(!board.is_blocked(head), "ran into {:?} at update {}, {} long", head, update, snake.len());
+            snake.insert(0, head);
+            if head == apple_pos {
+                eaten += 1;
+                // Never under the snake here, so there is always a way to it
+                while snake.contains(&apple_pos) {
+                    apple_pos = apple();
+                }
+            } else {
+                snake.pop();
+            }
+        }
+        eaten
+    }
+
+    #[test]
+    fn plays_without_crashing() {
+        for seed in 0..20 {
+            assert!(play(seed, usize::MAX, 500) >= 1, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn grows_long() {
+        for seed in 0..3 {
+            assert_eq!(play(seed, 90, 100_000), 89, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Tetris, played with the console turned on its side: the well is 10x20
+//! cells of 4 pixels on the 48x84 `Rotated` view.
+//!
+//! The first and last buttons move the piece, the second turns it and the
+//! third drops it a row. Pressed twice quickly, the second holds the piece
+//! and the third drops it all the way. Next to the well are the next piece,
+//! the held one, the level and the score, top to bottom.
+
+pub mod well;
+
+use embedded_graphics::Drawable;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive, Size};
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Baseline, Text};
+use heapless::String;
+use core::fmt::Write;
+
+use crate::audio::{self, effects::Effect};
+use crate::battery::{self, Level};
+use crate::display::Display;
+use crate::fonts::FONT_4X6;
+use crate::inputs::Input;
+use crate::rand::Rand;
+use crate::rotated::Rotated;
+use crate::sfx::transition::{Transition, TransitionKind};
+use crate::tasks::Ticker;
+
+use well::{Bag, Kind, Piece, Well, COLUMNS, LINES_PER_LEVEL, ROWS};
+
+const FRAME_MS: u32 = 20;
+/// Frames within which a second press of a button counts as a double press.
+const DOUBLE_PRESS_FRAMES: u32 = 12;
+/// Blink period of the low battery warning.
+const WARNING_FRAMES: u32 = 30;
+
+/// Pixels of a cell, the last row and column left blank.
+const CELL: i32 = 4;
+/// Top left corner of the well, inside its walls.
+const WELL_X: i32 = 1;
+const WELL_Y: i32 = 0;
+/// Column of the next piece, the held one, the level and the score.
+const PANEL_X: i32 = WELL_X + COLUMNS * CELL + 2;
+const PANEL_WIDTH: i32 = 5;
+/// Pixels of a cell of the pieces in the panel.
+const PREVIEW_CELL: i32 = 2;
+const NEXT_Y: i32 = 0;
+const HOLD_Y: i32 = 11;
+const LEVEL_Y: i32 = 22;
+const SCORE_Y: i32 = 37;
+
+/// A piece standing up, to fit in the panel.
+fn draw_preview<D: Display>(view: &mut Rotated<D>, kind: Kind, y: i32) {
+    let mut blocks = kind.blocks(1);
+    let left = blocks.iter().map(|(x, _)| *x).min().unwrap();
+    let top = blocks.iter().map(|(_, y)| *y).min().unwrap();
+    let width = blocks.iter().map(|(x, _)| *x).max().unwrap() - left + 1;
+    for (x, y) in blocks.iter_mut() {
+        *x -= left;
+        *y -= top;
+    }
+    let origin = Point::new(PANEL_X + (PANEL_WIDTH - width * PREVIEW_CELL) / 2, y);
+    for (x, y) in blocks {
+        Rectangle::new(origin + Point::new(x * PREVIEW_CELL, y * PREVIEW_CELL), Size::new(PREVIEW_CELL as u32, PREVIEW_CELL as u32))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(view).unwrap();
+    }
+}
+
+/// `number` a digit below the other, for the narrow panel.
+fn draw_number<D: Display>(view: &mut Rotated<D>, number: u32, y: i32) {
+    let mut digits = String::<10>::new();
+    let mut column = String::<20>::new();
+    write!(digits, "{}", number).unwrap();
+    for digit in digits.chars() {
+        column.push(digit).unwrap();
+        column.push('\n').unwrap();
+    }
+    Text::with_baseline(&column, Point::new(PANEL_X, y), MonoTextStyle::new(&FONT_4X6, BinaryColor::On), Baseline::Top)
+        .draw(view).unwrap();
+}
+
+pub struct TetrisGame<'a, D: Display, I: Input> {
+    pcd:    &'a mut D,
+    inputs: &'a mut I,
+    bag:   Bag,
+    well:  Well,
+    piece: Piece,
+    next:  Kind,
+    hold:  Option<Kind>,
+    /// Whether the piece falling was swapped with the held one already.
+    held:  bool,
+    score: u32,
+    lines: u32,
+    level: u32,
+    /// Frames since the piece last fell a row.
+    falling: u32,
+    /// Frame of the last press of each button, unless it made a double press.
+    pressed_at: [Option<u32>; 4],
+    frame: u32,
+}
+
+impl<'a, D: Display, I: Input> TetrisGame<'a, D, I> {
+    pub fn new(pcd: &'a mut D, inputs: &'a mut I) -> Self {
+        let mut bag = Bag::new(Rand::from_entropy());
+        let piece = Piece::spawn(bag.next());
+        let next = bag.next();
+        Self {
+            pcd,
+            inputs,
+            bag,
+            well: Well::new(),
+            piece,
+            next,
+            hold: None,
+            held: false,
+            score: 0,
+            lines: 0,
+            level: 1,
+            falling: 0,
+            pressed_at: [None; 4],
+            frame: 0,
+        }
+    }
+
+    pub async fn run(&mut self) {
+        audio::stop_music();
+
+        let from = *self.pcd.buffer();
+        self.render();
+        let mut slide = Transition::new(TransitionKind::Slide, &from, self.pcd.buffer(), 15);
+        let mut ticker = Ticker::every(FRAME_MS);
+        while slide.step(self.pcd) {
+            self.pcd.draw();
+            ticker.tick().await;
+        }
+
+        loop {
+            if !self.update() {
+                super::game_over(self.pcd, self.inputs).await;
+                return;
+            }
+
+            self.render();
+            self.pcd.draw();
+
+            ticker.tick().await;
+        }
+    }
+
+    /// Whether this press of `button` follows another one quickly enough.
+    fn double_press(&mut self, button: usize) -> bool {
+        let double = self.pressed_at[button]
+            .is_some_and(|frame| self.frame - frame <= DOUBLE_PRESS_FRAMES);
+        // A third press starts over
+        self.pressed_at[button] = if double { None } else { Some(self.frame) };
+        double
+    }
+
+    /// `false` once the game is over.
+    fn update(&mut self) -> bool {
+        self.frame += 1;
+        self.inputs.update();
+        let inputs = *self.inputs.is_pressed();
+        if inputs[0] {
+            self.well.shift(&mut self.piece, -1, 0);
+        }
+        if inputs[3] {
+            self.well.shift(&mut self.piece, 1, 0);
+        }
+        if inputs[1] {
+            // The first press turned the piece, but a held one comes back unturned
+            if self.double_press(1) {
+                if !self.hold_piece() {
+                    return false;
+                }
+            } else {
+                self.well.rotate(&mut self.piece);
+            }
+        }
+        if inputs[2] {
+            if self.double_press(2) {
+                let rows = self.well.drop_distance(&self.piece);
+                self.piece.y += rows;
+                self.score += 2 * rows as u32;
+                return self.lock_piece();
+            } else if self.well.shift(&mut self.piece, 0, 1) {
+                self.score += 1;
+                self.falling = 0;
+            }
+        }
+
+        self.falling += 1;
+        if self.falling >= well::gravity_frames(self.level) {
+            self.falling = 0;
+            // Landed pieces lock at the next row they can't fall
+            if !self.well.shift(&mut self.piece, 0, 1) {
+                return self.lock_piece();
+            }
+        }
+        true
+    }
+
+    /// Brings in the next piece, `false` if there is no room for it.
+    fn spawn(&mut self, kind: Kind) -> bool {
+        self.piece = Piece::spawn(kind);
+        self.falling = 0;
+        self.well.fits(&self.piece)
+    }
+
+    /// Swaps the piece with the held one, once for each piece.
+    fn hold_piece(&mut self) -> bool {
+        if self.held {
+            return true;
+        }
+        self.held = true;
+        let kind = match self.hold.replace(self.piece.kind) {
+            Some(kind) => kind,
+            None => {
+                let next = self.next;
+                self.next = self.bag.next();
+                next
+            },
+        };
+        self.spawn(kind)
+    }
+
+    /// Leaves the piece where it is, clears the lines it completes and
+    /// brings in the next one. `false` if the game is over.
+    fn lock_piece(&mut self) -> bool {
+        if !self.well.lock(&self.piece) {
+            return false;
+        }
+
+        let lines = self.well.clear_lines();
+        if lines > 0 {
+            audio::play(Effect::Lines);
+            self.score += well::line_score(lines, self.level);
+            self.lines += lines;
+            self.level = 1 + self.lines / LINES_PER_LEVEL;
+        } else {
+            audio::play(Effect::Bounce);
+        }
+
+        self.held = false;
+        let next = self.next;
+        self.next = self.bag.next();
+        self.spawn(next)
+    }
+
+    /// Draws the game into the buffer, `Display::draw` sends it.
+    fn render(&mut self) {
+        let on = BinaryColor::On;
+        self.pcd.clear_buffer();
+        let view = &mut Rotated::new(&mut *self.pcd);
+
+        // Well, open at the top
+        let wall = PrimitiveStyle::with_stroke(on, 1);
+        let (right, bottom) = (WELL_X + COLUMNS * CELL, WELL_Y + ROWS * CELL);
+        Line::new(Point::new(WELL_X - 1, WELL_Y), Point::new(WELL_X - 1, bottom)).into_styled(wall).draw(view).unwrap();
+        Line::new(Point::new(right, WELL_Y), Point::new(right, bottom)).into_styled(wall).draw(view).unwrap();
+        Line::new(Point::new(WELL_X - 1, bottom), Point::new(right, bottom)).into_styled(wall).draw(view).unwrap();
+
+        // Blocks, the cells drawn a pixel short to keep them apart
+        let block = PrimitiveStyle::with_fill(on);
+        let well = Point::new(WELL_X, WELL_Y);
+        for y in 0..ROWS {
+            for x in 0..COLUMNS {
+                if self.well.is_filled(x, y) {
+                    Rectangle::new(well + Point::new(x * CELL, y * CELL), Size::new(CELL as u32 - 1, CELL as u32 - 1))
+                        .into_styled(block)
+                        .draw(view).unwrap();
+                }
+            }
+        }
+
+        // Where the piece would land, and the piece
+        let ghost = Piece { y: self.piece.y + self.well.drop_distance(&self.piece), ..self.piece };
+        for (piece, style) in [(ghost, wall), (self.piece, block)] {
+            for (x, y) in piece.cells() {
+                Rectangle::new(well + Point::new(x * CELL, y * CELL), Size::new(CELL as u32 - 1, CELL as u32 - 1))
+                    .into_styled(style)
+                    .draw(view).unwrap();
+            }
+        }
+
+        // Panel, with a line between each part
+        for y in [HOLD_Y - 2, LEVEL_Y - 2, SCORE_Y - 2] {
+            Line::new(Point::new(PANEL_X, y), Point::new(PANEL_X + PANEL_WIDTH - 1, y)).into_styled(wall).draw(view).unwrap();
+        }
+        draw_preview(view, self.next, NEXT_Y);
+        if let Some(hold) = self.hold {
+            draw_preview(view, hold, HOLD_Y);
+        }
+        draw_number(view, self.level, LEVEL_Y);
+        draw_number(view, self.score, SCORE_Y);
+
+        // Low battery warning, at the top of the well where the pieces come in
+        let low = matches!(battery::level(), Level::Low | Level::Critical);
+        if low && self.frame % WARNING_FRAMES < WARNING_FRAMES / 2 {
+            let x = WELL_X + (COLUMNS * CELL - battery::ICON_WIDTH as i32) / 2;
+            battery::draw_icon(view, Point::new(x, WELL_Y + 1));
+        }
+    }
+}
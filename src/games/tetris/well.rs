@@ -0,0 +1,382 @@
+//! Pieces, well and scoring of `tetris`, turning by the Super Rotation System.
+//!
+//! Rows count down from the top of the well, like the screen. Cells above
+//! the well are free, so a piece coming in can move and turn.
+
+use crate::rand::Rand;
+
+pub const COLUMNS: i32 = 10;
+pub const ROWS: i32 = 20;
+/// Cleared lines for each level.
+pub const LINES_PER_LEVEL: u32 = 10;
+const FULL_ROW: u16 = (1 << COLUMNS) - 1;
+
+/// Frames a piece takes to fall a row, from level 1 on.
+const GRAVITY_FRAMES: [u32; 14] = [40, 34, 28, 23, 19, 15, 12, 9, 7, 5, 4, 3, 2, 1];
+
+/// SRS kicks for each clockwise turn, from 0, R, 2 and L, tried in order.
+/// As the guideline has them, with `y` going up.
+const KICKS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+const I_KICKS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+pub const KINDS: [Kind; 7] = [Kind::I, Kind::O, Kind::T, Kind::S, Kind::Z, Kind::J, Kind::L];
+
+impl Kind {
+    /// Side of the box the piece turns in.
+    fn box_size(self) -> i32 {
+        match self {
+            Kind::I | Kind::O => 4,
+            _ => 3,
+        }
+    }
+
+    /// Blocks as the piece comes in, in its box.
+    fn spawn_blocks(self) -> [(i32, i32); 4] {
+        match self {
+            Kind::I => [(0, 1), (1, 1), (2, 1), (3, 1)],
+            Kind::O => [(1, 0), (2, 0), (1, 1), (2, 1)],
+            Kind::T => [(1, 0), (0, 1), (1, 1), (2, 1)],
+            Kind::S => [(1, 0), (2, 0), (0, 1), (1, 1)],
+            Kind::Z => [(0, 0), (1, 0), (1, 1), (2, 1)],
+            Kind::J => [(0, 0), (0, 1), (1, 1), (2, 1)],
+            Kind::L => [(2, 0), (0, 1), (1, 1), (2, 1)],
+        }
+    }
+
+    /// Blocks in the box, turned clockwise `rotation` times.
+    pub fn blocks(self, rotation: u8) -> [(i32, i32); 4] {
+        let mut blocks = self.spawn_blocks();
+        // The O looks the same every way
+        if self != Kind::O {
+            let size = self.box_size();
+            for _ in 0..rotation % 4 {
+                for (x, y) in blocks.iter_mut() {
+                    (*x, *y) = (size - 1 - *y, *x);
+                }
+            }
+        }
+        blocks
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Piece {
+    pub kind: Kind,
+    /// Top left corner of its box.
+    pub x: i32,
+    pub y: i32,
+    /// Clockwise turns from the way it came in, 0 to 3.
+    pub rotation: u8,
+}
+
+impl Piece {
+    /// Coming in at the top of the well, in the middle.
+    pub fn spawn(kind: Kind) -> Self {
+        Self {
+            kind,
+            x: (COLUMNS - 4) / 2,
+            y: if kind == Kind::I { -1 } else { 0 },
+            rotation: 0,
+        }
+    }
+
+    /// Cells of the well it covers.
+    pub fn cells(&self) -> [(i32, i32); 4] {
+        self.kind.blocks(self.rotation).map(|(x, y)| (self.x + x, self.y + y))
+    }
+}
+
+/// Cells filled by the pieces locked so far.
+#[derive(Clone, Debug, Default)]
+pub struct Well {
+    /// A bit per column, the leftmost in bit 0.
+    rows: [u16; ROWS as usize],
+}
+
+impl Well {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_filled(&self, x: i32, y: i32) -> bool {
+        self.rows[y as usize] & 1 << x != 0
+    }
+
+    /// Whether `piece` is between the walls, above the floor and clear of the blocks.
+    pub fn fits(&self, piece: &Piece) -> bool {
+        piece.cells().iter().all(|(x, y)| {
+            (0..COLUMNS).contains(x) && *y < ROWS && (*y < 0 || !self.is_filled(*x, *y))
+        })
+    }
+
+    /// Moves `piece` if there is room, `false` if there isn't.
+    pub fn shift(&self, piece: &mut Piece, dx: i32, dy: i32) -> bool {
+        let moved = Piece { x: piece.x + dx, y: piece.y + dy, ..*piece };
+        if !self.fits(&moved) {
+            return false;
+        }
+        *piece = moved;
+        true
+    }
+
+    /// Turns `piece` clockwise, kicking it off walls and blocks the SRS way.
+    pub fn rotate(&self, piece: &mut Piece) -> bool {
+        let kicks = match piece.kind {
+            Kind::O => return true,
+            Kind::I => &I_KICKS,
+            _ => &KICKS,
+        };
+        for (dx, dy) in kicks[piece.rotation as usize] {
+            let turned = Piece {
+                x: piece.x + dx,
+                y: piece.y - dy,
+                rotation: (piece.rotation + 1) % 4,
+                ..*piece
+            };
+            if self.fits(&turned) {
+                *piece = turned;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Rows `piece` can fall straight down.
+    pub fn drop_distance(&self, piece: &Piece) -> i32 {
+        let mut distance = 0;
+        while self.fits(&Piece { y: piece.y + distance + 1, ..*piece }) {
+            distance += 1;
+        }
+        distance
+    }
+
+    /// Fills the cells under `piece`, `false` if some were above the well.
+    pub fn lock(&mut self, piece: &Piece) -> bool {
+        let mut inside = true;
+        for (x, y) in piece.cells() {
+            if y < 0 {
+                inside = false;
+            } else {
+                self.rows[y as usize] |= 1 << x;
+            }
+        }
+        inside
+    }
+
+    /// Takes out the full rows, dropping those above, and counts them.
+    pub fn clear_lines(&mut self) -> u32 {
+        let mut cleared = 0;
+        let mut to = ROWS as usize;
+        for from in (0..ROWS as usize).rev() {
+            if self.rows[from] == FULL_ROW {
+                cleared += 1;
+            } else {
+                to -= 1;
+                self.rows[to] = self.rows[from];
+            }
+        }
+        self.rows[..to].fill(0);
+        cleared
+    }
+}
+
+/// 7-bag randomiser: every kind once in each 7 pieces, in a random order.
+pub struct Bag {
+    kinds: [Kind; 7],
+    taken: usize,
+    rand: Rand,
+}
+
+impl Bag {
+    pub fn new(rand: Rand) -> Self {
+        Self {
+            kinds: KINDS,
+            taken: KINDS.len(),
+            rand,
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Kind {
+        if self.taken == self.kinds.len() {
+            self.rand.shuffle(&mut self.kinds);
+            self.taken = 0;
+        }
+        self.taken += 1;
+        self.kinds[self.taken - 1]
+    }
+}
+
+/// Points for clearing `lines` at once on `level`.
+pub fn line_score(lines: u32, level: u32) -> u32 {
+    [0, 100, 300, 500, 800][lines as usize] * level
+}
+
+/// Frames a piece takes to fall a row on `level`, from 1.
+pub fn gravity_frames(level: u32) -> u32 {
+    GRAVITY_FRAMES[(level as usize - 1).min(GRAVITY_FRAMES.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Standing up, in the third column of its box.
+    fn upright_i(x: i32, y: i32) -> Piece {
+        Piece { kind: Kind::I, x: x - 2, y, rotation: 1 }
+    }
+
+    /// Drops `piece` to the floor and locks it.
+    fn hard_drop(well: &mut Well, mut piece: Piece) -> Piece {
+        piece.y += well.drop_distance(&piece);
+        assert!(well.lock(&piece));
+        piece
+    }
+
+    #[test]
+    fn bag_deals_each_kind_once() {
+        let mut bag = Bag::new(Rand::new(1234));
+        let mut orders = std::vec::Vec::new();
+        for _ in 0..10 {
+            let mut dealt: [Kind; 7] = core::array::from_fn(|_| bag.next());
+            orders.push(dealt);
+            dealt.sort_by_key(|kind| KINDS.iter().position(|other| other == kind));
+            assert_eq!(dealt, KINDS);
+        }
+        // Shuffled afresh for each bag
+        assert!(orders.iter().any(|order| *order != orders[0]));
+    }
+
+    #[test]
+    fn blocks_turn_in_their_box() {
+        assert_eq!(Kind::T.blocks(1), [(2, 1), (1, 0), (1, 1), (1, 2)]);
+        assert_eq!(Kind::I.blocks(1), [(2, 0), (2, 1), (2, 2), (2, 3)]);
+        assert_eq!(Kind::O.blocks(3), Kind::O.blocks(0));
+        for kind in KINDS {
+            assert_eq!(kind.blocks(4), kind.blocks(0));
+        }
+    }
+
+    #[test]
+    fn clears_one_line() {
+        let mut well = Well::new();
+        well.rows[19] = FULL_ROW & !(1 << 9);
+        well.rows[18] = 0b11;
+        hard_drop(&mut well, upright_i(9, 0));
+
+        assert_eq!(well.clear_lines(), 1);
+        // The rows above came down one
+        assert_eq!(well.rows[19], 0b11 | 1 << 9);
+        assert_eq!(well.rows[18], 1 << 9);
+        assert_eq!(well.rows[17], 1 << 9);
+        assert!(well.rows[..17].iter().all(|row| *row == 0));
+        assert_eq!(well.clear_lines(), 0);
+    }
+
+    #[test]
+    fn clears_four_lines() {
+        let mut well = Well::new();
+        for row in 16..20 {
+            well.rows[row] = FULL_ROW & !1;
+        }
+        well.rows[15] = 1 << 3;
+        well.rows[14] = 1 << 4;
+        let piece = hard_drop(&mut well, upright_i(0, 0));
+        assert_eq!(piece.cells().map(|(_, y)| y), [16, 17, 18, 19]);
+
+        assert_eq!(well.clear_lines(), 4);
+        assert_eq!(well.rows[19], 1 << 3);
+        assert_eq!(well.rows[18], 1 << 4);
+        assert!(well.rows[..18].iter().all(|row| *row == 0));
+        assert_eq!(line_score(4, 2), 1600);
+    }
+
+    #[test]
+    fn kicks_t_off_the_wall() {
+        let well = Well::new();
+        // Standing up against the left wall, pointing right
+        let mut piece = Piece { kind: Kind::T, x: -1, y: 5, rotation: 1 };
+        assert!(well.fits(&piece));
+        assert!(well.rotate(&mut piece));
+        assert_eq!(piece, Piece { kind: Kind::T, x: 0, y: 5, rotation: 2 });
+
+        // Flat on the floor, the third kick lifts it up a row to stand
+        let mut piece = Piece { kind: Kind::T, x: 3, y: ROWS - 2, rotation: 0 };
+        assert!(well.rotate(&mut piece));
+        assert_eq!((piece.x, piece.y, piece.rotation), (2, ROWS - 3, 1));
+    }
+
+    #[test]
+    fn kicks_i_off_the_walls() {
+        let well = Well::new();
+        let mut piece = upright_i(0, 5);
+        assert!(well.rotate(&mut piece));
+        assert_eq!((piece.x, piece.rotation), (0, 2));
+        assert!(piece.cells().iter().all(|(x, y)| (0..4).contains(x) && *y == 7));
+
+        let mut piece = upright_i(COLUMNS - 1, 5);
+        assert!(well.rotate(&mut piece));
+        assert_eq!((piece.x, piece.rotation), (COLUMNS - 4, 2));
+        assert!(well.fits(&piece));
+    }
+
+    #[test]
+    fn stuck_pieces_stay_put() {
+        let mut well = Well::new();
+        // Walls of blocks on both sides of an upright I
+        for row in well.rows.iter_mut() {
+            *row = FULL_ROW & !(1 << 4);
+        }
+        let mut piece = upright_i(4, 10);
+        assert!(well.fits(&piece));
+        assert!(!well.rotate(&mut piece));
+        assert_eq!(piece, upright_i(4, 10));
+        assert!(!well.shift(&mut piece, 1, 0));
+        assert_eq!(well.drop_distance(&piece), ROWS - 14);
+    }
+
+    #[test]
+    fn tops_out() {
+        let mut well = Well::new();
+        // Locked with a block above the well
+        let piece = upright_i(0, -1);
+        assert_eq!(well.drop_distance(&piece), ROWS - 3);
+        assert!(!well.lock(&piece));
+
+        // No room left for the next piece
+        let mut well = Well::new();
+        well.rows[1] = 1 << 4;
+        assert!(well.fits(&Piece::spawn(Kind::I)));
+        assert!(!well.fits(&Piece::spawn(Kind::T)));
+    }
+
+    #[test]
+    fn gravity() {
+        assert_eq!(gravity_frames(1), 40);
+        assert_eq!(gravity_frames(14), 1);
+        assert_eq!(gravity_frames(99), 1);
+        assert_eq!(line_score(1, 1), 100);
+        assert_eq!(line_score(0, 5), 0);
+    }
+}
@@ -0,0 +1,260 @@
+//! Grayscale by frame-rate modulation: the screen cycles through bit-planes
+//! in the order given by `Schedule`, and plane `k` stays up twice as
+//! long as plane `k - 1`, so pixels set in some of them look gray.
+
+use embedded_graphics::{prelude::{OriginDimensions, Size}, pixelcolor::{Gray2, Gray4, GrayColor}, draw_target::DrawTarget, Pixel};
+
+use crate::display::{pixel_index, FrameBuffer, BUFFER_SIZE, HEIGHT, WIDTH};
+
+pub const MAX_PLANES: usize = 4;
+
+/// Order in which the planes are shown, spread out to keep the flicker down.
+pub struct Schedule {
+    planes: usize,
+    subframe: usize,
+}
+
+impl Schedule {
+    pub fn new(planes: usize) -> Self {
+        assert!(planes > 0 && planes <= MAX_PLANES);
+        Self {
+            planes,
+            subframe: 0,
+        }
+    }
+
+    /// Subframes in a full cycle, each plane gets its weight out of these.
+    pub fn cycle_len(&self) -> usize {
+        (1 << self.planes) - 1
+    }
+
+    /// Plane to show for the next subframe.
+    pub fn next_plane(&mut self) -> usize {
+        self.subframe = self.subframe % self.cycle_len() + 1;
+        // The most significant plane comes up every other subframe,
+        // the next one every fourth and so on
+        self.planes - 1 - self.subframe.trailing_zeros() as usize
+    }
+}
+
+/// Draw target keeping one bit-plane per bit of gray,
+/// `GrayCanvas<2>` takes `Gray2` and `GrayCanvas<4>` takes `Gray4`.
+pub struct GrayCanvas<const PLANES: usize> {
+    planes: [FrameBuffer; PLANES],
+}
+
+pub type Gray2Canvas = GrayCanvas<2>;
+
+impl<const PLANES: usize> Default for GrayCanvas<PLANES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const PLANES: usize> GrayCanvas<PLANES> {
+    pub fn new() -> Self {
+        Self {
+            planes: [[0; BUFFER_SIZE]; PLANES],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.planes = [[0; BUFFER_SIZE]; PLANES];
+    }
+
+    /// Plane `plane`, pixels with bit `plane` of their darkness set.
+    pub fn plane(&self, plane: usize) -> &FrameBuffer {
+        &self.planes[plane]
+    }
+
+    /// Copies the planes out, into the first `PLANES` entries of `planes`.
+    pub fn copy_planes(&self, planes: &mut [FrameBuffer]) {
+        planes[..PLANES].copy_from_slice(&self.planes);
+    }
+
+    /// Sets how dark a pixel is, from 0 (blank) to `2^PLANES - 1` (black).
+    pub fn set_darkness(&mut self, x: usize, y: usize, darkness: u8) {
+        let (index, mask) = pixel_index(x, y);
+        for (plane, buffer) in self.planes.iter_mut().enumerate() {
+            if darkness & (1 << plane) != 0 {
+                buffer[index] |= mask;
+            } else {
+                buffer[index] &= !mask;
+            }
+        }
+    }
+
+    /// Color must have `PLANES` bits of luma, where full luma is a blank pixel.
+    fn draw_pixels<C: GrayColor, I>(&mut self, pixels: I)
+    where
+        I: IntoIterator<Item = Pixel<C>> {
+        let max = (1u8 << PLANES) - 1;
+        for Pixel(coord, color) in pixels.into_iter() {
+            if coord.x < 0 || coord.x >= WIDTH as i32 || coord.y < 0 || coord.y >= HEIGHT as i32 {
+                continue;
+            }
+            self.set_darkness(coord.x as usize, coord.y as usize, max - color.luma());
+        }
+    }
+}
+
+impl<const PLANES: usize> OriginDimensions for GrayCanvas<PLANES> {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for GrayCanvas<2> {
+    type Color = Gray2;
+
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        self.draw_pixels(pixels);
+        Ok(())
+    }
+}
+
+impl DrawTarget for GrayCanvas<4> {
+    type Color = Gray4;
+
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        self.draw_pixels(pixels);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::Drawable;
+    use embedded_graphics::prelude::Point;
+
+    use crate::display::{Display, TestDisplay};
+
+    /// Subframes out of one schedule period in which the pixel at `(x, y)` is dark.
+    fn lit_subframes<const PLANES: usize>(canvas: &GrayCanvas<PLANES>, x: usize, y: usize) -> usize {
+        let mut planes = [[0; BUFFER_SIZE]; MAX_PLANES];
+        canvas.copy_planes(&mut planes);
+        let (index, mask) = pixel_index(x, y);
+        let mut schedule = Schedule::new(PLANES);
+        (0..schedule.cycle_len())
+            .filter(|_| planes[schedule.next_plane()][index] & mask != 0)
+            .count()
+    }
+
+    #[test]
+    fn schedule_weights() {
+        for planes in 1..=MAX_PLANES {
+            let mut schedule = Schedule::new(planes);
+            let mut shown = [0; MAX_PLANES];
+            for _ in 0..schedule.cycle_len() * 3 {
+                shown[schedule.next_plane()] += 1;
+            }
+            for (plane, shown) in shown.iter().enumerate() {
+                let weight = if plane < planes { 1 << plane } else { 0 };
+                assert_eq!(*shown, 3 * weight, "plane {} of {}", plane, planes);
+            }
+        }
+    }
+
+    #[test]
+    fn schedule_spreads_planes() {
+        // The heaviest plane every other subframe
+        let mut schedule = Schedule::new(4);
+        let order: [usize; 15] = core::array::from_fn(|_| schedule.next_plane());
+        assert_eq!(order, [3, 2, 3, 1, 3, 2, 3, 0, 3, 2, 3, 1, 3, 2, 3]);
+    }
+
+    #[test]
+    fn gray2_levels() {
+        let mut canvas = Gray2Canvas::new();
+        for luma in 0..4 {
+            Pixel(Point::new(luma as i32, 0), Gray2::new(luma)).draw(&mut canvas).unwrap();
+        }
+        // Out of one period of 3 subframes, black is always dark and white never
+        let lit: [usize; 4] = core::array::from_fn(|x| lit_subframes(&canvas, x, 0));
+        assert_eq!(lit, [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn gray4_levels() {
+        let mut canvas = GrayCanvas::<4>::new();
+        for luma in 0..16 {
+            Pixel(Point::new(luma as i32, 5), Gray4::new(luma)).draw(&mut canvas).unwrap();
+        }
+        for luma in 0..16 {
+            assert_eq!(lit_subframes(&canvas, luma, 5), 15 - luma, "luma {}", luma);
+        }
+    }
+
+    #[test]
+    fn bit_planes() {
+        let mut canvas = GrayCanvas::<4>::new();
+        // Darkness 0b0110
+        Pixel(Point::new(10, 20), Gray4::new(9)).draw(&mut canvas).unwrap();
+        // Off the screen, nothing happens
+        Pixel(Point::new(-1, 0), Gray4::BLACK).draw(&mut canvas).unwrap();
+        Pixel(Point::new(0, HEIGHT as i32), Gray4::BLACK).draw(&mut canvas).unwrap();
+
+        let mut planes = [[0xff; BUFFER_SIZE]; MAX_PLANES];
+        canvas.copy_planes(&mut planes);
+        let (index, mask) = pixel_index(10, 20);
+        for (plane, buffer) in planes.iter().enumerate() {
+            let mut expected = [0; BUFFER_SIZE];
+            if 0b0110 & (1 << plane) != 0 {
+                expected[index] = mask;
+            }
+            assert!(*buffer == expected, "plane {}", plane);
+        }
+
+        // Lighter again, the bits no longer set are cleared
+        canvas.set_darkness(10, 20, 0b0001);
+        canvas.copy_planes(&mut planes);
+        assert_eq!(planes.iter().map(|plane| plane[index] & mask != 0).collect::<std::vec::Vec<_>>(), [true, false, false, false]);
+
+        canvas.clear();
+        canvas.copy_planes(&mut planes);
+        assert!(planes.iter().all(|plane| *plane == [0; BUFFER_SIZE]));
+    }
+
+    #[test]
+    fn gray2_fills_two_planes() {
+        let mut planes = [[0xff; BUFFER_SIZE]; MAX_PLANES];
+        let mut canvas = Gray2Canvas::new();
+        canvas.copy_planes(&mut planes);
+        // Only the first two are touched
+        assert!(planes[..2].iter().all(|plane| *plane == [0; BUFFER_SIZE]));
+        assert!(planes[2..].iter().all(|plane| *plane == [0xff; BUFFER_SIZE]));
+
+        DrawTarget::clear(&mut canvas, Gray2::new(1)).unwrap();
+        canvas.copy_planes(&mut planes);
+        assert_eq!(planes[0], [0; BUFFER_SIZE]);
+        assert_eq!(planes[1], [0xff; BUFFER_SIZE]);
+    }
+
+    #[test]
+    fn one_bit_screens_show_the_darker_half() {
+        let mut canvas = GrayCanvas::<4>::new();
+        for darkness in 0..16 {
+            canvas.set_darkness(darkness as usize, 0, darkness);
+        }
+        let mut display = TestDisplay { buffer: [0x5a; BUFFER_SIZE], ..Default::default() };
+        display.draw_gray(&canvas);
+
+        assert_eq!(display.frames, 1);
+        for x in 0..16 {
+            let (index, mask) = pixel_index(x, 0);
+            assert_eq!(display.sent[index] & mask != 0, x >= 8, "darkness {}", x);
+        }
+        // What was being drawn is still there
+        assert_eq!(display.buffer, [0x5a; BUFFER_SIZE]);
+    }
+}
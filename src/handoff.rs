@@ -0,0 +1,374 @@
+//! Finished frames handed over to whoever sends them to the screen, the
+//! other core in the firmware.
+//!
+//! Frames travel in `SLOTS` shared buffers. The producer fills a free slot
+//! and sends its index down a FIFO, the consumer sends the index back once it
+//! is done with the frame, so a slot only ever belongs to one side. Commands
+//! for the screen go down the same FIFO, which keeps them in order with the
+//! frames. Nothing but plain loads and stores is needed, so it works the same
+//! between the RP2040 cores and between threads on the host.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{fence, Ordering};
+
+use crate::display::{FrameBuffer, BUFFER_SIZE};
+
+/// Two, so the game can fill one while the other is being sent.
+pub const SLOTS: usize = 2;
+
+const TAG_SHIFT: u32 = 24;
+const TAG_FRAME: u32 = 1;
+const TAG_COMMAND: u32 = 2;
+const TAG_SYNC: u32 = 3;
+
+/// One end of a pair of word FIFOs between the producer and the consumer,
+/// like the SIO FIFOs of the RP2040.
+pub trait Fifo {
+    /// Blocks while the FIFO to the other side is full.
+    fn write(&mut self, word: u32);
+
+    /// `None` if nothing came from the other side.
+    fn read(&mut self) -> Option<u32>;
+}
+
+/// Sent from the producer to the consumer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Message {
+    Frame(usize),
+    /// Up to the firmware, the handoff only keeps it in order with the frames.
+    Command(u16),
+    Sync,
+}
+
+/// Sent back from the consumer to the producer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reply {
+    /// Done with the frame in the slot, the producer can fill it again.
+    Done(usize),
+    /// Everything up to the `Message::Sync` was handled.
+    Synced,
+}
+
+impl Message {
+    pub fn to_word(self) -> u32 {
+        match self {
+            Message::Frame(slot) => TAG_FRAME << TAG_SHIFT | slot as u32,
+            Message::Command(command) => TAG_COMMAND << TAG_SHIFT | command as u32,
+            Message::Sync => TAG_SYNC << TAG_SHIFT,
+        }
+    }
+
+    pub fn from_word(word: u32) -> Option<Self> {
+        let value = word & ((1 << TAG_SHIFT) - 1);
+        match word >> TAG_SHIFT {
+            TAG_FRAME if (value as usize) < SLOTS => Some(Message::Frame(value as usize)),
+            TAG_COMMAND if value <= u16::MAX as u32 => Some(Message::Command(value as u16)),
+            TAG_SYNC => Some(Message::Sync),
+            _ => None,
+        }
+    }
+}
+
+impl Reply {
+    pub fn to_word(self) -> u32 {
+        match self {
+            Reply::Done(slot) => TAG_FRAME << TAG_SHIFT | slot as u32,
+            Reply::Synced => TAG_SYNC << TAG_SHIFT,
+        }
+    }
+
+    pub fn from_word(word: u32) -> Option<Self> {
+        let value = word & ((1 << TAG_SHIFT) - 1);
+        match word >> TAG_SHIFT {
+            TAG_FRAME if (value as usize) < SLOTS => Some(Reply::Done(value as usize)),
+            TAG_SYNC => Some(Reply::Synced),
+            _ => None,
+        }
+    }
+}
+
+/// The frame buffers shared by a `Producer` and a `Consumer`, usually a static.
+pub struct Slots([UnsafeCell<FrameBuffer>; SLOTS]);
+
+// Each slot is only touched by the side holding its index
+unsafe impl Sync for Slots {}
+
+impl Slots {
+    pub const fn new() -> Self {
+        Self([const { UnsafeCell::new([0; BUFFER_SIZE]) }; SLOTS])
+    }
+}
+
+impl Default for Slots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The game side, sends frames and commands.
+pub struct Producer<'a, F: Fifo> {
+    slots: &'a Slots,
+    fifo: F,
+    free: [bool; SLOTS],
+    synced: bool,
+}
+
+impl<'a, F: Fifo> Producer<'a, F> {
+    /// There must be only one `Producer` and one `Consumer` on `slots`.
+    pub fn new(slots: &'a Slots, fifo: F) -> Self {
+        Self {
+            slots,
+            fifo,
+            free: [true; SLOTS],
+            synced: false,
+        }
+    }
+
+    /// Takes in whatever the consumer sent back.
+    fn poll(&mut self) {
+        while let Some(word) = self.fifo.read() {
+            match Reply::from_word(word) {
+                Some(Reply::Done(slot)) => self.free[slot] = true,
+                Some(Reply::Synced) => self.synced = true,
+                None => {},
+            }
+        }
+    }
+
+    /// Copies `frame` into a free slot and sends it, waits for the consumer
+    /// to give a slot back if both are taken.
+    pub fn send_frame(&mut self, frame: &FrameBuffer) {
+        let slot = loop {
+            self.poll();
+            if let Some(slot) = self.free.iter().position(|free| *free) {
+                break slot;
+            }
+        };
+
+        // The consumer gave the slot back, and won't look at it again until told to
+        fence(Ordering::Acquire);
+        unsafe { *self.slots.0[slot].get() = *frame };
+        fence(Ordering::Release);
+
+        self.free[slot] = false;
+        self.fifo.write(Message::Frame(slot).to_word());
+    }
+
+    pub fn send_command(&mut self, command: u16) {
+        self.fifo.write(Message::Command(command).to_word());
+    }
+
+    /// `true` while the consumer still holds a frame.
+    pub fn is_busy(&mut self) -> bool {
+        self.poll();
+        self.free.iter().any(|free| !free)
+    }
+
+    /// Waits until the consumer handled everything sent so far.
+    pub fn sync(&mut self) {
+        self.synced = false;
+        self.fifo.write(Message::Sync.to_word());
+        while !self.synced {
+            self.poll();
+        }
+    }
+}
+
+/// Whatever the `Consumer` hands the messages to.
+pub trait Sink {
+    /// The slot goes back to the producer right after this returns.
+    fn frame(&mut self, frame: &FrameBuffer);
+
+    fn command(&mut self, command: u16);
+
+    /// Called before answering a `Message::Sync`, to finish anything still going on.
+    fn sync(&mut self) {}
+}
+
+/// The screen side, passes the frames and commands on to a `Sink`.
+pub struct Consumer<'a, F: Fifo> {
+    slots: &'a Slots,
+    fifo: F,
+}
+
+impl<'a, F: Fifo> Consumer<'a, F> {
+    pub fn new(slots: &'a Slots, fifo: F) -> Self {
+        Self {
+            slots,
+            fifo,
+        }
+    }
+
+    /// Handles the next message, returns `false` if there was none.
+    pub fn poll(&mut self, sink: &mut impl Sink) -> bool {
+        let Some(word) = self.fifo.read() else {
+            return false;
+        };
+
+        match Message::from_word(word) {
+            Some(Message::Frame(slot)) => {
+                fence(Ordering::Acquire);
+                sink.frame(unsafe { &*self.slots.0[slot].get() });
+                fence(Ordering::Release);
+                self.fifo.write(Reply::Done(slot).to_word());
+            },
+            Some(Message::Command(command)) => sink.command(command),
+            Some(Message::Sync) => {
+                sink.sync();
+                self.fifo.write(Reply::Synced.to_word());
+            },
+            None => {},
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use std::vec::Vec;
+
+    const FRAMES: u8 = 50;
+
+    /// One end of a pair of channels, standing in for the SIO FIFOs.
+    struct ChannelFifo {
+        to: Sender<u32>,
+        from: Receiver<u32>,
+        /// Every word read, to see which slots came through.
+        read: Vec<u32>,
+    }
+
+    impl Fifo for ChannelFifo {
+        fn write(&mut self, word: u32) {
+            self.to.send(word).unwrap();
+        }
+
+        fn read(&mut self) -> Option<u32> {
+            let word = self.from.try_recv().ok()?;
+            self.read.push(word);
+            Some(word)
+        }
+    }
+
+    fn fifos() -> (ChannelFifo, ChannelFifo) {
+        let (to_consumer, from_producer) = channel();
+        let (to_producer, from_consumer) = channel();
+        (
+            ChannelFifo { to: to_consumer, from: from_consumer, read: Vec::new() },
+            ChannelFifo { to: to_producer, from: from_producer, read: Vec::new() },
+        )
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Frame(u8),
+        Command(u16),
+        Sync,
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<Event>,
+    }
+
+    impl Sink for Recorder {
+        fn frame(&mut self, frame: &FrameBuffer) {
+            // Held long enough for the producer to come back for another slot
+            let copy = *frame;
+            thread::sleep(Duration::from_micros(200));
+            assert!(copy == *frame, "frame {} changed while held", copy[0]);
+            assert!(frame.iter().all(|byte| *byte == frame[0]));
+            self.events.push(Event::Frame(frame[0]));
+        }
+
+        fn command(&mut self, command: u16) {
+            self.events.push(Event::Command(command));
+        }
+
+        fn sync(&mut self) {
+            self.events.push(Event::Sync);
+        }
+    }
+
+    #[test]
+    fn words() {
+        for message in [Message::Frame(0), Message::Frame(SLOTS - 1), Message::Command(0xabcd), Message::Sync] {
+            assert_eq!(Message::from_word(message.to_word()), Some(message));
+        }
+        for reply in [Reply::Done(0), Reply::Done(SLOTS - 1), Reply::Synced] {
+            assert_eq!(Reply::from_word(reply.to_word()), Some(reply));
+        }
+        assert_eq!(Message::from_word(Message::Frame(0).to_word() + SLOTS as u32), None);
+        assert_eq!(Reply::from_word(0), None);
+    }
+
+    #[test]
+    fn frames_between_threads() {
+        let slots = Arc::new(Slots::new());
+        let (producer_fifo, consumer_fifo) = fifos();
+
+        let consumer = thread::spawn({
+            let slots = slots.clone();
+            move || {
+                let mut consumer = Consumer::new(&slots, consumer_fifo);
+                let mut recorder = Recorder::default();
+                // Until the final sync
+                while recorder.events.last() != Some(&Event::Sync) {
+                    if !consumer.poll(&mut recorder) {
+                        thread::yield_now();
+                    }
+                }
+                (recorder.events, consumer.fifo.read)
+            }
+        });
+
+        let mut producer = Producer::new(&slots, producer_fifo);
+        for number in 0..FRAMES {
+            producer.send_frame(&[number; BUFFER_SIZE]);
+            if number % 10 == 0 {
+                producer.send_command(number as u16);
+            }
+        }
+        producer.sync();
+        assert!(!producer.is_busy());
+
+        let (events, read) = consumer.join().unwrap();
+        let mut expected = Vec::new();
+        for number in 0..FRAMES {
+            expected.push(Event::Frame(number));
+            if number % 10 == 0 {
+                expected.push(Event::Command(number as u16));
+            }
+        }
+        expected.push(Event::Sync);
+        assert_eq!(events, expected);
+
+        // Both slots taken in turn, each given back before it was filled again
+        let sent: Vec<usize> = read.iter()
+            .filter_map(|word| match Message::from_word(*word) {
+                Some(Message::Frame(slot)) => Some(slot),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sent.len(), FRAMES as usize);
+        assert!((0..SLOTS).all(|slot| sent.contains(&slot)));
+        let mut given_back = [true; SLOTS];
+        let mut replies = producer.fifo.read.iter().filter_map(|word| Reply::from_word(*word));
+        for slot in sent {
+            // The producer read the replies in order, so the slot was back by then
+            while !given_back[slot] {
+                match replies.next() {
+                    Some(Reply::Done(done)) => given_back[done] = true,
+                    Some(Reply::Synced) => {},
+                    None => panic!("slot {} filled while held", slot),
+                }
+            }
+            given_back[slot] = false;
+        }
+    }
+}
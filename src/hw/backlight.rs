@@ -0,0 +1,133 @@
+//! Screen backlight on GPIO1, dimmed by PWM slice 0 and faded from its wrap interrupt.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use embedded_hal::PwmPin;
+use rp2040_game::backlight::{self, MAX_LEVEL};
+use rp2040_game::display::Power;
+use rp_pico::hal::{gpio::bank0::Gpio1, pwm::{FreeRunning, Pwm0, PwmPinToken, Slice}};
+
+type BacklightSlice = Slice<Pwm0, FreeRunning>;
+type BacklightPin = PwmPinToken<Gpio1>;
+
+/// Clock divider giving a 1MHz PWM counter, 125MHz system clock assumed.
+const CLOCK_DIV: u8 = 125;
+/// 1kHz PWM, too fast to flicker.
+const TOP: u16 = 999;
+/// Duty of each level, in roughly even steps to the eye. Above `TOP` it stays on.
+const LEVEL_DUTY: [u16; MAX_LEVEL as usize + 1] = [0, 60, 250, TOP + 1];
+/// The dimmed backlight is at 1/2^DIM_SHIFT of the level.
+const DIM_SHIFT: u32 = 3;
+/// Duty change per PWM period, a fade all the way takes a quarter of a second.
+const FADE_STEP: u16 = 4;
+
+struct Backlight {
+    slice: BacklightSlice,
+    _pin:  BacklightPin,
+    /// Switched from the debug console, off overrides the level.
+    on: bool,
+    power: Power,
+    /// Where the fade is at.
+    duty: u16,
+}
+
+impl Backlight {
+    fn target(&self) -> u16 {
+        if !self.on {
+            return 0;
+        }
+
+        let duty = LEVEL_DUTY[backlight::level() as usize];
+        match self.power {
+            Power::On => duty,
+            // Dimmed, but not off
+            Power::Dimmed => (duty >> DIM_SHIFT).max(duty.min(1)),
+            Power::Off => 0,
+        }
+    }
+
+    /// Starts fading towards the target, if not there yet.
+    fn retarget(&mut self) {
+        if self.duty != self.target() {
+            self.slice.enable_interrupt();
+        }
+    }
+
+    /// One step of the fade, returns `true` once it got there.
+    fn step(&mut self) -> bool {
+        let target = self.target();
+        self.duty = if self.duty < target {
+            (self.duty + FADE_STEP).min(target)
+        } else {
+            self.duty.saturating_sub(FADE_STEP).max(target)
+        };
+        self.slice.channel_b.set_duty(self.duty);
+        self.duty == target
+    }
+}
+
+/// Shared with the debug console, which can switch it from its interrupt.
+static BACKLIGHT: Mutex<RefCell<Option<Backlight>>> = Mutex::new(RefCell::new(None));
+
+/// Starts dark, `set(true)` fades it in.
+pub fn init(mut slice: BacklightSlice, pin: BacklightPin) {
+    slice.default_config();
+    slice.set_div_int(CLOCK_DIV);
+    slice.set_top(TOP);
+    slice.channel_b.set_duty(0);
+    slice.enable();
+
+    cortex_m::interrupt::free(|cs| {
+        BACKLIGHT.borrow(cs).replace(Some(Backlight {
+            slice,
+            _pin: pin,
+            on: false,
+            power: Power::On,
+            duty: 0,
+        }));
+    });
+}
+
+fn update(change: impl FnOnce(&mut Backlight)) {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(backlight) = BACKLIGHT.borrow(cs).borrow_mut().as_mut() {
+            change(backlight);
+            backlight.retarget();
+        }
+    });
+}
+
+pub fn set(on: bool) {
+    update(|backlight| backlight.on = on);
+}
+
+/// Fades to one of the levels of `rp2040_game::backlight`.
+pub fn set_level(level: u8) {
+    update(|_| backlight::set_level(level));
+}
+
+/// Follows the screen, dimmed or off while the console is idle.
+pub fn set_power(power: Power) {
+    update(|backlight| backlight.power = power);
+}
+
+/// `false` while fading.
+pub fn is_settled() -> bool {
+    cortex_m::interrupt::free(|cs| {
+        BACKLIGHT.borrow(cs).borrow().as_ref()
+            .is_none_or(|backlight| backlight.duty == backlight.target())
+    })
+}
+
+/// To be called from `PWM_IRQ_WRAP`.
+pub fn on_wrap() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(backlight) = BACKLIGHT.borrow(cs).borrow_mut().as_mut() {
+            backlight.slice.clear_interrupt();
+            if backlight.step() {
+                backlight.slice.disable_interrupt();
+            }
+        }
+    });
+}
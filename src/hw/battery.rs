@@ -0,0 +1,94 @@
+//! Battery voltage on VSYS, measured by a task of its own.
+
+use core::fmt::Write;
+
+use embedded_hal::adc::OneShot;
+use embedded_hal::digital::v2::OutputPin;
+use rp2040_game::battery::{self, Filter, Level};
+use rp2040_game::console::Console;
+use rp2040_game::lang;
+use rp2040_game::settings::Settings;
+use rp2040_game::tasks::Ticker;
+use rp_pico::hal::{adc::Adc, gpio::{Pin, FloatingInput, bank0::Gpio29}};
+
+use super::{flash, rescue};
+
+type VsysPin = Pin<Gpio29, FloatingInput>;
+
+const SAMPLE_MS: u32 = 100;
+/// Samples in a row below `battery::CRITICAL_MV` before shutting down,
+/// a single dip under load is no reason to.
+const CRITICAL_SAMPLES: u8 = 20;
+/// How long the shutdown message stays up.
+const MESSAGE_MS: u32 = 3000;
+
+pub struct Monitor {
+    adc: Adc,
+    vsys: VsysPin,
+    filter: Filter,
+    critical: u8,
+}
+
+/// VSYS goes through a 3:1 divider into the 12 bit ADC, with a 3.3 V reference.
+fn to_millivolts(raw: u16) -> u32 {
+    raw as u32 * 3 * 3300 / 4096
+}
+
+impl Monitor {
+    /// Takes a first sample right away, for the menu to show.
+    pub fn new(adc: Adc, vsys: VsysPin) -> Self {
+        let mut monitor = Self {
+            adc,
+            vsys,
+            filter: Filter::new(),
+            critical: 0,
+        };
+        monitor.sample();
+        monitor
+    }
+
+    /// Returns `true` once the battery was critical for too long.
+    fn sample(&mut self) -> bool {
+        let raw: u16 = self.adc.read(&mut self.vsys).unwrap_or(0);
+        battery::set_millivolts(self.filter.push(to_millivolts(raw)));
+
+        if battery::level() == Level::Critical {
+            self.critical = self.critical.saturating_add(1);
+        } else {
+            self.critical = 0;
+        }
+        self.critical >= CRITICAL_SAMPLES
+    }
+
+    /// Samples the battery for good, shuts the console down once it runs out.
+    pub async fn run(mut self) {
+        let mut ticker = Ticker::every(SAMPLE_MS);
+        loop {
+            ticker.tick().await;
+            if self.sample() {
+                shut_down();
+            }
+        }
+    }
+}
+
+/// Saves the settings and powers everything down before the battery gives up.
+fn shut_down() -> ! {
+    flash::save_settings(&Settings::current());
+
+    // The main loop is not coming back
+    let mut rescue = unsafe { rescue::take_over() };
+    let mut console = Console::new();
+    write!(console, "\n\n{}", lang::strings().battery_empty).ok();
+    console.draw(&mut rescue.pcd);
+    rescue.delay.delay_ms(MESSAGE_MS);
+
+    // Dropping the display sends POWER_DOWN
+    drop(rescue.pcd);
+    rescue.backlight.set_low().unwrap();
+    rescue.vcc.set_low().unwrap();
+    rescue.led.set_low().unwrap();
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,108 @@
+//! The buttons on GPIO18-21, scanned by a task of their own.
+
+use embedded_hal::digital::v2::InputPin;
+use rp_pico::hal::gpio::{bank0::{Gpio21, Gpio20, Gpio19, Gpio18}, PullDownInput};
+use rp_pico::hal::gpio::{Interrupt, Pin};
+use rp_pico::pac;
+
+use rp2040_game::{inputs, power, tasks};
+use rp2040_game::tasks::Ticker;
+
+use super::entropy::button_jitter;
+use super::usb;
+
+type GpioMode = PullDownInput;
+type GpioIn0 = Gpio21;
+type GpioIn1 = Gpio20;
+type GpioIn2 = Gpio19;
+type GpioIn3 = Gpio18;
+
+/// Time between two scans, long enough for the buttons to stop bouncing.
+const SCAN_MS: u32 = 10;
+
+/// Rising edge bits of GPIO18-21 in `INTR2`, which has 4 bits for each of GPIO16-23.
+const WAKE_EDGES: u32 = 0x8 << 8 | 0x8 << 12 | 0x8 << 16 | 0x8 << 20;
+
+/// To be called from `IO_IRQ_BANK0`, wakes the `Scanner` resting while the console sleeps.
+pub fn on_interrupt() {
+    // The pins belong to `Scanner`, but the bits are write-to-clear
+    let io = unsafe { &*pac::IO_BANK0::ptr() };
+    io.intr[2].write(|w| unsafe { w.bits(WAKE_EDGES) });
+    tasks::wake();
+}
+
+/// Turns the buttons going down into `inputs::press`.
+pub struct Scanner {
+    but0: Pin<GpioIn0, GpioMode>,
+    but1: Pin<GpioIn1, GpioMode>,
+    but2: Pin<GpioIn2, GpioMode>,
+    but3: Pin<GpioIn3, GpioMode>,
+    held: [bool; 4],
+}
+
+impl Scanner {
+    pub fn new(
+        but0: Pin<GpioIn0, GpioMode>,
+        but1: Pin<GpioIn1, GpioMode>,
+        but2: Pin<GpioIn2, GpioMode>,
+        but3: Pin<GpioIn3, GpioMode>,
+    ) -> Self {
+        Self {
+            but0, but1, but2, but3,
+            held: [false; 4],
+        }
+    }
+
+    fn read(&self) -> [bool; 4] {
+        [
+            self.but0.is_high().unwrap(),
+            self.but1.is_high().unwrap(),
+            self.but2.is_high().unwrap(),
+            self.but3.is_high().unwrap(),
+        ]
+    }
+
+    fn set_wake(&mut self, enabled: bool) {
+        self.but0.clear_interrupt(Interrupt::EdgeHigh);
+        self.but1.clear_interrupt(Interrupt::EdgeHigh);
+        self.but2.clear_interrupt(Interrupt::EdgeHigh);
+        self.but3.clear_interrupt(Interrupt::EdgeHigh);
+        self.but0.set_interrupt_enabled(Interrupt::EdgeHigh, enabled);
+        self.but1.set_interrupt_enabled(Interrupt::EdgeHigh, enabled);
+        self.but2.set_interrupt_enabled(Interrupt::EdgeHigh, enabled);
+        self.but3.set_interrupt_enabled(Interrupt::EdgeHigh, enabled);
+    }
+
+    /// Presses the buttons that went down since the last scan.
+    pub fn scan(&mut self) {
+        let held = self.read();
+        let mut pressed = [false; 4];
+        for (index, pressed) in pressed.iter_mut().enumerate() {
+            if held[index] && !self.held[index] {
+                inputs::press(index);
+                button_jitter();
+                *pressed = true;
+            }
+        }
+        self.held = held;
+
+        // First and last button held together
+        if held[0] && held[3] && (pressed[0] || pressed[3]) {
+            usb::request_screenshot();
+        }
+    }
+
+    /// Scans the buttons for good, resting on their interrupts while the console sleeps.
+    pub async fn run(mut self) {
+        let mut ticker = Ticker::every(SCAN_MS);
+        loop {
+            ticker.tick().await;
+            if power::is_asleep() {
+                self.set_wake(true);
+                tasks::until(|| (!power::is_asleep() || self.read().contains(&true)).then_some(())).await;
+                self.set_wake(false);
+            }
+            self.scan();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use embedded_hal::PwmPin;
+use rp2040_game::audio::{self, Output, MAX_VOLUME};
+use rp2040_game::{power, tasks};
+use rp2040_game::tasks::Ticker;
+use rp_pico::hal::{gpio::bank0::Gpio15, pwm::{Slice, Pwm7, FreeRunning, PwmPinToken}};
+
+type BuzzerSlice = Slice<Pwm7, FreeRunning>;
+type BuzzerPin = PwmPinToken<Gpio15>;
+
+/// Clock divider giving a 3.125MHz PWM counter, 125MHz system clock assumed.
+const CLOCK_DIV: u8 = 40;
+const COUNTER_HZ: u32 = 125_000_000 / CLOCK_DIV as u32;
+/// How often `audio::tick` gets called.
+const TICK_MS: u16 = 5;
+
+/// Piezo buzzer on a PWM pin.
+pub struct Buzzer {
+    slice: BuzzerSlice,
+    _pin:  BuzzerPin,
+}
+
+impl Buzzer {
+    pub fn new(mut slice: BuzzerSlice, pin: BuzzerPin) -> Self {
+        slice.default_config();
+        slice.set_div_int(CLOCK_DIV);
+        slice.channel_b.set_duty(0);
+        slice.enable();
+
+        Self {
+            slice,
+            _pin: pin,
+        }
+    }
+
+    /// Starts a square wave at `freq`, silent for 0.
+    pub fn tone(&mut self, freq: u16, volume: u8) {
+        if freq == 0 || volume == 0 {
+            self.slice.channel_b.set_duty(0);
+            return;
+        }
+
+        let top = (COUNTER_HZ / freq as u32).clamp(2, u16::MAX as u32) as u16;
+        self.slice.set_top(top - 1);
+        // Half duty is the loudest a piezo gets
+        let duty = (top as u32 / 2) * volume.min(MAX_VOLUME) as u32 / MAX_VOLUME as u32;
+        self.slice.channel_b.set_duty(duty as u16);
+    }
+
+    /// Plays whatever `audio` asks for, for good, resting while the console sleeps.
+    pub async fn run(mut self) {
+        let mut ticker = Ticker::every(TICK_MS as u32);
+        let mut output = Output { freq: 0, volume: 0 };
+        let mut last_ms = tasks::now_ms();
+        loop {
+            ticker.tick().await;
+            if power::is_asleep() {
+                self.tone(0, 0);
+                tasks::until(|| (!power::is_asleep()).then_some(())).await;
+                // `audio` picks up where it was, the time asleep doesn't count
+                self.tone(output.freq, output.volume);
+                last_ms = tasks::now_ms();
+            }
+
+            // A busy task can hold a tick back, the tones still last as long
+            let now_ms = tasks::now_ms();
+            let elapsed = (now_ms - last_ms).min(u16::MAX as u64) as u16;
+            last_ms = now_ms;
+            if let Some(next) = audio::tick(elapsed) {
+                output = next;
+                self.tone(output.freq, output.volume);
+            }
+        }
+    }
+}
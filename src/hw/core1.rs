@@ -0,0 +1,193 @@
+//! Core1 drives the PCD8544, fed with frames and commands by the `Screen`
+//! on core0 through the SIO FIFOs, see `rp2040_game::handoff`.
+
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use cortex_m::interrupt::Mutex;
+use rp2040_game::display::{FrameBuffer, BUFFER_SIZE};
+use rp2040_game::gray::{Schedule, MAX_PLANES};
+use rp2040_game::handoff::{Consumer, Fifo, Sink, Slots};
+use rp_pico::hal::multicore::{Multicore, Stack};
+use rp_pico::hal::sio::{Sio, SioFifo};
+use rp_pico::pac;
+
+use super::grayscale::SUBFRAME_US;
+use super::pcd8544::PCD8544;
+use super::screen::{Command, Screen};
+use super::time::uptime_us;
+
+/// In words, core1 only copies frames around.
+const STACK_WORDS: usize = 1024;
+
+pub static SLOTS: Slots = Slots::new();
+
+/// Handed over by `start` before core1 runs, and taken once by core1.
+static PCD: Mutex<RefCell<Option<PCD8544>>> = Mutex::new(RefCell::new(None));
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+/// Set by core0 to keep core1 out of the flash, see `park`.
+static PARK_REQUEST: AtomicBool = AtomicBool::new(false);
+static PARKED: AtomicBool = AtomicBool::new(false);
+
+/// The SIO FIFO of whichever core owns it.
+pub struct Port(pub SioFifo);
+
+impl Fifo for Port {
+    fn write(&mut self, word: u32) {
+        self.0.write_blocking(word);
+    }
+
+    fn read(&mut self) -> Option<u32> {
+        self.0.read()
+    }
+}
+
+/// Hands `pcd` over to core1, the game draws on the returned `Screen` from then on.
+pub fn start(pcd: PCD8544) -> Screen {
+    cortex_m::interrupt::free(|cs| {
+        PCD.borrow(cs).replace(Some(pcd));
+    });
+
+    // Neither the power state machine nor the vector table are used
+    // by anything else, and the FIFO is only used through the handoff
+    let mut pac = unsafe { pac::Peripherals::steal() };
+    let mut sio = Sio::new(pac.SIO);
+    let stack = cortex_m::singleton!(: Stack<STACK_WORDS> = Stack::new()).unwrap();
+    let mut multicore = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio);
+    multicore.cores()[1].spawn(run, &mut stack.mem).unwrap();
+    RUNNING.store(true, Ordering::Relaxed);
+
+    Screen::new(Port(sio.fifo))
+}
+
+/// Sends what the `Screen` hands over.
+struct Flusher {
+    pcd: PCD8544,
+    /// Of the gray picture, see `Command::Gray`.
+    planes: [FrameBuffer; MAX_PLANES],
+    plane_count: usize,
+    /// Planes still to come, the frames go into `planes` meanwhile.
+    planes_left: usize,
+    /// Cycling the planes, while the gray picture is up.
+    schedule: Option<Schedule>,
+    next_subframe_us: u64,
+}
+
+impl Flusher {
+    fn new(pcd: PCD8544) -> Self {
+        Self {
+            pcd,
+            planes: [[0; BUFFER_SIZE]; MAX_PLANES],
+            plane_count: 0,
+            planes_left: 0,
+            schedule: None,
+            next_subframe_us: 0,
+        }
+    }
+
+    /// Shows the next plane once the last one was up long enough,
+    /// `false` if there is no gray picture.
+    fn refresh(&mut self) -> bool {
+        let Some(schedule) = self.schedule.as_mut() else {
+            return false;
+        };
+        let now = uptime_us();
+        if now >= self.next_subframe_us {
+            self.next_subframe_us = now + SUBFRAME_US as u64;
+            self.pcd.draw_data(&self.planes[schedule.next_plane()]);
+        }
+        true
+    }
+}
+
+impl Sink for Flusher {
+    fn frame(&mut self, frame: &FrameBuffer) {
+        if self.planes_left > 0 {
+            // Until they are all in, the last picture keeps cycling with some of them
+            self.planes[self.plane_count - self.planes_left] = *frame;
+            self.planes_left -= 1;
+            if self.planes_left == 0 {
+                self.schedule = Some(Schedule::new(self.plane_count));
+            }
+            return;
+        }
+
+        self.schedule = None;
+        // Copied into the front buffer, so the slot is free when this returns
+        self.pcd.draw_data(frame);
+    }
+
+    fn command(&mut self, command: u16) {
+        match Command::from_bits(command) {
+            Some(Command::Contrast(contrast)) => self.pcd.set_contrast(contrast),
+            Some(Command::PowerDown(powered_down)) => self.pcd.set_powered_down(powered_down),
+            Some(Command::Gray(planes)) if (1..=MAX_PLANES).contains(&(planes as usize)) => {
+                self.plane_count = planes as usize;
+                self.planes_left = planes as usize;
+            },
+            Some(Command::Gray(_)) | None => {},
+        }
+    }
+
+    fn sync(&mut self) {
+        self.pcd.wait();
+    }
+}
+
+fn run() -> ! {
+    let pcd = cortex_m::interrupt::free(|cs| PCD.borrow(cs).take()).unwrap();
+    let sio = Sio::new(unsafe { pac::Peripherals::steal() }.SIO);
+    let mut consumer = Consumer::new(&SLOTS, Port(sio.fifo));
+    let mut flusher = Flusher::new(pcd);
+
+    loop {
+        if PARK_REQUEST.load(Ordering::Acquire) {
+            parked();
+        }
+        // Writes to the FIFO and `park` both send an event, a gray picture
+        // keeps the core busy cycling its planes
+        if !consumer.poll(&mut flusher) && !flusher.refresh() {
+            cortex_m::asm::wfe();
+        }
+    }
+}
+
+/// Waits in RAM until `unpark`, nothing in the flash can be reached meanwhile.
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+fn parked() {
+    PARKED.store(true, Ordering::Release);
+    while PARK_REQUEST.load(Ordering::Acquire) {
+        core::hint::spin_loop();
+    }
+    PARKED.store(false, Ordering::Release);
+}
+
+/// Keeps core1 in RAM while core0 writes the flash, until `unpark`.
+pub fn park() {
+    if !RUNNING.load(Ordering::Relaxed) {
+        return;
+    }
+    PARK_REQUEST.store(true, Ordering::Release);
+    cortex_m::asm::sev();
+    while !PARKED.load(Ordering::Acquire) {
+        core::hint::spin_loop();
+    }
+}
+
+pub fn unpark() {
+    if !RUNNING.load(Ordering::Relaxed) {
+        return;
+    }
+    PARK_REQUEST.store(false, Ordering::Release);
+}
+
+/// Stops core1 for good, for the rescue code taking the screen over.
+pub fn stop() {
+    if RUNNING.load(Ordering::Relaxed) && Sio::core() == 0 {
+        let psm = unsafe { &*pac::PSM::ptr() };
+        psm.frce_off.modify(|_, w| w.proc1().set_bit());
+        RUNNING.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,32 @@
+use embedded_hal::adc::OneShot;
+use rp_pico::hal::{adc::Adc, gpio::{Pin, FloatingInput, bank0::Gpio28}};
+use rp_pico::pac;
+
+use rp2040_game::rand::add_entropy;
+
+type NoisePin = Pin<Gpio28, FloatingInput>;
+
+/// Fills the entropy pool from the ring oscillator and ADC noise.
+///
+/// `noise` should be left unconnected so it picks up noise.
+pub fn gather(rosc: &pac::ROSC, adc: &mut Adc, noise: &mut NoisePin) {
+    for _ in 0..8 {
+        let mut value = 0u32;
+        for _ in 0..32 {
+            value = (value << 1) | rosc.randombit.read().randombit().bit() as u32;
+        }
+
+        let adc_value: u16 = adc.read(noise).unwrap_or(0);
+        add_entropy(value ^ ((adc_value as u32) << 16) ^ timer_low());
+    }
+}
+
+/// Mixes in when the user pressed a button, down to the microsecond.
+pub fn button_jitter() {
+    add_entropy(timer_low());
+}
+
+fn timer_low() -> u32 {
+    // Read only access to a free running counter
+    unsafe { (*pac::TIMER::ptr()).timerawl.read().bits() }
+}
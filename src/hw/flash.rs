@@ -0,0 +1,154 @@
+//! Settings in the last sector of the flash, kept out of the firmware by `memory.x`,
+//! and the unique ID of the flash chip.
+
+use rp2040_game::settings::{Settings, RECORD_SIZE};
+use rp_pico::hal::rom_data;
+
+use super::core1;
+
+const FLASH_BASE: u32 = 0x1000_0000;
+const FLASH_SIZE: u32 = 2048 * 1024;
+const SECTOR_SIZE: u32 = 4096;
+const PAGE_SIZE: usize = 256;
+const SETTINGS_OFFSET: u32 = FLASH_SIZE - SECTOR_SIZE;
+const SECTOR_ERASE: u8 = 0x20;
+const BOOT2_WORDS: usize = 256 / 4;
+const READ_UNIQUE_ID: u8 = 0x4b;
+/// The command, 4 dummy bytes, then the 8 of the ID coming back.
+const UNIQUE_ID_BYTES: usize = 1 + 4 + 8;
+
+// Registers driven by hand while XIP is off, the PAC can't be reached from RAM
+const GPIO_QSPI_SS_CTRL: *mut u32 = 0x4001_800c as *mut u32;
+const OUTOVER_MASK: u32 = 0b11 << 8;
+const OUTOVER_LOW: u32 = 0b10 << 8;
+const OUTOVER_HIGH: u32 = 0b11 << 8;
+const SSI_SR: *const u32 = 0x1800_0028 as *const u32;
+const SSI_SR_TFNF: u32 = 1 << 1;
+const SSI_SR_RFNE: u32 = 1 << 3;
+const SSI_DR0: *mut u32 = 0x1800_0060 as *mut u32;
+/// Bytes sent ahead of the ones read back, kept below the 16 of the RX FIFO.
+const MAX_IN_FLIGHT: usize = 14;
+
+/// ROM functions, looked up while the flash can still be read.
+struct Rom {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+}
+
+impl Rom {
+    fn new() -> Self {
+        Self {
+            connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+            flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+            flash_range_erase: rom_data::flash_range_erase::ptr(),
+            flash_range_program: rom_data::flash_range_program::ptr(),
+            flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        }
+    }
+}
+
+/// The second stage bootloader sets up the fast XIP mode again afterwards.
+fn copy_boot2() -> [u32; BOOT2_WORDS] {
+    let mut boot2 = [0u32; BOOT2_WORDS];
+    unsafe {
+        core::ptr::copy_nonoverlapping(FLASH_BASE as *const u32, boot2.as_mut_ptr(), BOOT2_WORDS);
+    }
+    boot2
+}
+
+pub fn load_settings() -> Option<Settings> {
+    // Memory mapped through XIP
+    let record = unsafe {
+        core::slice::from_raw_parts((FLASH_BASE + SETTINGS_OFFSET) as *const u8, RECORD_SIZE)
+    };
+    Settings::from_bytes(record)
+}
+
+/// Writes `settings` unless they are already there, with interrupts off for
+/// the tens of milliseconds it takes.
+pub fn save_settings(settings: &Settings) {
+    if load_settings() == Some(*settings) {
+        return;
+    }
+
+    let mut page = [0xff; PAGE_SIZE];
+    page[..RECORD_SIZE].copy_from_slice(&settings.to_bytes());
+
+    let rom = Rom::new();
+    let boot2 = copy_boot2();
+
+    // Core1 runs from the flash too
+    core1::park();
+    cortex_m::interrupt::free(|_| unsafe {
+        write_sector(&rom, &boot2, SETTINGS_OFFSET, &page);
+    });
+    core1::unpark();
+}
+
+/// Runs from RAM, nothing in the flash can be reached until it returns.
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn write_sector(rom: &Rom, boot2: &[u32; BOOT2_WORDS], offset: u32, page: &[u8; PAGE_SIZE]) {
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    (rom.flash_range_erase)(offset, SECTOR_SIZE as usize, SECTOR_SIZE, SECTOR_ERASE);
+    (rom.flash_range_program)(offset, page.as_ptr(), PAGE_SIZE);
+    (rom.flash_flush_cache)();
+
+    // Thumb code, hence the odd address
+    let boot2: extern "C" fn() = core::mem::transmute(boot2.as_ptr() as usize + 1);
+    boot2();
+}
+
+/// Unique ID of the flash chip, which makes a serial number for the board.
+pub fn unique_id() -> u64 {
+    let mut buffer = [0; UNIQUE_ID_BYTES];
+    buffer[0] = READ_UNIQUE_ID;
+
+    let rom = Rom::new();
+    let boot2 = copy_boot2();
+    core1::park();
+    cortex_m::interrupt::free(|_| unsafe {
+        transfer(&rom, &boot2, &mut buffer);
+    });
+    core1::unpark();
+    u64::from_be_bytes(buffer[5..].try_into().unwrap())
+}
+
+/// Sends `buffer` to the flash as one command, replacing it with what comes
+/// back. Runs from RAM like `write_sector`.
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn transfer(rom: &Rom, boot2: &[u32; BOOT2_WORDS], buffer: &mut [u8; UNIQUE_ID_BYTES]) {
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    set_chip_select(false);
+
+    let (mut sent, mut received) = (0, 0);
+    while received < buffer.len() {
+        let status = SSI_SR.read_volatile();
+        if status & SSI_SR_TFNF != 0 && sent < buffer.len() && sent - received < MAX_IN_FLIGHT {
+            SSI_DR0.write_volatile(buffer[sent] as u32);
+            sent += 1;
+        }
+        if status & SSI_SR_RFNE != 0 {
+            buffer[received] = SSI_DR0.read_volatile() as u8;
+            received += 1;
+        }
+    }
+
+    set_chip_select(true);
+    (rom.flash_flush_cache)();
+    let boot2: extern "C" fn() = core::mem::transmute(boot2.as_ptr() as usize + 1);
+    boot2();
+}
+
+/// Drives the chip select of the flash, `false` selects it.
+#[inline(always)]
+unsafe fn set_chip_select(high: bool) {
+    let outover = if high { OUTOVER_HIGH } else { OUTOVER_LOW };
+    GPIO_QSPI_SS_CTRL.write_volatile(GPIO_QSPI_SS_CTRL.read_volatile() & !OUTOVER_MASK | outover);
+}
@@ -0,0 +1,77 @@
+//! Shows a `GrayCanvas` by cycling its planes from the `TIMER_IRQ_1` interrupt.
+//!
+//! This is for the title, before `core1::start` takes the PCD8544 for good.
+//! From then on `Screen` hands the planes to core1 with `Display::draw_gray`
+//! and core1 cycles them the same way.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use embedded_time::duration::Extensions;
+use rp2040_game::display::{FrameBuffer, BUFFER_SIZE};
+use rp2040_game::gray::{GrayCanvas, Schedule, MAX_PLANES};
+use rp_pico::hal::timer::Alarm1;
+
+use super::pcd8544::PCD8544;
+
+/// How long each subframe stays on the screen.
+pub const SUBFRAME_US: u32 = 8_000;
+
+struct Refresh {
+    pcd: PCD8544,
+    alarm: Alarm1,
+    planes: [FrameBuffer; MAX_PLANES],
+    schedule: Schedule,
+}
+
+static REFRESH: Mutex<RefCell<Option<Refresh>>> = Mutex::new(RefCell::new(None));
+
+/// Hands the display over to the interrupt, which keeps showing `canvas`
+/// until `stop` gives it back.
+pub fn start<const PLANES: usize>(pcd: PCD8544, mut alarm: Alarm1, canvas: &GrayCanvas<PLANES>) {
+    let mut planes = [[0; BUFFER_SIZE]; MAX_PLANES];
+    canvas.copy_planes(&mut planes);
+
+    alarm.enable_interrupt();
+    alarm.schedule(SUBFRAME_US.microseconds()).unwrap();
+    cortex_m::interrupt::free(|cs| {
+        REFRESH.borrow(cs).replace(Some(Refresh {
+            pcd,
+            alarm,
+            planes,
+            schedule: Schedule::new(PLANES),
+        }));
+    });
+}
+
+/// Swaps in a new picture, with the same number of planes as in `start`.
+pub fn update<const PLANES: usize>(canvas: &GrayCanvas<PLANES>) {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(refresh) = REFRESH.borrow(cs).borrow_mut().as_mut() {
+            canvas.copy_planes(&mut refresh.planes);
+        }
+    });
+}
+
+/// Stops the refresh and returns the display, `None` if it was not running.
+pub fn stop() -> Option<(PCD8544, Alarm1)> {
+    let refresh = cortex_m::interrupt::free(|cs| REFRESH.borrow(cs).take());
+    refresh.map(|mut refresh| {
+        refresh.alarm.disable_interrupt();
+        refresh.alarm.clear_interrupt();
+        (refresh.pcd, refresh.alarm)
+    })
+}
+
+/// To be called from `TIMER_IRQ_1`, time for the next subframe.
+pub fn on_alarm() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(refresh) = REFRESH.borrow(cs).borrow_mut().as_mut() {
+            refresh.alarm.clear_interrupt();
+            refresh.alarm.schedule(SUBFRAME_US.microseconds()).unwrap();
+
+            let plane = refresh.schedule.next_plane();
+            refresh.pcd.draw_data(&refresh.planes[plane]);
+        }
+    });
+}
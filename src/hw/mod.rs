@@ -0,0 +1,20 @@
+//! Parts of the firmware that talk to the RP2040 and the board directly.
+
+pub mod backlight;
+pub mod battery;
+pub mod buttons;
+pub mod buzzer;
+pub mod core1;
+pub mod entropy;
+pub mod flash;
+pub mod grayscale;
+pub mod panic;
+pub mod pcd8544;
+pub mod power;
+pub mod reboot;
+pub mod rescue;
+pub mod screen;
+pub mod shell;
+pub mod time;
+pub mod title;
+pub mod usb;
@@ -0,0 +1,117 @@
+//! Panic handler showing where and why the firmware stopped.
+//!
+//! The screen shows the file, line and message, the onboard LED keeps
+//! blinking three short flashes and a pause, and the reboot button goes
+//! straight to the USB bootloader.
+
+use core::fmt::Write;
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use cortex_m::delay::Delay;
+use embedded_graphics::Drawable;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::mono_font::ascii::FONT_4X6;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::Point;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use heapless::String;
+use rp2040_game::display::Display;
+
+use super::pcd8544::PCD8544;
+use super::rescue::{self, Rescue};
+
+const LINE_CHARS: usize = 21;
+const LINE_HEIGHT: i32 = 6;
+/// Lines left for the message, below the location and above the hint.
+const MESSAGE_LINES: usize = 6;
+const BLINK_MS: u32 = 150;
+const PAUSE_MS: u32 = 1000;
+/// How often the reboot button is looked at.
+const POLL_MS: u32 = 10;
+
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    // A panic while showing a panic, nothing left to trust
+    if PANICKING.load(Ordering::Relaxed) {
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+    PANICKING.store(true, Ordering::Relaxed);
+
+    // Whoever owned the hardware is never coming back
+    let Rescue { mut pcd, mut delay, mut led, reboot, .. } = unsafe { rescue::take_over() };
+
+    show(&mut pcd, info);
+
+    loop {
+        for _ in 0..3 {
+            led.set_high().unwrap();
+            wait(&mut delay, &reboot, BLINK_MS);
+            led.set_low().unwrap();
+            wait(&mut delay, &reboot, BLINK_MS);
+        }
+        wait(&mut delay, &reboot, PAUSE_MS);
+    }
+}
+
+/// Keeps what fits, in ASCII so it can be split anywhere.
+struct Truncated<const N: usize>(String<N>);
+
+impl<const N: usize> Write for Truncated<N> {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        for c in text.chars() {
+            let c = if c.is_ascii() { c } else { '?' };
+            if self.0.push(c).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn show(pcd: &mut PCD8544, info: &PanicInfo) {
+    pcd.clear_buffer();
+    let style = MonoTextStyle::new(&FONT_4X6, BinaryColor::On);
+    let mut line = |text: &str, row: usize| {
+        Text::with_baseline(text, Point::new(0, row as i32 * LINE_HEIGHT), style, Baseline::Top)
+            .draw(pcd).unwrap();
+    };
+
+    let mut location = Truncated::<LINE_CHARS>(String::new());
+    if let Some(place) = info.location() {
+        let file = place.file().trim_start_matches("src/");
+        // Keep the end of the path, with the line number. Counted in characters, a
+        // byte offset could fall inside one
+        let start = file.char_indices().rev().nth(LINE_CHARS - 7).map_or(0, |(i, _)| i);
+        let file = &file[start..];
+        let _ = write!(location, "{}:{}", file, place.line());
+    }
+    line(&location.0, 0);
+
+    let mut message = Truncated::<{ LINE_CHARS * MESSAGE_LINES }>(String::new());
+    let _ = write!(message, "{}", info.message());
+    let mut rest = message.0.as_str();
+    for row in 1..=MESSAGE_LINES {
+        let end = rest.char_indices().nth(LINE_CHARS).map_or(rest.len(), |(i, _)| i);
+        line(&rest[..end], row);
+        rest = &rest[end..];
+    }
+
+    line("Reset: BOOTSEL", MESSAGE_LINES + 1);
+    pcd.draw();
+}
+
+fn wait<P: InputPin>(delay: &mut Delay, reboot_pin: &P, ms: u32) {
+    for _ in 0..ms / POLL_MS {
+        if reboot_pin.is_high().unwrap_or(false) {
+            rescue::reboot_to_bootloader();
+        }
+        delay.delay_ms(POLL_MS);
+    }
+}
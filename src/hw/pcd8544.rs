@@ -0,0 +1,291 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use cortex_m::{prelude::_embedded_hal_blocking_spi_Write, delay::Delay};
+use embedded_graphics::{prelude::{OriginDimensions, Size}, pixelcolor::BinaryColor, draw_target::DrawTarget};
+use embedded_hal::digital::v2::OutputPin;
+use rp2040_game::shell::MAX_CONTRAST;
+use rp2040_game::display::{draw_pixels, Display, FrameBuffer, Power, BUFFER_SIZE, HEIGHT, WIDTH};
+use rp_pico::hal::{dma::DREQ_SPI0_TX, gpio::{Pin, Output, PushPull, bank0::{Gpio8, Gpio5, Gpio4}}, spi::{Spi, Enabled}};
+use rp_pico::pac::{DMA, RESETS, SPI0};
+
+use super::{backlight, power, usb};
+
+const FUNCTION_SET: u8 = 0x20;
+const ADDRESSING_VERT: u8 = 0x02;
+const EXTENDED_INSTR: u8 = 0x01;
+const TEMP_COEFF_2: u8 = 0x06;
+const BIAS_1_40: u8 = 0x14;
+const SET_VOP: u8 = 0x80;
+const DISPLAY_NORMAL: u8 = 0x0c;
+const POWER_DOWN: u8 = 0x04;
+const DEFAULT_CONTRAST: u8 = 0x3f;
+/// `CONTRAST` value when no change is waiting.
+const NO_CONTRAST: u8 = 0xff;
+
+/// DMA channel feeding the SPI, nothing else uses DMA.
+const DMA_CHANNEL: usize = 0;
+
+/// Contrast to set on the next `draw`, from outside the main loop.
+static CONTRAST: AtomicU8 = AtomicU8::new(NO_CONTRAST);
+
+/// The front buffer, which the DMA reads while the `PCD8544` owning it
+/// draws the next frame into its back buffer. It is static so it stays put
+/// when the `PCD8544` gets moved around mid transfer.
+struct FrontBuffer(UnsafeCell<FrameBuffer>);
+
+// Only touched by the one `PCD8544`, see `PCD8544::new`
+unsafe impl Sync for FrontBuffer {}
+
+static FRONT: FrontBuffer = FrontBuffer(UnsafeCell::new([0; BUFFER_SIZE]));
+
+type GpioRst = Gpio8;
+type GpioCe  = Gpio5;
+type GpioDc  = Gpio4;
+type SpiPin  = SPI0;
+
+#[allow(non_camel_case_types)]
+pub struct PCD8544 {
+    rst: Pin<GpioRst, Output<PushPull>>,
+    ce:  Pin<GpioCe, Output<PushPull>>,
+    dc:  Pin<GpioDc, Output<PushPull>>,
+    spi: Spi<Enabled, SpiPin, 8>,
+    dma: DMA,
+    pub fnset: u8,
+    /// The back buffer, see `FrontBuffer`.
+    draw_buffer: FrameBuffer,
+    /// A transfer was started and CE is still low.
+    sending: bool,
+}
+
+#[allow(non_camel_case_types)]
+impl PCD8544 {
+    /// Resets the DMA, so a transfer of a previous `PCD8544` can't go on
+    /// reading the front buffer. There must only ever be one alive.
+    pub fn new(
+            rst: Pin<GpioRst, Output<PushPull>>,
+            ce:  Pin<GpioCe, Output<PushPull>>,
+            dc:  Pin<GpioDc, Output<PushPull>>,
+            spi: Spi<Enabled, SpiPin, 8>,
+            dma: DMA,
+            resets: &mut RESETS,
+            delay: &mut Delay
+    ) -> Self {
+        resets.reset.modify(|_, w| w.dma().set_bit());
+        resets.reset.modify(|_, w| w.dma().clear_bit());
+        while resets.reset_done.read().dma().bit_is_clear() {}
+
+        let mut pcd = Self {
+            fnset: FUNCTION_SET & !ADDRESSING_VERT,
+            rst,
+            ce,
+            dc,
+            spi,
+            dma,
+            draw_buffer: [0; BUFFER_SIZE],
+            sending: false,
+        };
+        pcd.init(delay);
+        pcd
+    }
+
+    fn init(&mut self, delay: &mut Delay) {
+        self.ce.set_high().unwrap();
+        self.dc.set_low().unwrap();
+
+        self.rst.set_high().unwrap();
+        delay.delay_us(100);
+        self.rst.set_low().unwrap();
+        delay.delay_us(100);
+        self.rst.set_high().unwrap();
+        delay.delay_us(100);
+
+        self.command(self.fnset);
+        self.command(self.fnset | EXTENDED_INSTR);
+        self.command(TEMP_COEFF_2);
+        self.command(BIAS_1_40);
+        self.command(SET_VOP | DEFAULT_CONTRAST);
+        self.command(self.fnset & !EXTENDED_INSTR);
+        self.command(DISPLAY_NORMAL);
+
+        // Blank until the splash draws
+        self.set(0);
+    }
+
+    pub fn set(&mut self, value: u8) {
+        self.draw_data(&[value; BUFFER_SIZE]);
+    }
+
+    /// `true` while a frame is still on its way to the screen.
+    pub fn is_busy(&self) -> bool {
+        let spi = unsafe { &*SPI0::ptr() };
+        self.dma.ch[DMA_CHANNEL].ch_ctrl_trig.read().busy().bit_is_set()
+            || spi.sspsr.read().bsy().bit_is_set()
+    }
+
+    /// Waits for the last frame to reach the screen, done by anything else
+    /// that talks to it first.
+    pub fn wait(&mut self) {
+        if !self.sending {
+            return;
+        }
+        while self.is_busy() {}
+
+        // The DMA only fed the TX side, the blocking writes expect an empty RX FIFO
+        let spi = unsafe { &*SPI0::ptr() };
+        while spi.sspsr.read().rne().bit_is_set() {
+            spi.sspdr.read();
+        }
+        spi.sspicr.write(|w| w.roric().set_bit());
+
+        self.ce.set_high().unwrap();
+        self.sending = false;
+    }
+
+    /// Blank and drawing next to nothing, with the RAM kept.
+    pub fn set_powered_down(&mut self, powered_down: bool) {
+        if powered_down {
+            self.fnset |= POWER_DOWN;
+        } else {
+            self.fnset &= !POWER_DOWN;
+        }
+        self.command(self.fnset);
+    }
+
+    pub fn is_powered_down(&self) -> bool {
+        self.fnset & POWER_DOWN != 0
+    }
+
+    /// Sets the operating voltage, up to `shell::MAX_CONTRAST`.
+    pub fn set_contrast(&mut self, contrast: u8) {
+        self.command(self.fnset | EXTENDED_INSTR);
+        self.command(SET_VOP | (contrast & MAX_CONTRAST));
+        self.command(self.fnset & !EXTENDED_INSTR);
+    }
+
+    fn command(&mut self, data: u8) {
+        self.wait();
+        self.dc.set_low().unwrap();
+        self.ce.set_low().unwrap();
+
+        self.spi.write(&[data]).unwrap();
+
+        self.ce.set_high().unwrap();
+    }
+
+    /// Starts sending up to a frame of `data` through the DMA, returns right
+    /// away. Waits for the previous transfer first, they share the front buffer.
+    pub fn draw_data(&mut self, data: &[u8]) {
+        self.wait();
+        let front = unsafe { &mut *FRONT.0.get() };
+        front[..data.len()].copy_from_slice(data);
+        self.send_front(data.len());
+    }
+
+    fn send_front(&mut self, len: usize) {
+        self.dc.set_high().unwrap();
+        self.ce.set_low().unwrap();
+        self.sending = true;
+
+        let spi = unsafe { &*SPI0::ptr() };
+        let channel = &self.dma.ch[DMA_CHANNEL];
+        channel.ch_read_addr.write(|w| unsafe { w.bits(FRONT.0.get() as u32) });
+        channel.ch_write_addr.write(|w| unsafe { w.bits(spi.sspdr.as_ptr() as u32) });
+        channel.ch_trans_count.write(|w| unsafe { w.bits(len as u32) });
+        channel.ch_ctrl_trig.write(|w| unsafe {
+            w.data_size().size_byte()
+                .incr_read().set_bit()
+                .incr_write().clear_bit()
+                .treq_sel().bits(DREQ_SPI0_TX)
+                // Chaining to itself means no chaining
+                .chain_to().bits(DMA_CHANNEL as u8)
+                .en().set_bit()
+        });
+    }
+}
+
+/// Makes the screen change its contrast with the next frame.
+pub fn request_contrast(contrast: u8) {
+    CONTRAST.store(contrast & MAX_CONTRAST, Ordering::Relaxed);
+}
+
+/// Takes the contrast asked for by `request_contrast`, for whoever draws the next frame.
+pub fn requested_contrast() -> Option<u8> {
+    let contrast = CONTRAST.load(Ordering::Relaxed);
+    if contrast == NO_CONTRAST {
+        return None;
+    }
+    CONTRAST.store(NO_CONTRAST, Ordering::Relaxed);
+    Some(contrast)
+}
+
+#[allow(non_camel_case_types)]
+impl Display for PCD8544 {
+    fn buffer(&self) -> &FrameBuffer {
+        &self.draw_buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.draw_buffer
+    }
+
+    fn draw(&mut self) {
+        if let Some(contrast) = requested_contrast() {
+            self.set_contrast(contrast);
+        }
+
+        self.wait();
+        unsafe { *FRONT.0.get() = self.draw_buffer };
+        self.send_front(BUFFER_SIZE);
+        usb::frame_drawn(&self.draw_buffer);
+    }
+
+    fn set_power(&mut self, power: Power) {
+        backlight::set_power(power);
+
+        let powered_down = self.is_powered_down();
+        match power {
+            Power::Off if !powered_down => {
+                // Fade out before the screen goes blank
+                power::sleep_until(backlight::is_settled);
+                self.set_powered_down(true);
+            },
+            Power::On | Power::Dimmed if powered_down => {
+                self.set_powered_down(false);
+                // The RAM survives power-down, this covers a buffer changed meanwhile
+                self.draw();
+            },
+            _ => {},
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+impl Drop for PCD8544 {
+    fn drop(&mut self) {
+        self.set_powered_down(true);
+    }
+}
+
+#[allow(non_camel_case_types)]
+impl OriginDimensions for PCD8544 {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+#[allow(non_camel_case_types)]
+impl DrawTarget for PCD8544 {
+    type Color = BinaryColor;
+
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>> {
+        draw_pixels(&mut self.draw_buffer, pixels);
+
+        //self.draw();
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Sleeping until an interrupt instead of spinning, for the idle tasks and the screen.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::Mutex;
+use embedded_time::duration::Extensions;
+use rp2040_game::tasks;
+use rp_pico::hal::timer::Alarm3;
+
+use super::time::uptime_us;
+
+/// Shortest time the alarm takes.
+const MIN_ALARM_US: u64 = 10;
+
+static ALARM: Mutex<RefCell<Option<Alarm3>>> = Mutex::new(RefCell::new(None));
+
+/// Sleeps until `wake` says so, checking again after every interrupt.
+pub fn sleep_until(mut wake: impl FnMut() -> bool) {
+    loop {
+        // An interrupt coming in between the check and `wfi` still wakes it
+        // up, the handler runs once the critical section ends
+        let awake = cortex_m::interrupt::free(|_| {
+            wake() || {
+                cortex_m::asm::wfi();
+                false
+            }
+        });
+        if awake {
+            return;
+        }
+    }
+}
+
+/// Runs the `tasks`, sleeping until `TIMER_IRQ_3` or another interrupt while they wait.
+pub struct Platform;
+
+impl Platform {
+    pub fn new(mut alarm: Alarm3) -> Self {
+        alarm.enable_interrupt();
+        cortex_m::interrupt::free(|cs| {
+            ALARM.borrow(cs).replace(Some(alarm));
+        });
+        Self
+    }
+}
+
+impl tasks::Platform for Platform {
+    fn now_ms(&mut self) -> u64 {
+        uptime_us() / 1000
+    }
+
+    fn idle(&mut self, until_ms: Option<u64>) {
+        let until_us = until_ms.map(|until_ms| until_ms * 1000);
+        if let Some(until_us) = until_us {
+            // Waking up early is fine, the tasks just wait some more
+            let us = until_us.saturating_sub(uptime_us()).clamp(MIN_ALARM_US, u32::MAX as u64) as u32;
+            cortex_m::interrupt::free(|cs| {
+                if let Some(alarm) = ALARM.borrow(cs).borrow_mut().as_mut() {
+                    alarm.schedule(us.microseconds()).unwrap();
+                }
+            });
+        }
+        sleep_until(|| tasks::is_woken() || until_us.is_some_and(|until_us| uptime_us() >= until_us));
+    }
+}
+
+/// To be called from `TIMER_IRQ_3`, only there to wake `Platform::idle` up.
+pub fn on_alarm() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(alarm) = ALARM.borrow(cs).borrow_mut().as_mut() {
+            alarm.clear_interrupt();
+        }
+    });
+}
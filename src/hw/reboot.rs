@@ -0,0 +1,123 @@
+//! The reboot button on GPIO22.
+//!
+//! A short press restarts the console through the watchdog, holding it for
+//! `BOOTSEL_US` reboots into the USB bootloader instead. Either way the
+//! console never comes back, so once the press is confirmed the interrupt
+//! takes the screen over and counts down until the button is let go.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+
+use cortex_m::interrupt::Mutex;
+use embedded_graphics::Drawable;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Primitive, Size};
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::text::Text;
+use embedded_hal::digital::v2::InputPin;
+use heapless::String;
+use rp2040_game::display::{Display, WIDTH};
+use rp2040_game::fonts::FONT_5X7;
+use rp2040_game::lang;
+use rp_pico::hal::gpio::{bank0::Gpio22, Interrupt, Pin, PullDownInput};
+
+use super::pcd8544::PCD8544;
+use super::rescue::{self, Rescue};
+use super::time::uptime_us;
+
+type RebootPin = Pin<Gpio22, PullDownInput>;
+
+/// Shorter presses are taken for contact bounce or noise.
+const DEBOUNCE_US: u64 = 50_000;
+const BOOTSEL_US: u64 = 2_000_000;
+const FRAME_MS: u32 = 50;
+
+static REBOOT_PIN: Mutex<RefCell<Option<RebootPin>>> = Mutex::new(RefCell::new(None));
+
+/// Watches the button through the `IO_IRQ_BANK0` interrupt.
+pub fn init(pin: RebootPin) {
+    pin.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+    cortex_m::interrupt::free(|cs| {
+        REBOOT_PIN.borrow(cs).replace(Some(pin));
+    });
+}
+
+fn is_held() -> bool {
+    cortex_m::interrupt::free(|cs| {
+        REBOOT_PIN.borrow(cs).borrow().as_ref()
+            .is_some_and(|pin| pin.is_high().unwrap())
+    })
+}
+
+/// To be called from `IO_IRQ_BANK0`.
+pub fn on_interrupt() {
+    let pressed = cortex_m::interrupt::free(|cs| {
+        match REBOOT_PIN.borrow(cs).borrow_mut().as_mut() {
+            Some(pin) if pin.interrupt_status(Interrupt::EdgeHigh) => {
+                pin.clear_interrupt(Interrupt::EdgeHigh);
+                true
+            },
+            _ => false,
+        }
+    });
+    if !pressed {
+        return;
+    }
+
+    let start = uptime_us();
+    while uptime_us() - start < DEBOUNCE_US {
+        if !is_held() {
+            return;
+        }
+    }
+
+    // Nothing the interrupted code was doing matters any more
+    let mut rescue = unsafe { rescue::take_over() };
+    countdown(&mut rescue, start);
+}
+
+fn countdown(rescue: &mut Rescue, start: u64) -> ! {
+    loop {
+        let held = uptime_us() - start;
+        if held >= BOOTSEL_US {
+            draw(&mut rescue.pcd, lang::strings().bootsel, BOOTSEL_US);
+            rescue.pcd.wait();
+            rescue::reboot_to_bootloader();
+        }
+
+        if !rescue.reboot.is_high().unwrap() {
+            draw(&mut rescue.pcd, lang::strings().restarting, held);
+            rescue.pcd.wait();
+            rescue::reboot();
+        }
+
+        draw(&mut rescue.pcd, lang::strings().release_to_restart, held);
+        rescue.delay.delay_ms(FRAME_MS);
+    }
+}
+
+fn draw(pcd: &mut PCD8544, text: &str, held: u64) {
+    pcd.clear_buffer();
+    let strings = lang::strings();
+    let style = MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
+    Text::new(text, Point::new(2, 10), style).draw(pcd).unwrap();
+    Text::new(strings.hold_for_bootsel, Point::new(2, 22), style).draw(pcd).unwrap();
+
+    // Fills up until BOOTSEL
+    let bar = WIDTH as u32 - 4;
+    Rectangle::new(Point::new(2, 30), Size::new(bar, 8))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(pcd).unwrap();
+    let filled = (bar as u64 * held.min(BOOTSEL_US) / BOOTSEL_US) as u32;
+    Rectangle::new(Point::new(2, 30), Size::new(filled, 8))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(pcd).unwrap();
+
+    let left_ms = (BOOTSEL_US - held.min(BOOTSEL_US)) / 1000;
+    let mut countdown = String::<16>::new();
+    write!(countdown, "{} {}.{} s", strings.bootsel_in, left_ms / 1000, left_ms % 1000 / 100).unwrap();
+    Text::new(&countdown, Point::new(2, 46), style).draw(pcd).unwrap();
+
+    pcd.draw();
+}
@@ -0,0 +1,84 @@
+//! Taking the hardware over from the main loop, for when it never runs again:
+//! after a panic and while the reboot button is held.
+
+use cortex_m::delay::Delay;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::spi::MODE_0;
+use embedded_time::rate::Extensions;
+use rp2040_game::bootsel;
+use rp_pico::hal::gpio::{bank0::{Gpio0, Gpio1, Gpio22, Gpio25}, FunctionSpi, Pin, PullDownInput, PushPullOutput};
+use rp_pico::hal::{pac, rom_data::reset_to_usb_boot, sio::Sio, Spi};
+
+use super::core1;
+use super::pcd8544::PCD8544;
+
+/// Set up by `init_clocks_and_plls` long before any rescue.
+const SYSTEM_CLOCK_HZ: u32 = 125_000_000;
+const LED_PIN: u32 = 25;
+
+pub struct Rescue {
+    pub pcd:    PCD8544,
+    pub delay:  Delay,
+    pub led:    Pin<Gpio25, PushPullOutput>,
+    pub reboot: Pin<Gpio22, PullDownInput>,
+    /// Power of the screen and its backlight, both on.
+    pub vcc:       Pin<Gpio0, PushPullOutput>,
+    pub backlight: Pin<Gpio1, PushPullOutput>,
+}
+
+/// Re-initialises the screen from scratch, a transfer may have been cut in the middle.
+///
+/// # Safety
+///
+/// Steals the peripherals, whatever owned them before must never run again.
+pub unsafe fn take_over() -> Rescue {
+    // Core1 would go on sending frames
+    core1::stop();
+    let mut pac = pac::Peripherals::steal();
+    let core = pac::CorePeripherals::steal();
+    let mut delay = Delay::new(core.SYST, SYSTEM_CLOCK_HZ);
+    let sio = Sio::new(pac.SIO);
+    // Resets the GPIO banks, which also silences the buzzer
+    let pins = rp_pico::Pins::new(pac.IO_BANK0, pac.PADS_BANK0, sio.gpio_bank0, &mut pac.RESETS);
+
+    let mut vcc = pins.gpio0.into_push_pull_output();
+    let mut backlight = pins.gpio1.into_push_pull_output();
+    vcc.set_high().unwrap();
+    backlight.set_high().unwrap();
+    let _ = pins.gpio6.into_mode::<FunctionSpi>();
+    let _ = pins.gpio7.into_mode::<FunctionSpi>();
+    let spi = Spi::<_, _, 8>::new(pac.SPI0).init(&mut pac.RESETS, SYSTEM_CLOCK_HZ.Hz(), 2_000_000u32.Hz(), &MODE_0);
+    let pcd = PCD8544::new(
+        pins.gpio8.into_push_pull_output(),
+        pins.gpio5.into_push_pull_output(),
+        pins.gpio4.into_push_pull_output(),
+        spi,
+        pac.DMA,
+        &mut pac.RESETS,
+        &mut delay,
+    );
+
+    Rescue {
+        pcd,
+        delay,
+        led: pins.led.into_push_pull_output(),
+        reboot: pins.gpio22.into_pull_down_input(),
+        vcc,
+        backlight,
+    }
+}
+
+/// Reboots into BOOTSEL with the interfaces chosen in `bootsel`, the LED shows USB activity.
+pub fn reboot_to_bootloader() -> ! {
+    reset_to_usb_boot(1 << LED_PIN, bootsel::disable_mask());
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
+
+/// Resets the chip through the watchdog, like after power up.
+pub fn reboot() -> ! {
+    let watchdog = unsafe { &*pac::WATCHDOG::ptr() };
+    watchdog.ctrl.write(|w| w.trigger().set_bit());
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
@@ -0,0 +1,142 @@
+//! The screen as seen from core0, drawing happens here and core1 sends the frames.
+
+use embedded_graphics::{prelude::{OriginDimensions, Size}, pixelcolor::BinaryColor, draw_target::DrawTarget};
+use rp2040_game::display::{draw_pixels, Display, FrameBuffer, Power, BUFFER_SIZE, HEIGHT, WIDTH};
+use rp2040_game::gray::GrayCanvas;
+use rp2040_game::handoff::Producer;
+
+use super::core1::{Port, SLOTS};
+use super::{backlight, pcd8544, power, usb};
+
+/// What `Screen` asks core1 to do with the PCD8544, besides drawing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Contrast(u8),
+    PowerDown(bool),
+    /// The next frames are this many planes of a gray picture, cycled
+    /// until a frame comes after them.
+    Gray(u8),
+}
+
+impl Command {
+    pub fn to_bits(self) -> u16 {
+        match self {
+            Command::Contrast(contrast) => 0x100 | contrast as u16,
+            Command::PowerDown(powered_down) => 0x200 | powered_down as u16,
+            Command::Gray(planes) => 0x300 | planes as u16,
+        }
+    }
+
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        let value = bits as u8;
+        match bits >> 8 {
+            1 => Some(Command::Contrast(value)),
+            2 => Some(Command::PowerDown(value != 0)),
+            3 => Some(Command::Gray(value)),
+            _ => None,
+        }
+    }
+}
+
+pub struct Screen {
+    producer: Producer<'static, Port>,
+    draw_buffer: FrameBuffer,
+    powered_down: bool,
+}
+
+impl Screen {
+    /// Made by `core1::start`.
+    pub fn new(fifo: Port) -> Self {
+        Self {
+            producer: Producer::new(&SLOTS, fifo),
+            draw_buffer: [0; BUFFER_SIZE],
+            powered_down: false,
+        }
+    }
+
+    fn command(&mut self, command: Command) {
+        self.producer.send_command(command.to_bits());
+    }
+
+    /// Waits until everything sent so far reached the screen.
+    pub fn wait(&mut self) {
+        self.producer.sync();
+    }
+}
+
+impl Display for Screen {
+    fn buffer(&self) -> &FrameBuffer {
+        &self.draw_buffer
+    }
+
+    fn buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.draw_buffer
+    }
+
+    /// Returns as soon as the frame is handed over, unless core1 is two frames behind.
+    fn draw(&mut self) {
+        if let Some(contrast) = pcd8544::requested_contrast() {
+            self.command(Command::Contrast(contrast));
+        }
+
+        self.producer.send_frame(&self.draw_buffer);
+        usb::frame_drawn(&self.draw_buffer);
+    }
+
+    /// Core1 gets the planes, one frame each, and cycles them itself.
+    fn draw_gray<const PLANES: usize>(&mut self, canvas: &GrayCanvas<PLANES>) {
+        self.command(Command::Gray(PLANES as u8));
+        for plane in 0..PLANES {
+            self.producer.send_frame(canvas.plane(plane));
+        }
+        // Screenshots get the darker half, as on a screen without gray
+        usb::frame_drawn(canvas.plane(PLANES - 1));
+    }
+
+    fn set_power(&mut self, power: Power) {
+        backlight::set_power(power);
+
+        match power {
+            Power::Off if !self.powered_down => {
+                // Fade out before the screen goes blank
+                power::sleep_until(backlight::is_settled);
+                self.command(Command::PowerDown(true));
+                self.wait();
+                self.powered_down = true;
+            },
+            Power::On | Power::Dimmed if self.powered_down => {
+                self.command(Command::PowerDown(false));
+                self.powered_down = false;
+                // The RAM survives power-down, this covers a buffer changed meanwhile
+                self.draw();
+            },
+            _ => {},
+        }
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        self.command(Command::PowerDown(true));
+        self.wait();
+    }
+}
+
+impl OriginDimensions for Screen {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Screen {
+    type Color = BinaryColor;
+
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>> {
+        draw_pixels(&mut self.draw_buffer, pixels);
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use core::fmt::Write;
+
+use rp2040_game::{audio, battery, inputs};
+use rp2040_game::rand::{fixed_seed, set_fixed_seed};
+use rp2040_game::shell::{Command, HELP};
+
+use super::{backlight, pcd8544, rescue, usb};
+use super::time::uptime_us;
+
+/// Runs a debug console command, from the `USBCTRL_IRQ` interrupt.
+pub fn execute(command: Command, out: &mut impl Write) {
+    match command {
+        Command::Help => {
+            out.write_str(HELP).ok();
+        },
+        Command::Stats => {
+            let uptime_ms = uptime_us() / 1000;
+            write!(out, "uptime {}.{:03} s\r\n", uptime_ms / 1000, uptime_ms % 1000).ok();
+            write!(out, "frames {}\r\n", usb::frames()).ok();
+            write!(out, "volume {}\r\n", audio::volume()).ok();
+            if let Some(millivolts) = battery::millivolts() {
+                write!(out, "battery {} mV\r\n", millivolts).ok();
+            }
+            match fixed_seed() {
+                Some(seed) => write!(out, "seed {}\r\n", seed).ok(),
+                None => write!(out, "seed off\r\n").ok(),
+            };
+        },
+        Command::SetContrast(contrast) => pcd8544::request_contrast(contrast),
+        Command::SetVolume(volume) => audio::set_volume(volume),
+        Command::Backlight(on) => backlight::set(on),
+        Command::Seed(seed) => set_fixed_seed(seed),
+        Command::Press(button) => inputs::press(button),
+        Command::Screenshot => usb::request_screenshot(),
+        Command::Reboot { bootloader: true } => rescue::reboot_to_bootloader(),
+        Command::Reboot { bootloader: false } => rescue::reboot(),
+    }
+}
@@ -0,0 +1,14 @@
+use rp_pico::pac;
+
+/// Microseconds since boot, from the free running timer.
+pub fn uptime_us() -> u64 {
+    let timer = unsafe { &*pac::TIMER::ptr() };
+    // The raw registers don't latch, read again if the high half moved on
+    loop {
+        let high = timer.timerawh.read().bits();
+        let low = timer.timerawl.read().bits();
+        if timer.timerawh.read().bits() == high {
+            return (high as u64) << 32 | low as u64;
+        }
+    }
+}
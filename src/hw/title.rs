@@ -0,0 +1,96 @@
+use cortex_m::delay::Delay;
+use embedded_graphics::Drawable;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::{Gray2, GrayColor};
+use embedded_graphics::prelude::{Point, Size, Primitive};
+use embedded_graphics::primitives::{Rectangle, PrimitiveStyle};
+use embedded_graphics::text::Text;
+use rp2040_game::display::{Display, WIDTH};
+use rp2040_game::fonts::FONT_6X10;
+use rp2040_game::gray::Gray2Canvas;
+use rp2040_game::inputs::Input;
+use rp2040_game::splash;
+use rp_pico::hal::timer::Alarm1;
+
+use super::buttons::Scanner;
+use super::grayscale;
+use super::pcd8544::PCD8544;
+
+const SHOW_MS: u32 = 3000;
+const TICK_MS: u32 = 20;
+
+/// Frames for the bands to move one step.
+const SCROLL_TICKS: u32 = 10;
+
+fn render(canvas: &mut Gray2Canvas, step: u8) {
+    canvas.clear();
+
+    // Shaded bands behind the title, cycling through the grays
+    for band in 0..4 {
+        Rectangle::new(Point::new(0, band as i32 * 12), Size::new(WIDTH as u32, 12))
+            .into_styled(PrimitiveStyle::with_fill(Gray2::new((band + step) % 4)))
+            .draw(canvas).unwrap();
+    }
+
+    let style = MonoTextStyle::new(&FONT_6X10, Gray2::BLACK);
+    Text::new("RP2040", Point::new(24, 20), style).draw(canvas).unwrap();
+    Text::new("GAME", Point::new(30, 32), style).draw(canvas).unwrap();
+}
+
+/// Scans the buttons, the tasks don't run yet. `true` once one is pressed.
+fn pressed<I: Input>(scanner: &mut Scanner, inputs: &mut I) -> bool {
+    scanner.scan();
+    inputs.update();
+    inputs.any_pressed()
+}
+
+/// Plays `splash`, `false` if a button cut it short.
+fn play_splash<I: Input>(pcd: &mut PCD8544, scanner: &mut Scanner, inputs: &mut I, delay: &mut Delay) -> bool {
+    for frame in 0..splash::FRAMES {
+        splash::render(pcd, frame);
+        pcd.draw();
+        if pressed(scanner, inputs) {
+            return false;
+        }
+        delay.delay_ms(splash::FRAME_MS);
+    }
+    true
+}
+
+/// Boot splash followed by the grayscale title screen, each shown until a
+/// button is pressed or some time passes. A press skips both.
+///
+/// `alarm` is dropped once it's over, the gray screens after this one are
+/// cycled by core1, see `Display::draw_gray`.
+pub fn show<I: Input>(
+    mut pcd: PCD8544,
+    alarm:   Alarm1,
+    scanner: &mut Scanner,
+    inputs:  &mut I,
+    delay:   &mut Delay) -> PCD8544
+{
+    if !play_splash(&mut pcd, scanner, inputs, delay) {
+        return pcd;
+    }
+
+    let mut canvas = Gray2Canvas::new();
+    render(&mut canvas, 0);
+    grayscale::start(pcd, alarm, &canvas);
+
+    let mut ticks = 0;
+    while ticks * TICK_MS < SHOW_MS {
+        if pressed(scanner, inputs) {
+            break;
+        }
+        delay.delay_ms(TICK_MS);
+        ticks += 1;
+
+        if ticks % SCROLL_TICKS == 0 {
+            render(&mut canvas, (ticks / SCROLL_TICKS) as u8);
+            grayscale::update(&canvas);
+        }
+    }
+
+    let (pcd, _alarm) = grayscale::stop().unwrap();
+    pcd
+}
@@ -0,0 +1,162 @@
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use cortex_m::interrupt::Mutex;
+use heapless::Vec;
+use rp2040_game::display::{FrameBuffer, BUFFER_SIZE};
+use rp2040_game::screenshot;
+use rp2040_game::shell::LineReader;
+use rp_pico::hal::usb::UsbBus;
+use rp_pico::pac::{self, Interrupt};
+use usb_device::{class_prelude::UsbBusAllocator, prelude::*};
+use usbd_serial::SerialPort;
+
+use super::shell;
+
+/// Room for a screenshot with its header line, and some text.
+const OUTGOING_SIZE: usize = 1024;
+const SCREENSHOT_SIZE: usize = 16 + screenshot::PBM_SIZE;
+const PROMPT: &str = "> ";
+
+/// CDC-ACM serial port with the debug console, served from the `USBCTRL_IRQ` interrupt.
+struct Usb {
+    device: UsbDevice<'static, UsbBus>,
+    serial: SerialPort<'static, UsbBus>,
+    reader: LineReader,
+    /// Bytes waiting for room in the serial port.
+    outgoing: Vec<u8, OUTGOING_SIZE>,
+    sent: usize,
+}
+
+static USB: Mutex<RefCell<Option<Usb>>> = Mutex::new(RefCell::new(None));
+/// Copy of the last frame sent to the screen.
+static LAST_FRAME: Mutex<RefCell<FrameBuffer>> = Mutex::new(RefCell::new([0; BUFFER_SIZE]));
+static SCREENSHOT: AtomicBool = AtomicBool::new(false);
+static FRAMES: AtomicU32 = AtomicU32::new(0);
+
+impl Usb {
+    fn received(&mut self, byte: u8) {
+        // Echo for terminals
+        match byte {
+            b'\r' | b'\n' => self.write_str("\r\n").ok(),
+            0x08 | 0x7f => self.write_str("\x08 \x08").ok(),
+            b' '..=b'~' => self.outgoing.push(byte).ok(),
+            _ => None,
+        };
+
+        if let Some(parsed) = self.reader.push(byte) {
+            match parsed {
+                Ok(command) => shell::execute(command, self),
+                Err(err) => {
+                    write!(self, "{}\r\n", err.message()).ok();
+                },
+            }
+            self.write_str(PROMPT).ok();
+        }
+    }
+
+    fn queue_screenshot(&mut self, frame: &FrameBuffer) {
+        self.outgoing.extend_from_slice(screenshot::frame_header().as_bytes()).unwrap();
+        self.outgoing.extend_from_slice(&screenshot::encode(frame)).unwrap();
+    }
+
+    fn has_room(&self, len: usize) -> bool {
+        self.outgoing.capacity() - self.outgoing.len() >= len
+    }
+
+    fn flush(&mut self) {
+        // Nobody to listen, don't keep the bytes around
+        if self.device.state() != UsbDeviceState::Configured {
+            self.outgoing.clear();
+            self.sent = 0;
+            return;
+        }
+
+        while self.sent < self.outgoing.len() {
+            match self.serial.write(&self.outgoing[self.sent..]) {
+                Ok(count) if count > 0 => self.sent += count,
+                // Carries on once the host took the last packet
+                _ => return,
+            }
+        }
+        self.outgoing.clear();
+        self.sent = 0;
+    }
+}
+
+/// Text that doesn't fit is dropped.
+impl fmt::Write for Usb {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        for &byte in text.as_bytes() {
+            if self.outgoing.push(byte).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Brings up the serial port, the `USBCTRL_IRQ` interrupt has to be unmasked.
+pub fn init(bus: UsbBus) {
+    let allocator = cortex_m::singleton!(: UsbBusAllocator<UsbBus> = UsbBusAllocator::new(bus)).unwrap();
+    let serial = SerialPort::new(allocator);
+    // Test VID/PID pair shared by hobby projects
+    let device = UsbDeviceBuilder::new(allocator, UsbVidPid(0x16c0, 0x27dd))
+        .manufacturer("rp2040-game")
+        .product("RP2040 Game")
+        .serial_number("0001")
+        .device_class(usbd_serial::USB_CLASS_CDC)
+        .build();
+
+    cortex_m::interrupt::free(|cs| {
+        USB.borrow(cs).replace(Some(Usb {
+            device,
+            serial,
+            reader: LineReader::new(),
+            outgoing: Vec::new(),
+            sent: 0,
+        }));
+    });
+}
+
+/// To be called with every frame sent to the screen, keeps it for screenshots.
+pub fn frame_drawn(buffer: &FrameBuffer) {
+    cortex_m::interrupt::free(|cs| {
+        *LAST_FRAME.borrow(cs).borrow_mut() = *buffer;
+        FRAMES.store(FRAMES.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+    });
+}
+
+/// Frames sent to the screen since boot.
+pub fn frames() -> u32 {
+    FRAMES.load(Ordering::Relaxed)
+}
+
+/// Sends the last frame to the host as soon as the port is free.
+pub fn request_screenshot() {
+    SCREENSHOT.store(true, Ordering::Relaxed);
+    pac::NVIC::pend(Interrupt::USBCTRL_IRQ);
+}
+
+/// To be called from `USBCTRL_IRQ`.
+pub fn on_interrupt() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(usb) = USB.borrow(cs).borrow_mut().as_mut() {
+            if usb.device.poll(&mut [&mut usb.serial]) {
+                let mut received = [0; 64];
+                if let Ok(count) = usb.serial.read(&mut received) {
+                    for &byte in &received[..count] {
+                        usb.received(byte);
+                    }
+                }
+            }
+
+            if SCREENSHOT.load(Ordering::Relaxed) && usb.has_room(SCREENSHOT_SIZE) {
+                SCREENSHOT.store(false, Ordering::Relaxed);
+                usb.queue_screenshot(&LAST_FRAME.borrow(cs).borrow());
+            }
+            usb.flush();
+        }
+    });
+}
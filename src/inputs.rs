@@ -1,58 +1,79 @@
-use embedded_hal::digital::v2::InputPin;
-use rp_pico::hal::gpio::{bank0::{Gpio21, Gpio20, Gpio19, Gpio18}, PullDownInput};
-use rp_pico::hal::gpio::Pin;
-
-type GpioMode = PullDownInput;
-type GpioIn0 = Gpio21;
-type GpioIn1 = Gpio20;
-type GpioIn2 = Gpio19;
-type GpioIn3 = Gpio18;
-
-pub struct Inputs {
-    but0: Pin<GpioIn0, GpioMode>,
-    but1: Pin<GpioIn1, GpioMode>,
-    but2: Pin<GpioIn2, GpioMode>,
-    but3: Pin<GpioIn3, GpioMode>,
+//! The four buttons, as the menus and games see them.
+
+use core::cell::Cell;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::Poll;
+
+use bare_metal::Mutex;
+
+use crate::tasks::{self, Timer};
+
+/// Presses waiting for `Buttons::update`, one bit per button.
+static PRESSES: Mutex<Cell<u8>> = Mutex::new(Cell::new(0));
+
+/// Source of the four button presses, either real buttons or a computer player.
+pub trait Input {
+    fn update(&mut self);
+    fn is_pressed(&self) -> &[bool; 4];
+
+    fn any_pressed(&self) -> bool {
+        self.is_pressed().iter().any(|x| *x)
+    }
+
+    /// Ends the game right away, regardless of its state.
+    fn interrupted(&self) -> bool {
+        false
+    }
+}
+
+/// Presses `button`, for the task scanning the buttons and for the debug console.
+pub fn press(button: usize) {
+    critical_section::with(|cs| {
+        let presses = PRESSES.borrow(cs);
+        presses.set(presses.get() | 1 << button);
+    });
+    tasks::wake();
+}
+
+/// The buttons pressed through `press`, each press shows in one `update`.
+#[derive(Default)]
+pub struct Buttons {
     pressed: [bool; 4],
-    pressed_once: [bool; 4],
 }
 
-impl Inputs {
-    pub fn new(
-        but0: Pin<GpioIn0, GpioMode>,
-        but1: Pin<GpioIn1, GpioMode>,
-        but2: Pin<GpioIn2, GpioMode>,
-        but3: Pin<GpioIn3, GpioMode>,
-    ) -> Self {
-        Self {
-            but0, but1, but2, but3,
-            pressed: [false; 4],
-            pressed_once: [false; 4],
-        }
+impl Buttons {
+    pub fn new() -> Self {
+        Self::default()
     }
+}
 
-    fn update_button(&mut self, is_pressed: bool, index: usize) {
-        if is_pressed {
-            if !self.pressed[index] {
-                self.pressed_once[index] = true;
-            } else {
-                self.pressed_once[index] = false;
-            }
-            self.pressed[index] = true;
-        } else {
-            self.pressed[index] = false;
-            self.pressed_once[index] = false;
+impl Input for Buttons {
+    fn update(&mut self) {
+        let presses = critical_section::with(|cs| PRESSES.borrow(cs).replace(0));
+        for (index, pressed) in self.pressed.iter_mut().enumerate() {
+            *pressed = presses & 1 << index != 0;
         }
     }
 
-    pub fn update(&mut self) {
-        self.update_button(self.but0.is_high().unwrap(), 0);
-        self.update_button(self.but1.is_high().unwrap(), 1);
-        self.update_button(self.but2.is_high().unwrap(), 2);
-        self.update_button(self.but3.is_high().unwrap(), 3);
+    fn is_pressed(&self) -> &[bool; 4] {
+        &self.pressed
     }
+}
 
-    pub fn is_pressed(&self) -> &[bool; 4] {
-        &self.pressed_once
-    }
+/// Updates `inputs` until a button gets pressed, `false` if `timeout_ms` passes first.
+///
+/// The press shows in `is_pressed` afterwards, as after any `update`.
+pub async fn next_press(inputs: &mut impl Input, timeout_ms: Option<u32>) -> bool {
+    let mut timeout = timeout_ms.map(Timer::after_ms);
+    poll_fn(|cx| {
+        inputs.update();
+        if inputs.any_pressed() {
+            return Poll::Ready(true);
+        }
+        match timeout.as_mut().map(|timer| Pin::new(timer).poll(cx)) {
+            Some(Poll::Ready(())) => Poll::Ready(false),
+            _ => Poll::Pending,
+        }
+    }).await
 }
@@ -0,0 +1,145 @@
+//! Labels in Polish and English, the language picked in the menu and kept in the settings.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::audio::MAX_VOLUME;
+use crate::backlight::MAX_LEVEL;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    Polish,
+    English,
+}
+
+impl Language {
+    /// The one after this in the menu.
+    pub fn next(self) -> Self {
+        match self {
+            Language::Polish  => Language::English,
+            Language::English => Language::Polish,
+        }
+    }
+
+    /// Number kept in the settings.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Language::Polish  => 0,
+            Language::English => 1,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Language::Polish),
+            1 => Some(Language::English),
+            _ => None,
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn language() -> Language {
+    Language::from_u8(LANGUAGE.load(Ordering::Relaxed)).unwrap()
+}
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language.to_u8(), Ordering::Relaxed);
+}
+
+/// Every label in one language. Those for a setting have one per value.
+pub struct Strings {
+    pub menu: &'static str,
+    pub play: &'static str,
+    pub brightness: [&'static str; MAX_LEVEL as usize + 1],
+    pub volume: [&'static str; MAX_VOLUME as usize + 1],
+    pub language: &'static str,
+    pub debug: &'static str,
+    pub about: &'static str,
+    pub quit: &'static str,
+    pub snake: &'static str,
+    pub ping_pong: &'static str,
+    pub breakout: &'static str,
+    pub tetris: &'static str,
+    /// Goes before the level number.
+    pub level: &'static str,
+    pub fixed_seed: [&'static str; 2],
+    pub mass_storage: [&'static str; 2],
+    pub picoboot: [&'static str; 2],
+    /// About page, each goes before a value.
+    pub built: &'static str,
+    pub serial: &'static str,
+    pub uptime: &'static str,
+    /// Reboot button screen.
+    pub restarting: &'static str,
+    pub bootsel: &'static str,
+    pub release_to_restart: &'static str,
+    pub hold_for_bootsel: &'static str,
+    /// Goes before the seconds left until BOOTSEL.
+    pub bootsel_in: &'static str,
+    pub battery_empty: &'static str,
+}
+
+pub const POLISH: Strings = Strings {
+    menu: "Menu",
+    play: "Graj",
+    brightness: ["Podświetl:0", "Podświetl:1", "Podświetl:2", "Podświetl:3"],
+    volume: ["Dźwięk:0", "Dźwięk:1", "Dźwięk:2", "Dźwięk:3"],
+    language: "Język:PL",
+    debug: "Debug",
+    about: "O programie",
+    quit: "Wyjdź",
+    snake: "Wąż",
+    ping_pong: "PingPong",
+    breakout: "Arkanoid",
+    tetris: "Tetris",
+    level: "Poziom",
+    fixed_seed: ["Ziarno:0", "Ziarno:1"],
+    mass_storage: ["USB MSD:0", "USB MSD:1"],
+    picoboot: ["PICOBOOT:0", "PICOBOOT:1"],
+    built: "Data",
+    serial: "Nr seryjny:",
+    uptime: "Działa",
+    restarting: "Restart",
+    bootsel: "BOOTSEL",
+    release_to_restart: "Puść: restart",
+    hold_for_bootsel: "Trzymaj: BOOTSEL",
+    bootsel_in: "za",
+    battery_empty: "Bateria\nrozładowana",
+};
+
+pub const ENGLISH: Strings = Strings {
+    menu: "Menu",
+    play: "Play",
+    brightness: ["Light:0", "Light:1", "Light:2", "Light:3"],
+    volume: ["Sound:0", "Sound:1", "Sound:2", "Sound:3"],
+    language: "Lang:EN",
+    debug: "Debug",
+    about: "About",
+    quit: "Quit",
+    snake: "Snake",
+    ping_pong: "PingPong",
+    breakout: "Breakout",
+    tetris: "Tetris",
+    level: "Level",
+    fixed_seed: ["Seed:0", "Seed:1"],
+    mass_storage: ["USB MSD:0", "USB MSD:1"],
+    picoboot: ["PICOBOOT:0", "PICOBOOT:1"],
+    built: "Built",
+    serial: "Serial:",
+    uptime: "Uptime",
+    restarting: "Restart",
+    bootsel: "BOOTSEL",
+    release_to_restart: "Release: restart",
+    hold_for_bootsel: "Hold: BOOTSEL",
+    bootsel_in: "in",
+    battery_empty: "Battery\nempty",
+};
+
+/// Labels in the language picked right now.
+pub fn strings() -> &'static Strings {
+    match language() {
+        Language::Polish  => &POLISH,
+        Language::English => &ENGLISH,
+    }
+}
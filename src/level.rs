@@ -0,0 +1,59 @@
+//! Level maps from `assets/levels`, packed by `build.rs`, see `assets`.
+//!
+//! A cell is a nibble, 0 for empty and the rest meaning whatever the game
+//! makes of them. Cells go row by row, two a byte with the first in the
+//! high nibble.
+
+#[derive(Clone, Copy, Debug)]
+pub struct Level<'a> {
+    pub width: usize,
+    pub height: usize,
+    cells: &'a [u8],
+}
+
+impl<'a> Level<'a> {
+    pub const fn new(width: usize, height: usize, cells: &'a [u8]) -> Self {
+        assert!(cells.len() == (width * height).div_ceil(2));
+        Self { width, height, cells }
+    }
+
+    /// Cell at `x`, `y`, 0 outside the level.
+    pub fn cell(&self, x: usize, y: usize) -> u8 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        let index = y * self.width + x;
+        let byte = self.cells[index / 2];
+        if index.is_multiple_of(2) { byte >> 4 } else { byte & 0x0f }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        // 1.2
+        // F.A
+        let level = Level::new(3, 2, &[0x10, 0x2f, 0x0a]);
+        assert_eq!([level.cell(0, 0), level.cell(1, 0), level.cell(2, 0)], [1, 0, 2]);
+        assert_eq!([level.cell(0, 1), level.cell(1, 1), level.cell(2, 1)], [15, 0, 10]);
+    }
+
+    #[test]
+    fn outside_is_empty() {
+        let level = Level::new(1, 1, &[0xff]);
+        assert_eq!(level.cell(0, 0), 15);
+        // The low nibble of the last byte is padding
+        assert_eq!(level.cell(1, 0), 0);
+        assert_eq!(level.cell(0, 1), 0);
+        assert_eq!(level.cell(usize::MAX, 0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        Level::new(3, 2, &[0x10, 0x2f]);
+    }
+}
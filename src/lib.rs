@@ -0,0 +1,37 @@
+//! Menus, games and effects of the console, independent of the hardware.
+//!
+//! Everything here draws into a `display::Display` and reads an
+//! `inputs::Input`, waiting on the timers of `tasks`, so the same code runs
+//! in the firmware (`main.rs`) and in the host simulator (`bin/sim.rs`).
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod about;
+pub mod assets;
+pub mod audio;
+pub mod backlight;
+pub mod battery;
+pub mod bootsel;
+pub mod console;
+pub mod debug_menu;
+pub mod display;
+pub mod fonts;
+pub mod games;
+pub mod handoff;
+pub mod gray;
+pub mod inputs;
+pub mod lang;
+pub mod level;
+pub mod main_menu;
+pub mod menu;
+pub mod power;
+pub mod rand;
+pub mod rotated;
+pub mod screenshot;
+pub mod settings;
+pub mod sfx;
+pub mod splash;
+pub mod sprite;
+pub mod shell;
+pub mod tasks;
+pub mod tilemap;
@@ -1,40 +1,40 @@
 #![no_std]
 #![no_main]
 
-mod pcd8544;
-mod inputs;
-mod menu;
-mod games;
-mod rand;
-mod sfx;
+mod hw;
 
-use games::games_menu::GamesMenu;
-use inputs::Inputs;
-use menu::{Menu, MenuOption};
-use core::cell::RefCell;
+use core::future::Future;
+use core::pin::{pin, Pin};
 
-use cortex_m::interrupt::Mutex;
-use pcd8544::PCD8544;
+use hw::buttons::{self, Scanner};
+use hw::buzzer::Buzzer;
+use hw::power::Platform;
+use hw::screen::Screen;
+use hw::{backlight, battery, core1, entropy, flash, grayscale, reboot, title, usb};
+use rp2040_game::display::Display;
+use rp2040_game::inputs::Buttons;
+use rp2040_game::main_menu::{MainMenu, System};
+use rp2040_game::settings::Settings;
+use rp2040_game::tasks;
+
+use hw::pcd8544::PCD8544;
 
 use cortex_m_rt::entry;
-use embedded_hal::{digital::v2::{OutputPin, ToggleableOutputPin}, spi::MODE_0};
+use embedded_hal::{digital::v2::OutputPin, spi::MODE_0};
 use embedded_time::{fixed_point::FixedPoint, rate::Extensions};
 
 use rp_pico as bsp;
-use panic_halt as _;
 
 use bsp::hal::{
     clocks::{init_clocks_and_plls, Clock},
     pac,
     sio::Sio,
-    watchdog::Watchdog, gpio::FunctionSpi, Spi, rom_data::reset_to_usb_boot,
+    watchdog::Watchdog, gpio::FunctionSpi, Spi,
+    adc::Adc, Timer, pwm::Slices, usb::UsbBus,
 };
 
 use pac::interrupt;
 
-type RebootPin = bsp::hal::gpio::Pin<bsp::hal::gpio::bank0::Gpio22, bsp::hal::gpio::PullDownInput>;
-static REBOOT_PIN: Mutex<RefCell<Option<RebootPin>>> = Mutex::new(RefCell::new(None));
-
 #[entry]
 fn main() -> ! {
     let mut pac = pac::Peripherals::take().unwrap();
@@ -58,6 +58,14 @@ fn main() -> ! {
 
     let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().integer());
 
+    usb::init(UsbBus::new(
+        pac.USBCTRL_REGS,
+        pac.USBCTRL_DPRAM,
+        clocks.usb_clock,
+        true,
+        &mut pac.RESETS,
+    ));
+
     let pins = bsp::Pins::new(
         pac.IO_BANK0,
         pac.PADS_BANK0,
@@ -67,96 +75,129 @@ fn main() -> ! {
 
     let mut led_pin = pins.led.into_push_pull_output();
 
-    let reboot_pin = pins.gpio22.into_pull_down_input();
-    reboot_pin.set_interrupt_enabled(bsp::hal::gpio::Interrupt::EdgeHigh, true);
-    cortex_m::interrupt::free(|cs| {
-        REBOOT_PIN.borrow(cs).replace(Some(reboot_pin));
-    });
+    let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS);
+    let mut adc = Adc::new(pac.ADC, &mut pac.RESETS);
+    entropy::gather(&pac.ROSC, &mut adc, &mut pins.gpio28.into_floating_input());
+    let monitor = battery::Monitor::new(adc, pins.voltage_monitor.into_floating_input());
+
+    if let Some(settings) = flash::load_settings() {
+        settings.apply();
+    }
+
+    reboot::init(pins.gpio22.into_pull_down_input());
+
+    let mut pwm_slices = Slices::new(pac.PWM, &mut pac.RESETS);
+    let backlight_pin = pwm_slices.pwm0.channel_b.output_to(pins.gpio1);
+    backlight::init(pwm_slices.pwm0, backlight_pin);
+    let buzzer_pin = pwm_slices.pwm7.channel_b.output_to(pins.gpio15);
+    let buzzer = Buzzer::new(pwm_slices.pwm7, buzzer_pin);
 
     unsafe {
         pac::NVIC::unmask(pac::Interrupt::IO_IRQ_BANK0);
+        pac::NVIC::unmask(pac::Interrupt::PWM_IRQ_WRAP);
+        pac::NVIC::unmask(pac::Interrupt::TIMER_IRQ_1);
+        pac::NVIC::unmask(pac::Interrupt::TIMER_IRQ_3);
+        pac::NVIC::unmask(pac::Interrupt::USBCTRL_IRQ);
     }
 
     let mut vcc_pin = pins.gpio0.into_push_pull_output();
-    {
-        led_pin.set_high().unwrap();
-        let mut bl_pin  = pins.gpio1.into_push_pull_output();
-        let rst_pin = pins.gpio8.into_push_pull_output();
-        let ce_pin  = pins.gpio5.into_push_pull_output();
-        let dc_pin  = pins.gpio4.into_push_pull_output();
-        //let mut din_pin = pins.gpio7.into_push_pull_output();
-        //let mut clk_pin = pins.gpio6.into_push_pull_output();
-        let _ = pins.gpio6.into_mode::<FunctionSpi>();
-        let _ = pins.gpio7.into_mode::<FunctionSpi>();
-        let spi = Spi::<_, _, 8>::new(pac.SPI0).init(&mut pac.RESETS, 125_000_000u32.Hz(), 2_000_000u32.Hz(), &MODE_0);
-
-        vcc_pin.set_high().unwrap();
-        bl_pin.set_high().unwrap();
-        let mut pcd = PCD8544::new(rst_pin, ce_pin, dc_pin, spi, &mut delay);
-
-        led_pin.set_low().unwrap();
-
-        let mut inputs = Inputs::new(
-            pins.gpio21.into_pull_down_input(),
-            pins.gpio20.into_pull_down_input(),
-            pins.gpio19.into_pull_down_input(),
-            pins.gpio18.into_pull_down_input(),
-        );
-
-        #[derive(Clone, Copy)]
-        enum MenuSelected {
-            Play, Backlight, Quit
-        }
-
-        'menu: loop {
-            let mut menu = Menu::new(
-                "Menu",
-                [
-                    MenuOption::new(MenuSelected::Play, "Graj"),
-                    MenuOption::new(MenuSelected::Backlight, "Podswl"),
-                    MenuOption::new(MenuSelected::Quit, "Wyjdz"),
-                ]
-                );
-
-            match menu.run(&mut pcd, &mut inputs, &mut delay) {
-                MenuSelected::Play => {
-                    GamesMenu::run(&mut pcd, &mut inputs, &mut delay);
-                },
-                MenuSelected::Backlight => {
-                    bl_pin.toggle().unwrap();
-                },
-                MenuSelected::Quit => {
-                    break 'menu;
-                }
-            }
-        }
-
-        bl_pin.set_low().unwrap();
+    led_pin.set_high().unwrap();
+    let rst_pin = pins.gpio8.into_push_pull_output();
+    let ce_pin  = pins.gpio5.into_push_pull_output();
+    let dc_pin  = pins.gpio4.into_push_pull_output();
+    //let mut din_pin = pins.gpio7.into_push_pull_output();
+    //let mut clk_pin = pins.gpio6.into_push_pull_output();
+    let _ = pins.gpio6.into_mode::<FunctionSpi>();
+    let _ = pins.gpio7.into_mode::<FunctionSpi>();
+    let spi = Spi::<_, _, 8>::new(pac.SPI0).init(&mut pac.RESETS, 125_000_000u32.Hz(), 2_000_000u32.Hz(), &MODE_0);
+
+    vcc_pin.set_high().unwrap();
+    // Fades in while the title shows
+    backlight::set(true);
+    let pcd = PCD8544::new(rst_pin, ce_pin, dc_pin, spi, pac.DMA, &mut pac.RESETS, &mut delay);
+
+    led_pin.set_low().unwrap();
+
+    let mut scanner = Scanner::new(
+        pins.gpio21.into_pull_down_input(),
+        pins.gpio20.into_pull_down_input(),
+        pins.gpio19.into_pull_down_input(),
+        pins.gpio18.into_pull_down_input(),
+    );
+    let mut inputs = Buttons::new();
+
+    // Read while core1 can't be running from the flash yet
+    let serial = flash::unique_id();
+
+    let pcd = title::show(pcd, timer.alarm_1().unwrap(), &mut scanner, &mut inputs, &mut delay);
+    // Frames get sent by core1 from here on
+    let pcd = core1::start(pcd);
+
+    // Everything else runs as tasks, sleeping while they all wait
+    let audio = pin!(buzzer.run());
+    let buttons = pin!(scanner.run());
+    let game = pin!(play(pcd, inputs, serial));
+    let battery = pin!(monitor.run());
+    let mut all: [Pin<&mut dyn Future<Output = ()>>; 4] = [audio, buttons, game, battery];
+    tasks::run(&mut Platform::new(timer.alarm_3().unwrap()), &mut all);
+    unreachable!()
+}
+
+/// What the main menu needs of the board.
+struct Board {
+    serial: u64,
+}
+
+impl System for Board {
+    fn serial(&self) -> Option<u64> {
+        Some(self.serial)
     }
-    vcc_pin.set_low().unwrap();
 
-    loop {
-        led_pin.toggle().unwrap();
-        delay.delay_ms(1000);
-        cortex_m::asm::nop();
+    /// The settings are kept for the next power up, then the console sleeps.
+    fn quit<D: Display>(&mut self, _pcd: &mut D) -> bool {
+        flash::save_settings(&Settings::current());
+        true
     }
+
+    fn set_backlight(&mut self, level: u8) {
+        backlight::set_level(level);
+    }
+}
+
+/// The main menu, for good.
+async fn play(mut pcd: Screen, mut inputs: Buttons, serial: u64) {
+    MainMenu::run(&mut pcd, &mut inputs, &mut Board { serial }).await;
 }
 
 #[allow(non_snake_case)]
 #[interrupt]
 fn IO_IRQ_BANK0() {
-    static mut REBOOT_BUTTON: Option<RebootPin> = None;
+    reboot::on_interrupt();
+    buttons::on_interrupt();
+}
 
-    if REBOOT_BUTTON.is_none() {
-        cortex_m::interrupt::free(|cs| {
-            *REBOOT_BUTTON = REBOOT_PIN.borrow(cs).take();
-        });
-    }
+#[allow(non_snake_case)]
+#[interrupt]
+fn PWM_IRQ_WRAP() {
+    backlight::on_wrap();
+}
 
-    if let Some(button) = REBOOT_BUTTON {
-        reset_to_usb_boot(0, 0);
-        button.clear_interrupt(bsp::hal::gpio::Interrupt::EdgeHigh);
-    }
+#[allow(non_snake_case)]
+#[interrupt]
+fn TIMER_IRQ_1() {
+    grayscale::on_alarm();
+}
+
+#[allow(non_snake_case)]
+#[interrupt]
+fn TIMER_IRQ_3() {
+    hw::power::on_alarm();
+}
+
+#[allow(non_snake_case)]
+#[interrupt]
+fn USBCTRL_IRQ() {
+    usb::on_interrupt();
 }
 
 // End of file
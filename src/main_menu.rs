@@ -0,0 +1,106 @@
+//! The menu the console starts in, the same in the firmware and in the simulator.
+//!
+//! Settings picked here take effect right away. Keeping them, and anything
+//! else that needs the hardware, is up to a `System`.
+
+use crate::about::AboutPage;
+use crate::audio::{self, MAX_VOLUME};
+use crate::backlight::{self, MAX_LEVEL};
+use crate::debug_menu::DebugMenu;
+use crate::display::Display;
+use crate::games::attract::AttractMode;
+use crate::games::games_menu::GamesMenu;
+use crate::games::snake::SnakeInput;
+use crate::lang;
+use crate::menu::{Menu, MenuOption};
+use crate::power;
+
+/// Time the main menu waits for input before the attract mode starts.
+pub const ATTRACT_IDLE_MS: u32 = 30_000;
+
+/// What the main menu leaves to the firmware or the simulator.
+pub trait System {
+    /// Unique ID of the board for the about page, if there is one.
+    fn serial(&self) -> Option<u64> {
+        None
+    }
+
+    /// Picked "Quit", the music is stopped already. `true` puts the console
+    /// to sleep, `false` leaves the menu.
+    fn quit<D: Display>(&mut self, pcd: &mut D) -> bool;
+
+    /// Picked another brightness, the firmware fades the backlight to it.
+    fn set_backlight(&mut self, level: u8) {
+        backlight::set_level(level);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MainSelected {
+    Play, Backlight, Sound, Language, Debug, About, Quit
+}
+
+/// Loops until a game or the sleep stops it.
+fn play_menu_theme() {
+    audio::play_melody(audio::melodies::MENU_THEME, true).unwrap();
+}
+
+pub struct MainMenu;
+
+impl MainMenu {
+    /// Only returns once `System::quit` says so, in the firmware never.
+    pub async fn run<D: Display, I: SnakeInput, S: System>(pcd: &mut D, inputs: &mut I, system: &mut S) {
+        play_menu_theme();
+        loop {
+            let strings = lang::strings();
+            let mut menu = Menu::new(
+                strings.menu,
+                [
+                    MenuOption::new(MainSelected::Play, strings.play),
+                    MenuOption::new(MainSelected::Backlight, strings.brightness[backlight::level() as usize]),
+                    MenuOption::new(MainSelected::Sound, strings.volume[audio::volume() as usize]),
+                    MenuOption::new(MainSelected::Language, strings.language),
+                    MenuOption::new(MainSelected::Debug, strings.debug),
+                    MenuOption::new(MainSelected::About, strings.about),
+                    MenuOption::new(MainSelected::Quit, strings.quit),
+                ]
+                );
+
+            match menu.run_idle(pcd, inputs, Some(ATTRACT_IDLE_MS)).await {
+                None => {
+                    if !AttractMode::run(pcd, inputs).await {
+                        power::sleep(pcd, inputs).await;
+                    }
+                    play_menu_theme();
+                },
+                Some(MainSelected::Play) => {
+                    GamesMenu::run(pcd, inputs).await;
+                    play_menu_theme();
+                },
+                Some(MainSelected::Backlight) => {
+                    system.set_backlight((backlight::level() + 1) % (MAX_LEVEL + 1));
+                },
+                Some(MainSelected::Sound) => {
+                    audio::set_volume((audio::volume() + 1) % (MAX_VOLUME + 1));
+                },
+                Some(MainSelected::Language) => {
+                    lang::set_language(lang::language().next());
+                },
+                Some(MainSelected::Debug) => {
+                    DebugMenu::run(pcd, inputs).await;
+                },
+                Some(MainSelected::About) => {
+                    AboutPage::run(pcd, inputs, system.serial()).await;
+                },
+                Some(MainSelected::Quit) => {
+                    audio::stop_music();
+                    if !system.quit(pcd) {
+                        return;
+                    }
+                    power::sleep(pcd, inputs).await;
+                    play_menu_theme();
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,21 @@
-use cortex_m::delay::Delay;
 use embedded_graphics::Drawable;
 use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::mono_font::ascii::FONT_6X10;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::{Point, Size, Primitive};
 use embedded_graphics::primitives::{Rectangle, PrimitiveStyle, Line};
 use embedded_graphics::text::Text;
 
-use crate::inputs::Inputs;
-use crate::pcd8544::PCD8544;
+use crate::audio::{self, effects::Effect};
+use crate::battery;
+use crate::inputs::{self, Input};
+use crate::display::Display;
+use crate::fonts::FONT_6X10;
+use crate::power::IdleTimer;
+use crate::sfx::transition::{Transition, TransitionKind};
+use crate::tasks::{self, Ticker};
 
 const WIDTH: usize = 84;
+const ENTER_FRAME_MS: u32 = 25;
 
 struct Selected<const OPTION_COUNT: usize>(usize);
 impl<const OPTION_COUNT: usize> Selected<OPTION_COUNT> {
@@ -67,27 +72,77 @@ impl<'a, OptionId: Copy, const OPTION_COUNT: usize> Menu<'a, OptionId, OPTION_CO
         }
     }
 
-    pub fn run(&mut self, pcd: &mut PCD8544, inputs: &mut Inputs, delay: &mut Delay) -> OptionId {
-        self.draw(pcd);
+    pub async fn run<D: Display, I: Input>(&mut self, pcd: &mut D, inputs: &mut I) -> OptionId {
         loop {
-            inputs.update();
+            if let Some(id) = self.run_idle(pcd, inputs, None).await {
+                return id;
+            }
+        }
+    }
+
+    /// Like `run`, but gives up and returns `None` once no button
+    /// was pressed for `idle_ms` milliseconds.
+    pub async fn run_idle<D: Display, I: Input>(
+        &mut self,
+        pcd:     &mut D,
+        inputs:  &mut I,
+        idle_ms: Option<u32>) -> Option<OptionId>
+    {
+        let mut since_ms = tasks::now_ms();
+        let mut idle_timer = IdleTimer::new();
+        self.enter(pcd).await;
+        loop {
+            let mut timeout_ms = idle_timer.remaining_ms();
+            if let Some(idle_ms) = idle_ms {
+                let left_ms = (since_ms + idle_ms as u64).saturating_sub(tasks::now_ms());
+                timeout_ms = timeout_ms.min(left_ms as u32);
+            }
+            inputs::next_press(inputs, Some(timeout_ms)).await;
+            idle_timer.update(pcd, inputs).await;
+
             let inputs = inputs.is_pressed();
             if inputs[0] {
-                return self.options[self.selected.0].id;
+                audio::play(Effect::Confirm);
+                return Some(self.options[self.selected.0].id);
             }
             else if inputs[1] {
                 self.selected.inc();
+                audio::play(Effect::MenuMove);
                 self.draw(pcd);
+                since_ms = tasks::now_ms();
             } else if inputs[2] {
                 self.selected.dec();
+                audio::play(Effect::MenuMove);
                 self.draw(pcd);
+                since_ms = tasks::now_ms();
+            } else if let Some(idle_ms) = idle_ms {
+                if tasks::now_ms() - since_ms >= idle_ms as u64 {
+                    return None;
+                }
             }
-            delay.delay_ms(100);
         }
     }
 
-    pub fn draw(&self, pcd: &mut PCD8544) {
-        pcd.clear();
+    /// Wipes the menu in over whatever was on the screen.
+    async fn enter<D: Display>(&self, pcd: &mut D) {
+        let from = *pcd.buffer();
+        self.render(pcd);
+        let mut wipe = Transition::new(TransitionKind::Wipe, &from, pcd.buffer(), 8);
+        let mut ticker = Ticker::every(ENTER_FRAME_MS);
+        while wipe.step(pcd) {
+            pcd.draw();
+            ticker.tick().await;
+        }
+        pcd.draw();
+    }
+
+    pub fn draw<D: Display>(&self, pcd: &mut D) {
+        self.render(pcd);
+        pcd.draw();
+    }
+
+    fn render<D: Display>(&self, pcd: &mut D) {
+        pcd.clear_buffer();
 
         // Border
         Rectangle::new(Point::new(0, 0), Size::new(84, 48))
@@ -99,23 +154,21 @@ impl<'a, OptionId: Copy, const OPTION_COUNT: usize> Menu<'a, OptionId, OPTION_CO
 
         // Header
         self.draw_header(pcd, self.header, style);
+        battery::draw_icon(pcd, Point::new((WIDTH as u32 - battery::ICON_WIDTH - 2) as i32, 2));
         Line::new(Point::new(0,11), Point::new(WIDTH as i32, 11))
             .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
             .draw(pcd).unwrap();
 
         // Options
         self.draw_options(pcd, style);
-
-        // Draw to pcd
-        pcd.draw();
     }
 
-    fn draw_header(&self, pcd: &mut PCD8544, text: &str, style: MonoTextStyle<BinaryColor>) {
-        let pos = center_text(text.len() * 6);
+    fn draw_header<D: Display>(&self, pcd: &mut D, text: &str, style: MonoTextStyle<BinaryColor>) {
+        let pos = center_text(text.chars().count() * 6);
         Text::new(text, Point::new(pos as i32, 8), style).draw(pcd).unwrap();
     }
 
-    fn draw_options(&self, pcd: &mut PCD8544, style: MonoTextStyle<BinaryColor>) {
+    fn draw_options<D: Display>(&self, pcd: &mut D, style: MonoTextStyle<BinaryColor>) {
         let mut first = true;
         for i in [self.selected.0, self.selected.peek()] {
             let y = if first {
@@ -126,13 +179,13 @@ impl<'a, OptionId: Copy, const OPTION_COUNT: usize> Menu<'a, OptionId, OPTION_CO
             };
 
             let text = self.options[i].text;
-            let pos = center_text(text.len() * 6);
+            let pos = center_text(text.chars().count() * 6);
             let font_width = style.font.character_size;
-            Text::new(text, Point::new(pos as i32, y as i32), style).draw(pcd).unwrap();
+            Text::new(text, Point::new(pos as i32, y), style).draw(pcd).unwrap();
             if self.selected.0 == i {
                 Rectangle::new(
-                    Point::new((pos - 3) as i32, y as i32 - font_width.height as i32 + 2),
-                    Size::new((text.len() as u32 * font_width.width) + 5, font_width.height+3))
+                    Point::new((pos - 3) as i32, y - font_width.height as i32 + 2),
+                    Size::new((text.chars().count() as u32 * font_width.width) + 5, font_width.height+3))
                     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
                     .draw(pcd).unwrap();
             }
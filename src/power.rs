@@ -0,0 +1,80 @@
+//! Dimming and sleeping once nobody touches the buttons.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::display::{Display, Power};
+use crate::inputs::{self, Input};
+use crate::tasks;
+
+/// Time without a press before the backlight goes down.
+pub const DIM_MS: u32 = 45_000;
+/// Time without a press before the screen goes off and the console sleeps.
+pub const SLEEP_MS: u32 = 90_000;
+
+static ASLEEP: AtomicBool = AtomicBool::new(false);
+
+/// Set during `sleep`, for tasks that can rest meanwhile, like the audio.
+pub fn is_asleep() -> bool {
+    ASLEEP.load(Ordering::Relaxed)
+}
+
+/// Turns the screen off until a button gets pressed, then brings it back as it was.
+///
+/// The waking press is used up, it won't show in `is_pressed`.
+pub async fn sleep<D: Display, I: Input>(pcd: &mut D, inputs: &mut I) {
+    pcd.set_power(Power::Off);
+    ASLEEP.store(true, Ordering::Relaxed);
+    inputs::next_press(inputs, None).await;
+    ASLEEP.store(false, Ordering::Relaxed);
+    tasks::wake();
+    pcd.set_power(Power::On);
+}
+
+/// Keeps the time since the last press, for loops waiting on the player.
+pub struct IdleTimer {
+    since_ms: u64,
+    dimmed: bool,
+}
+
+impl IdleTimer {
+    pub fn new() -> Self {
+        Self {
+            since_ms: tasks::now_ms(),
+            dimmed: false,
+        }
+    }
+
+    /// Time until it dims or sleeps, a wait for a press can time out after it.
+    pub fn remaining_ms(&self) -> u32 {
+        let step_ms = if self.dimmed { SLEEP_MS } else { DIM_MS };
+        (self.since_ms + step_ms as u64).saturating_sub(tasks::now_ms()) as u32
+    }
+
+    /// To be called after each `Input::update`.
+    pub async fn update<D: Display, I: Input>(&mut self, pcd: &mut D, inputs: &mut I) {
+        if inputs.any_pressed() {
+            if self.dimmed {
+                pcd.set_power(Power::On);
+            }
+            self.since_ms = tasks::now_ms();
+            self.dimmed = false;
+            return;
+        }
+
+        let idle_ms = tasks::now_ms() - self.since_ms;
+        if idle_ms >= SLEEP_MS as u64 {
+            sleep(pcd, inputs).await;
+            self.since_ms = tasks::now_ms();
+            self.dimmed = false;
+        } else if idle_ms >= DIM_MS as u64 && !self.dimmed {
+            pcd.set_power(Power::Dimmed);
+            self.dimmed = true;
+        }
+    }
+}
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,230 @@
-use core::num::Wrapping;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
+static ENTROPY: AtomicU32 = AtomicU32::new(0);
+static FIXED_SEED: AtomicU32 = AtomicU32::new(0);
+static USE_FIXED_SEED: AtomicBool = AtomicBool::new(false);
+
+/// Stirs `value` into the global entropy pool.
+pub fn add_entropy(value: u32) {
+    // Only plain loads and stores, thumbv6m has no atomic read-modify-write
+    let pool = ENTROPY.load(Ordering::Relaxed);
+    ENTROPY.store(mix(pool, value), Ordering::Relaxed);
+}
+
+/// Makes every `Rand::from_entropy` start from `seed`, `None` goes back to entropy.
+pub fn set_fixed_seed(seed: Option<u32>) {
+    if let Some(seed) = seed {
+        FIXED_SEED.store(seed, Ordering::Relaxed);
+    }
+    USE_FIXED_SEED.store(seed.is_some(), Ordering::Relaxed);
+}
+
+pub fn fixed_seed() -> Option<u32> {
+    if USE_FIXED_SEED.load(Ordering::Relaxed) {
+        Some(FIXED_SEED.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+fn mix(pool: u32, value: u32) -> u32 {
+    (pool.rotate_left(5) ^ value).wrapping_mul(0x9e37_79b1)
+}
+
+/// PCG32 (XSH-RR), small state and good statistical quality.
 pub struct Rand {
-    next: Wrapping<u32>,
+    state: u64,
 }
 
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT:  u64 = 1442695040888963407;
+
 impl Rand {
     pub fn new(seed: u32) -> Self{
-        Self {
-            next: Wrapping(seed),
+        let mut rand = Self {
+            state: 0,
+        };
+        rand.next();
+        rand.state = rand.state.wrapping_add(seed as u64);
+        rand.next();
+        rand
+    }
+
+    /// Seeds from the entropy pool, or the fixed seed if one is set.
+    pub fn from_entropy() -> Self {
+        if let Some(seed) = fixed_seed() {
+            return Self::new(seed);
         }
+
+        let seed = ENTROPY.load(Ordering::Relaxed);
+        // Don't hand out the same seed twice
+        add_entropy(seed);
+        Self::new(seed)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> u32 {
-        self.next = self.next * Wrapping(1103515245) + Wrapping(12345);
-        ((self.next/Wrapping(65536)) % Wrapping(32768)).0
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Uniform value in `lo..hi`, without modulo bias.
+    pub fn range(&mut self, lo: u32, hi: u32) -> u32 {
+        assert!(lo < hi);
+        let span = hi - lo;
+        // Reject the values that would make the low results more likely
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let value = self.next();
+            if value >= threshold {
+                return lo + value % span;
+            }
+        }
+    }
+
+    /// True with probability `p`, from 0.0 to 1.0.
+    pub fn chance(&mut self, p: f32) -> bool {
+        // 24 bits is all the precision an f32 has
+        ((self.next() >> 8) as f32) < p * (1 << 24) as f32
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range(0, i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.range(0, items.len() as u32) as usize)
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::RngCore for Rand {
+    fn next_u32(&mut self) -> u32 {
+        self.next()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg32_reference() {
+        // pcg32_srandom_r(42, 0) of the reference implementation, whose
+        // increment for sequence 0 is the same as ours
+        let mut rand = Rand::new(42);
+        let values: [u32; 6] = core::array::from_fn(|_| rand.next());
+        assert_eq!(values, [0xc2f5_7bd6, 0x6b07_c4a9, 0x72b7_b29b, 0x4421_5383, 0xf5af_5ead, 0x68be_b632]);
+    }
+
+    #[test]
+    fn range_bounds() {
+        let mut rand = Rand::new(1);
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            let value = rand.range(10, 17);
+            assert!((10..17).contains(&value));
+            seen[value as usize - 10] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+
+        assert!((0..100).all(|_| rand.range(5, 6) == 5));
+        assert!((0..100).all(|_| rand.range(u32::MAX - 1, u32::MAX) == u32::MAX - 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_range() {
+        Rand::new(1).range(3, 3);
+    }
+
+    #[test]
+    fn range_is_uniform() {
+        const BINS: usize = 10;
+        const SAMPLES: usize = 100_000;
+        let mut rand = Rand::new(7);
+        let mut counts = [0; BINS];
+        for _ in 0..SAMPLES {
+            counts[rand.range(0, BINS as u32) as usize] += 1;
+        }
+
+        let expected = (SAMPLES / BINS) as f32;
+        let chi_square: f32 = counts.iter()
+            .map(|count| (*count as f32 - expected) * (*count as f32 - expected) / expected)
+            .sum();
+        // 99.9th percentile for 9 degrees of freedom
+        assert!(chi_square < 27.88, "{} {:?}", chi_square, counts);
+    }
+
+    #[test]
+    fn chance() {
+        let mut rand = Rand::new(3);
+        assert!((0..10_000).all(|_| !rand.chance(0.0)));
+        assert!((0..10_000).all(|_| rand.chance(1.0)));
+        let heads = (0..10_000).filter(|_| rand.chance(0.5)).count();
+        assert!((4_800..5_200).contains(&heads), "{}", heads);
+    }
+
+    #[test]
+    fn shuffle() {
+        let mut rand = Rand::new(5);
+        let mut items: [u8; 20] = core::array::from_fn(|i| i as u8);
+        let mut moved = false;
+        for _ in 0..10 {
+            rand.shuffle(&mut items);
+            let mut sorted = items;
+            sorted.sort();
+            assert_eq!(sorted, core::array::from_fn(|i| i as u8));
+            moved |= items != sorted;
+        }
+        assert!(moved);
+
+        rand.shuffle::<u8>(&mut []);
+        let mut one = [9];
+        rand.shuffle(&mut one);
+        assert_eq!(one, [9]);
+    }
+
+    #[test]
+    fn choose() {
+        let mut rand = Rand::new(11);
+        assert_eq!(rand.choose::<u8>(&[]), None);
+        assert_eq!(rand.choose(&[4]), Some(&4));
+        let items = [1, 2, 3];
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let item = rand.choose(&items).unwrap();
+            seen[*item - 1] = true;
+        }
+        assert_eq!(seen, [true; 3]);
+    }
+
+    #[test]
+    fn same_seed_same_values() {
+        let (mut a, mut b) = (Rand::new(99), Rand::new(99));
+        assert!((0..100).all(|_| a.next() == b.next()));
+        assert_ne!(Rand::new(1).next(), Rand::new(2).next());
     }
 }
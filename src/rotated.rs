@@ -0,0 +1,46 @@
+//! Portrait view of a screen, for games played with the console turned on its side.
+
+use embedded_graphics::Pixel;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::prelude::{OriginDimensions, Point, Size};
+
+/// Draws onto `target` turned a quarter counterclockwise: the top of the
+/// view is the right end of the screen and its left side the top.
+///
+/// On the 84x48 screen that is a 48x84 view.
+pub struct Rotated<'a, D> {
+    target: &'a mut D,
+}
+
+impl<'a, D: DrawTarget> Rotated<'a, D> {
+    pub fn new(target: &'a mut D) -> Self {
+        Self { target }
+    }
+}
+
+impl<D: DrawTarget> OriginDimensions for Rotated<'_, D> {
+    fn size(&self) -> Size {
+        let size = self.target.bounding_box().size;
+        Size::new(size.height, size.width)
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Rotated<'_, D> {
+    type Color = D::Color;
+
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        let width = self.target.bounding_box().size.width as i32;
+        // Outside of the view would wrap around onto the screen
+        let size = self.size();
+        let pixels = pixels.into_iter()
+            .filter(|Pixel(point, _)| {
+                (0..size.width as i32).contains(&point.x) && (0..size.height as i32).contains(&point.y)
+            })
+            .map(|Pixel(point, color)| Pixel(Point::new(width - 1 - point.y, point.x), color));
+        self.target.draw_iter(pixels)
+    }
+}
@@ -0,0 +1,242 @@
+//! Screenshots of a `FrameBuffer` as binary PBM images.
+//!
+//! On the USB serial port a screenshot is sent as one frame:
+//!
+//! ```text
+//! PBM <length>\n
+//! <length> bytes of a P4 image
+//! ```
+//!
+//! The image is the header `P4\n84 48\n` followed by 48 rows of 11 bytes,
+//! top row first, with the leftmost pixel of each byte in its highest bit and
+//! 1 for a dark pixel. Anything else on the line is text, `Receiver` skips it.
+//!
+//! The firmware sends a screenshot on the `screenshot` command of the debug
+//! console (see `shell`), or when the first and the last button are held down together.
+
+use core::fmt::Write;
+
+use heapless::{String, Vec};
+
+use crate::display::{pixel_index, FrameBuffer, BUFFER_SIZE, HEIGHT, WIDTH};
+
+pub const PBM_HEADER: &[u8] = b"P4\n84 48\n";
+const ROW_BYTES: usize = WIDTH.div_ceil(8);
+pub const PBM_SIZE: usize = PBM_HEADER.len() + ROW_BYTES * HEIGHT;
+/// Starts the line announcing a screenshot.
+pub const FRAME_TAG: &[u8] = b"PBM ";
+/// Longest text line `Receiver` looks at, longer ones can't be a frame header.
+const MAX_LINE: usize = 16;
+
+pub fn encode(buffer: &FrameBuffer) -> [u8; PBM_SIZE] {
+    let mut pbm = [0; PBM_SIZE];
+    pbm[..PBM_HEADER.len()].copy_from_slice(PBM_HEADER);
+    let rows = &mut pbm[PBM_HEADER.len()..];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (index, mask) = pixel_index(x, y);
+            if buffer[index] & mask != 0 {
+                rows[y * ROW_BYTES + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+    pbm
+}
+
+/// Reverse of `encode`, `None` if `pbm` isn't an image of the screen.
+pub fn decode(pbm: &[u8]) -> Option<FrameBuffer> {
+    if pbm.len() != PBM_SIZE || !pbm.starts_with(PBM_HEADER) {
+        return None;
+    }
+
+    let rows = &pbm[PBM_HEADER.len()..];
+    let mut buffer = [0; BUFFER_SIZE];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if rows[y * ROW_BYTES + x / 8] & (0x80 >> (x % 8)) != 0 {
+                let (index, mask) = pixel_index(x, y);
+                buffer[index] |= mask;
+            }
+        }
+    }
+    Some(buffer)
+}
+
+/// The `PBM <length>\n` line sent before the image.
+pub fn frame_header() -> String<MAX_LINE> {
+    let mut header = String::new();
+    writeln!(header, "PBM {}", PBM_SIZE).unwrap();
+    header
+}
+
+/// Picks screenshots out of the bytes coming from the serial port.
+#[derive(Default)]
+pub struct Receiver {
+    line:  Vec<u8, MAX_LINE>,
+    image: Vec<u8, PBM_SIZE>,
+    receiving: bool,
+    /// Rest of a line too long to be a header.
+    skipping: bool,
+}
+
+impl Receiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next byte, returns the screen once a whole image came in.
+    pub fn push(&mut self, byte: u8) -> Option<FrameBuffer> {
+        if self.receiving {
+            self.image.push(byte).unwrap();
+            if self.image.is_full() {
+                self.receiving = false;
+                let frame = decode(&self.image);
+                self.image.clear();
+                return frame;
+            }
+            return None;
+        }
+
+        if byte == b'\n' {
+            self.receiving = !self.skipping && self.line.strip_prefix(FRAME_TAG)
+                .and_then(|len| core::str::from_utf8(len).ok())
+                .and_then(|len| len.trim_end_matches('\r').parse::<usize>().ok())
+                == Some(PBM_SIZE);
+            self.line.clear();
+            self.skipping = false;
+        } else if self.line.push(byte).is_err() {
+            self.line.clear();
+            self.skipping = true;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    /// Every few pixels set, so that a swapped bit or row shows.
+    fn pattern() -> FrameBuffer {
+        let mut buffer = [0; BUFFER_SIZE];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                if (x * 7 + y * 3) % 5 == 0 || x == y {
+                    let (index, mask) = pixel_index(x, y);
+                    buffer[index] |= mask;
+                }
+            }
+        }
+        buffer
+    }
+
+    /// A frame as the firmware sends it.
+    fn frame(buffer: &FrameBuffer) -> Vec<u8> {
+        let mut bytes = Vec::from(frame_header().as_bytes());
+        bytes.extend_from_slice(&encode(buffer));
+        bytes
+    }
+
+    /// Frames `receiver` picks out of `bytes`, fed `chunk` at a time.
+    fn receive(receiver: &mut Receiver, bytes: &[u8], chunk: usize) -> Vec<FrameBuffer> {
+        let mut frames = Vec::new();
+        for chunk in bytes.chunks(chunk) {
+            frames.extend(chunk.iter().filter_map(|byte| receiver.push(*byte)));
+        }
+        frames
+    }
+
+    #[test]
+    fn encodes() {
+        let mut buffer = [0; BUFFER_SIZE];
+        for (x, y) in [(0, 0), (7, 0), (8, 0), (83, 0), (0, 47), (83, 47)] {
+            let (index, mask) = pixel_index(x, y);
+            buffer[index] |= mask;
+        }
+        let pbm = encode(&buffer);
+        assert_eq!(pbm.len(), PBM_SIZE);
+        assert!(pbm.starts_with(PBM_HEADER));
+        let rows = &pbm[PBM_HEADER.len()..];
+        assert_eq!(rows[..ROW_BYTES], [0x81, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0x10]);
+        assert_eq!(rows[(HEIGHT - 1) * ROW_BYTES..], [0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10]);
+        assert_eq!(rows.iter().map(|byte| byte.count_ones()).sum::<u32>(), 6);
+    }
+
+    #[test]
+    fn round_trip() {
+        let buffer = pattern();
+        assert_eq!(decode(&encode(&buffer)), Some(buffer));
+        assert_eq!(decode(&encode(&[0xff; BUFFER_SIZE])), Some([0xff; BUFFER_SIZE]));
+    }
+
+    #[test]
+    fn rejects_bad_images() {
+        let pbm = encode(&pattern());
+        assert_eq!(decode(&pbm[..PBM_SIZE - 1]), None);
+        assert_eq!(decode(&[]), None);
+        let mut longer = Vec::from(&pbm[..]);
+        longer.push(0);
+        assert_eq!(decode(&longer), None);
+
+        let mut wrong = pbm;
+        wrong[1] = b'1';
+        assert_eq!(decode(&wrong), None);
+        let mut wrong = pbm;
+        wrong[3..8].copy_from_slice(b"48 84");
+        assert_eq!(decode(&wrong), None);
+    }
+
+    #[test]
+    fn receives_in_chunks() {
+        let buffer = pattern();
+        let mut bytes = Vec::from(&b"stats\r\nuptime 12 s\r\n"[..]);
+        bytes.extend(frame(&buffer));
+        bytes.extend_from_slice(b"ok\r\n");
+        bytes.extend(frame(&[0; BUFFER_SIZE]));
+
+        for chunk in [1, 3, 7, 13, 64, bytes.len()] {
+            let mut receiver = Receiver::new();
+            assert_eq!(receive(&mut receiver, &bytes, chunk), [buffer, [0; BUFFER_SIZE]], "chunks of {}", chunk);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let pbm = encode(&pattern());
+        let long_line = [b'x'; MAX_LINE * 2];
+        for header in [&b"PBM 100\n"[..], b"PBM\n", b"PBM -1\n", b"pbm 537\n", b"PNG 537\n", &long_line] {
+            let mut receiver = Receiver::new();
+            let mut bytes = Vec::from(header);
+            bytes.extend_from_slice(&pbm);
+            assert!(receive(&mut receiver, &bytes, 5).is_empty(), "{:?}", core::str::from_utf8(header));
+        }
+
+        // A header at the end of a line too long to be one
+        let mut receiver = Receiver::new();
+        let mut bytes = Vec::from(&long_line[..]);
+        bytes.extend(frame(&pattern()));
+        assert!(receive(&mut receiver, &bytes, 5).is_empty());
+
+        // The header may end in CR-LF
+        let mut receiver = Receiver::new();
+        let mut bytes = Vec::from(&b"PBM 537\r\n"[..]);
+        bytes.extend_from_slice(&pbm);
+        assert_eq!(receive(&mut receiver, &bytes, 5), [pattern()]);
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let mut receiver = Receiver::new();
+        let bytes = frame(&pattern());
+        assert!(receive(&mut receiver, &bytes[..bytes.len() - 1], 11).is_empty());
+
+        // A full image with a header that isn't the screen's
+        let mut receiver = Receiver::new();
+        let mut bytes = frame(&pattern());
+        let header_len = frame_header().len();
+        bytes[header_len + 1] = b'5';
+        assert!(receive(&mut receiver, &bytes, 11).is_empty());
+    }
+}
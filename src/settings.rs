@@ -0,0 +1,82 @@
+//! Settings kept across power cycles, stored by the firmware as a small record.
+
+use crate::{audio, backlight, bootsel, lang, rand};
+use crate::lang::Language;
+
+pub const RECORD_SIZE: usize = 16;
+const MAGIC: [u8; 4] = *b"RPGS";
+/// Bump when the layout of the record changes, old records get ignored.
+const VERSION: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub volume: u8,
+    pub brightness: u8,
+    pub fixed_seed: Option<u32>,
+    pub mass_storage: bool,
+    pub picoboot: bool,
+    pub language: Language,
+}
+
+impl Settings {
+    /// The settings as they are right now.
+    pub fn current() -> Self {
+        Self {
+            volume: audio::volume(),
+            brightness: backlight::level(),
+            fixed_seed: rand::fixed_seed(),
+            mass_storage: bootsel::mass_storage(),
+            picoboot: bootsel::picoboot(),
+            language: lang::language(),
+        }
+    }
+
+    pub fn apply(&self) {
+        audio::set_volume(self.volume);
+        backlight::set_level(self.brightness);
+        rand::set_fixed_seed(self.fixed_seed);
+        lang::set_language(self.language);
+        // In this order so the guard against turning off both can't kick in
+        bootsel::set_mass_storage(true);
+        bootsel::set_picoboot(self.picoboot);
+        bootsel::set_mass_storage(self.mass_storage);
+    }
+
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut record = [0xff; RECORD_SIZE];
+        record[..4].copy_from_slice(&MAGIC);
+        record[4] = VERSION;
+        record[5] = self.volume;
+        record[6] = self.fixed_seed.is_some() as u8;
+        record[7..11].copy_from_slice(&self.fixed_seed.unwrap_or(0).to_le_bytes());
+        record[11] = self.mass_storage as u8 | (self.picoboot as u8) << 1;
+        record[12] = self.brightness;
+        record[13] = self.language.to_u8();
+        record[RECORD_SIZE - 1] = checksum(&record[..RECORD_SIZE - 1]);
+        record
+    }
+
+    /// `None` for an erased or damaged record, or one from another version.
+    pub fn from_bytes(record: &[u8]) -> Option<Self> {
+        if record.len() < RECORD_SIZE
+            || record[..4] != MAGIC
+            || record[4] != VERSION
+            || record[RECORD_SIZE - 1] != checksum(&record[..RECORD_SIZE - 1]) {
+            return None;
+        }
+
+        let seed = u32::from_le_bytes(record[7..11].try_into().unwrap());
+        Some(Self {
+            volume: record[5].min(audio::MAX_VOLUME),
+            brightness: record[12].min(backlight::MAX_LEVEL),
+            fixed_seed: (record[6] != 0).then_some(seed),
+            mass_storage: record[11] & 1 != 0,
+            picoboot: record[11] & 2 != 0,
+            language: Language::from_u8(record[13]).unwrap_or(Language::Polish),
+        })
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum: u8, byte| sum.rotate_left(1) ^ byte)
+}
@@ -1,10 +1,11 @@
-use cortex_m::delay::Delay;
+use embedded_hal::blocking::delay::DelayMs;
 
-use crate::pcd8544::PCD8544;
+use crate::display::Display;
 
-pub fn inverse_blink(
-    pcd:    &mut PCD8544,
-    delay:  &mut Delay,
+/// Blinks the screen inverted `times` times, blocking the caller until it is over.
+pub fn inverse_blink<D: Display, T: DelayMs<u32>>(
+    pcd:    &mut D,
+    delay:  &mut T,
     delay_time:  u32,
     times:  u32)
 {
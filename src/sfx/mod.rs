@@ -1 +1,4 @@
 pub mod inverse_blink;
+pub mod particles;
+pub mod transition;
+pub mod tween;
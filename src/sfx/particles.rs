@@ -0,0 +1,83 @@
+use embedded_graphics::Pixel;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::Point;
+use heapless::Vec;
+
+use crate::display::{Display, HEIGHT, WIDTH};
+use crate::rand::Rand;
+
+/// Positions and speeds are in 1/`SUBPIXELS` of a pixel.
+const SUBPIXELS: i32 = 16;
+const GRAVITY: i32 = 2;
+
+#[derive(Clone, Copy)]
+struct Particle {
+    x: i32,
+    y: i32,
+    vx: i32,
+    vy: i32,
+    life: u8,
+}
+
+/// Short lived pixels flying out of a point, stepped once per frame.
+pub struct Particles<const N: usize> {
+    particles: Vec<Particle, N>,
+}
+
+impl<const N: usize> Default for Particles<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Particles<N> {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+        }
+    }
+
+    /// Throws `count` particles out of `center` in random directions.
+    pub fn burst(&mut self, center: Point, count: usize, rand: &mut Rand) {
+        for _ in 0..count {
+            let particle = Particle {
+                x: center.x * SUBPIXELS,
+                y: center.y * SUBPIXELS,
+                vx: rand.range(0, 2*SUBPIXELS as u32 + 1) as i32 - SUBPIXELS,
+                vy: rand.range(0, 2*SUBPIXELS as u32 + 1) as i32 - SUBPIXELS - SUBPIXELS/2,
+                life: rand.range(6, 14) as u8,
+            };
+            // A full system just skips the extra particles
+            if self.particles.push(particle).is_err() {
+                break;
+            }
+        }
+    }
+
+    pub fn step(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.x += particle.vx;
+            particle.y += particle.vy;
+            particle.vy += GRAVITY;
+            particle.life -= 1;
+        }
+
+        let mut i = 0;
+        while i < self.particles.len() {
+            let particle = &self.particles[i];
+            if particle.life > 0 &&
+               (0..WIDTH as i32 * SUBPIXELS).contains(&particle.x) &&
+               (0..HEIGHT as i32 * SUBPIXELS).contains(&particle.y) {
+                i += 1;
+            } else {
+                self.particles.swap_remove(i);
+            }
+        }
+    }
+
+    pub fn draw<D: Display>(&self, pcd: &mut D) {
+        pcd.draw_iter(self.particles.iter().map(|particle| {
+            Pixel(Point::new(particle.x / SUBPIXELS, particle.y / SUBPIXELS), BinaryColor::On)
+        })).unwrap();
+    }
+}
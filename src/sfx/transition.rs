@@ -0,0 +1,231 @@
+use crate::display::{pixel_index, Display, FrameBuffer, BUFFER_SIZE, HEIGHT, WIDTH};
+use crate::rand::Rand;
+
+use super::tween::{Ease, Tween, ONE};
+
+/// Largest offset of the screen shake, in pixels.
+const SHAKE_AMPLITUDE: i32 = 3;
+
+#[derive(Clone, Copy)]
+pub enum TransitionKind {
+    /// New screen uncovered column by column, left to right.
+    Wipe,
+    /// Pixels switch over in a scattered order.
+    Dissolve,
+    /// New screen pushes the old one out to the left.
+    Slide,
+    /// New screen shown shaking, settling down over time.
+    Shake,
+}
+
+/// Effect between two screens, advanced one frame at a time with `step`.
+pub struct Transition {
+    kind: TransitionKind,
+    from: FrameBuffer,
+    to:   FrameBuffer,
+    tween: Tween,
+    rand: Rand,
+}
+
+fn copy_column(dst: &mut FrameBuffer, dst_x: usize, src: &FrameBuffer, src_x: usize) {
+    for bank in 0..HEIGHT/8 {
+        dst[bank*WIDTH + (WIDTH - 1 - dst_x)] = src[bank*WIDTH + (WIDTH - 1 - src_x)];
+    }
+}
+
+fn get_pixel(buffer: &FrameBuffer, x: usize, y: usize) -> bool {
+    let (index, mask) = pixel_index(x, y);
+    buffer[index] & mask != 0
+}
+
+fn set_pixel(buffer: &mut FrameBuffer, x: usize, y: usize, on: bool) {
+    let (index, mask) = pixel_index(x, y);
+    if on {
+        buffer[index] |= mask;
+    } else {
+        buffer[index] &= !mask;
+    }
+}
+
+/// Fixed order in which the dissolve switches pixels, in `0..ONE`.
+fn dissolve_order(x: usize, y: usize) -> i32 {
+    let mut hash = (x as u32).wrapping_mul(0x9e37_79b1) ^ (y as u32).wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    (hash % ONE as u32) as i32
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, from: &FrameBuffer, to: &FrameBuffer, frames: u16) -> Self {
+        let ease = match kind {
+            TransitionKind::Wipe  => Ease::In,
+            TransitionKind::Slide => Ease::InOut,
+            TransitionKind::Shake => Ease::Out,
+            TransitionKind::Dissolve => Ease::Linear,
+        };
+
+        Self {
+            kind,
+            from: *from,
+            to: *to,
+            tween: Tween::new(0, ONE, frames, ease),
+            rand: Rand::new(frames as u32),
+        }
+    }
+
+    /// Renders the next frame into `pcd` without sending it,
+    /// returns `false` once the transition is over.
+    pub fn step<D: Display>(&mut self, pcd: &mut D) -> bool {
+        let progress = self.tween.step();
+        let buffer = pcd.buffer_mut();
+
+        match self.kind {
+            TransitionKind::Wipe => {
+                let edge = (progress * WIDTH as i32 / ONE) as usize;
+                for x in 0..WIDTH {
+                    let src = if x < edge { &self.to } else { &self.from };
+                    copy_column(buffer, x, src, x);
+                }
+            },
+            TransitionKind::Dissolve => {
+                for y in 0..HEIGHT {
+                    for x in 0..WIDTH {
+                        let src = if dissolve_order(x, y) < progress { &self.to } else { &self.from };
+                        set_pixel(buffer, x, y, get_pixel(src, x, y));
+                    }
+                }
+            },
+            TransitionKind::Slide => {
+                let shift = (progress * WIDTH as i32 / ONE) as usize;
+                for x in 0..WIDTH {
+                    if x + shift < WIDTH {
+                        copy_column(buffer, x, &self.from, x + shift);
+                    } else {
+                        copy_column(buffer, x, &self.to, x + shift - WIDTH);
+                    }
+                }
+            },
+            TransitionKind::Shake => {
+                let amplitude = SHAKE_AMPLITUDE - progress * SHAKE_AMPLITUDE / ONE;
+                let (dx, dy) = if amplitude > 0 {
+                    let span = 2 * amplitude as u32 + 1;
+                    (self.rand.range(0, span) as i32 - amplitude,
+                     self.rand.range(0, span) as i32 - amplitude)
+                } else {
+                    (0, 0)
+                };
+
+                *buffer = [0; BUFFER_SIZE];
+                for y in 0..HEIGHT {
+                    for x in 0..WIDTH {
+                        let (src_x, src_y) = (x as i32 - dx, y as i32 - dy);
+                        if (0..WIDTH as i32).contains(&src_x) && (0..HEIGHT as i32).contains(&src_y)
+                            && get_pixel(&self.to, src_x as usize, src_y as usize) {
+                            set_pixel(buffer, x, y, true);
+                        }
+                    }
+                }
+            },
+        }
+
+        !self.tween.done()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::display::TestDisplay;
+
+    const FRAMES: u16 = 16;
+
+    fn pattern(on: impl Fn(usize, usize) -> bool) -> FrameBuffer {
+        let mut buffer = [0; BUFFER_SIZE];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                set_pixel(&mut buffer, x, y, on(x, y));
+            }
+        }
+        buffer
+    }
+
+    fn screens() -> (FrameBuffer, FrameBuffer) {
+        (pattern(|x, y| (x + y) % 3 == 0), pattern(|x, y| x % 2 == 0 && y % 5 != 0))
+    }
+
+    fn column_is(buffer: &FrameBuffer, x: usize, src: &FrameBuffer, src_x: usize) -> bool {
+        (0..HEIGHT).all(|y| get_pixel(buffer, x, y) == get_pixel(src, src_x, y))
+    }
+
+    /// Steps through the whole transition, checking each frame with `check`.
+    fn run(kind: TransitionKind, check: impl Fn(u16, &FrameBuffer)) {
+        let (from, to) = screens();
+        let mut transition = Transition::new(kind, &from, &to, FRAMES);
+        let mut display = TestDisplay::default();
+        for frame in 1..=FRAMES {
+            assert_eq!(transition.step(&mut display), frame < FRAMES);
+            check(frame, &display.buffer);
+        }
+        // Only rendered, never sent
+        assert_eq!(display.frames, 0);
+    }
+
+    #[test]
+    fn wipe() {
+        let (from, to) = screens();
+        let edge = core::cell::Cell::new(0);
+        run(TransitionKind::Wipe, |frame, buffer| {
+            // The first step is still the old screen, the last one all new
+            if frame == 1 {
+                assert_eq!(*buffer, from);
+            }
+            if frame == FRAMES {
+                assert_eq!(*buffer, to);
+            }
+
+            // New columns on the left, old ones on the right, the edge only moving right
+            let new = (0..WIDTH).take_while(|x| column_is(buffer, *x, &to, *x)).count();
+            assert!((new..WIDTH).all(|x| column_is(buffer, x, &from, x)));
+            assert!(new >= edge.get());
+            edge.set(new);
+        });
+    }
+
+    #[test]
+    fn slide() {
+        let (from, to) = screens();
+        run(TransitionKind::Slide, |frame, buffer| {
+            if frame == 1 {
+                assert_eq!(*buffer, from);
+            }
+            if frame == FRAMES {
+                assert_eq!(*buffer, to);
+            }
+            if frame == FRAMES / 2 {
+                // Both screens moved left by the same amount
+                let shift = (0..WIDTH).find(|shift| {
+                    (0..WIDTH).all(|x| if x + shift < WIDTH {
+                        column_is(buffer, x, &from, x + shift)
+                    } else {
+                        column_is(buffer, x, &to, x + shift - WIDTH)
+                    })
+                });
+                assert!(shift.is_some_and(|shift| shift > 0));
+            }
+        });
+    }
+
+    #[test]
+    fn dissolve_and_shake_end_on_the_new_screen() {
+        let (_, to) = screens();
+        for kind in [TransitionKind::Dissolve, TransitionKind::Shake] {
+            run(kind, |frame, buffer| {
+                if frame == FRAMES {
+                    assert_eq!(*buffer, to);
+                }
+            });
+        }
+    }
+}
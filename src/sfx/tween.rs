@@ -0,0 +1,130 @@
+/// Progress of a tween, from 0 to `ONE`.
+pub const ONE: i32 = 256;
+
+#[derive(Clone, Copy)]
+pub enum Ease {
+    Linear,
+    In,
+    Out,
+    InOut,
+}
+
+impl Ease {
+    /// Maps linear progress `t` onto the easing curve, both in `0..=ONE`.
+    pub fn apply(self, t: i32) -> i32 {
+        let t = t.clamp(0, ONE);
+        match self {
+            Ease::Linear => t,
+            Ease::In => t * t / ONE,
+            Ease::Out => ONE - (ONE - t) * (ONE - t) / ONE,
+            Ease::InOut => {
+                if t < ONE/2 {
+                    2 * t * t / ONE
+                } else {
+                    ONE - 2 * (ONE - t) * (ONE - t) / ONE
+                }
+            }
+        }
+    }
+}
+
+pub fn lerp(from: i32, to: i32, t: i32) -> i32 {
+    from + (to - from) * t / ONE
+}
+
+/// Value moving from `from` to `to` over a number of frames.
+pub struct Tween {
+    from: i32,
+    to: i32,
+    frame: u16,
+    frames: u16,
+    ease: Ease,
+}
+
+impl Tween {
+    pub fn new(from: i32, to: i32, frames: u16, ease: Ease) -> Self {
+        Self {
+            from,
+            to,
+            frame: 0,
+            frames: frames.max(1),
+            ease,
+        }
+    }
+
+    /// Progress in `0..=ONE`, after easing.
+    pub fn progress(&self) -> i32 {
+        self.ease.apply(self.frame as i32 * ONE / self.frames as i32)
+    }
+
+    pub fn value(&self) -> i32 {
+        lerp(self.from, self.to, self.progress())
+    }
+
+    /// Advances one frame and returns the new value.
+    pub fn step(&mut self) -> i32 {
+        if self.frame < self.frames {
+            self.frame += 1;
+        }
+        self.value()
+    }
+
+    pub fn done(&self) -> bool {
+        self.frame == self.frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    const EASES: [Ease; 4] = [Ease::Linear, Ease::In, Ease::Out, Ease::InOut];
+
+    #[test]
+    fn ease_endpoints() {
+        for ease in EASES {
+            assert_eq!(ease.apply(0), 0);
+            assert_eq!(ease.apply(ONE), ONE);
+            // Out of range progress is clamped
+            assert_eq!(ease.apply(-10), 0);
+            assert_eq!(ease.apply(2 * ONE), ONE);
+            // And the curves never go back
+            for t in 0..ONE {
+                assert!(ease.apply(t) <= ease.apply(t + 1));
+            }
+        }
+        assert!(Ease::In.apply(ONE / 2) < ONE / 2);
+        assert!(Ease::Out.apply(ONE / 2) > ONE / 2);
+        assert_eq!(Ease::InOut.apply(ONE / 2), ONE / 2);
+    }
+
+    #[test]
+    fn lerp_endpoints() {
+        assert_eq!(lerp(10, -20, 0), 10);
+        assert_eq!(lerp(10, -20, ONE / 2), -5);
+        assert_eq!(lerp(10, -20, ONE), -20);
+    }
+
+    #[test]
+    fn steps() {
+        let mut tween = Tween::new(0, 100, 4, Ease::Linear);
+        assert_eq!(tween.value(), 0);
+        assert!(!tween.done());
+        let values: Vec<i32> = (0..4).map(|_| tween.step()).collect();
+        assert_eq!(values, [25, 50, 75, 100]);
+        assert!(tween.done());
+
+        // Stays at the end
+        assert_eq!(tween.step(), 100);
+        assert!(tween.done());
+    }
+
+    #[test]
+    fn no_frames() {
+        let mut tween = Tween::new(5, -5, 0, Ease::Out);
+        assert_eq!(tween.step(), -5);
+        assert!(tween.done());
+    }
+}
@@ -0,0 +1,269 @@
+//! Commands of the debug console on the USB serial port.
+//!
+//! Only the parsing lives here, running a command is up to the firmware.
+//! Lines end with `\r` or `\n`, words are separated by spaces.
+
+use heapless::String;
+
+use crate::audio::MAX_VOLUME;
+
+/// Longest line accepted.
+pub const MAX_LINE: usize = 64;
+/// Highest operating voltage setting of the PCD8544.
+pub const MAX_CONTRAST: u8 = 0x7f;
+
+pub const HELP: &str = "\
+help                   this list\r
+stats                  uptime, frames, volume, battery, seed\r
+set contrast <0-127>   screen contrast\r
+set volume <0-3>       buzzer volume\r
+backlight on|off\r
+seed <number>|off      fixed seed for the games\r
+press <button>         select, down, up, right or 0-3\r
+screenshot             PBM image of the screen\r
+reboot [bootloader]    restart, or go to the USB bootloader\r
+";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Stats,
+    SetContrast(u8),
+    SetVolume(u8),
+    Backlight(bool),
+    Seed(Option<u32>),
+    Press(usize),
+    Screenshot,
+    Reboot { bootloader: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseError {
+    UnknownCommand,
+    MissingArgument,
+    BadArgument,
+    TooManyArguments,
+    TooLong,
+}
+
+impl ParseError {
+    pub fn message(self) -> &'static str {
+        match self {
+            ParseError::UnknownCommand   => "unknown command, try help",
+            ParseError::MissingArgument  => "missing argument",
+            ParseError::BadArgument      => "bad argument",
+            ParseError::TooManyArguments => "too many arguments",
+            ParseError::TooLong          => "line too long",
+        }
+    }
+}
+
+/// Index of the button called `name`, the same one `Input::is_pressed` uses.
+pub fn button(name: &str) -> Option<usize> {
+    match name {
+        "select" | "left" | "0" => Some(0),
+        "down" | "1" => Some(1),
+        "up" | "2" => Some(2),
+        "right" | "3" => Some(3),
+        _ => None,
+    }
+}
+
+fn number<T: core::str::FromStr + PartialOrd>(word: &str, max: T) -> Result<T, ParseError> {
+    match word.parse() {
+        Ok(value) if value <= max => Ok(value),
+        _ => Err(ParseError::BadArgument),
+    }
+}
+
+/// Parses a line without its line ending, `None` for a blank line.
+pub fn parse(line: &str) -> Option<Result<Command, ParseError>> {
+    let mut words = line.split_ascii_whitespace();
+    let command = words.next()?;
+    Some(parse_words(command, words))
+}
+
+fn parse_words<'a>(command: &str, mut words: impl Iterator<Item = &'a str>) -> Result<Command, ParseError> {
+    let mut argument = || words.next().ok_or(ParseError::MissingArgument);
+
+    let command = match command {
+        "help" => Command::Help,
+        "stats" => Command::Stats,
+        "set" => match argument()? {
+            "contrast" => Command::SetContrast(number(argument()?, MAX_CONTRAST)?),
+            "volume" => Command::SetVolume(number(argument()?, MAX_VOLUME)?),
+            _ => return Err(ParseError::BadArgument),
+        },
+        "backlight" => match argument()? {
+            "on" => Command::Backlight(true),
+            "off" => Command::Backlight(false),
+            _ => return Err(ParseError::BadArgument),
+        },
+        "seed" => match argument()? {
+            "off" => Command::Seed(None),
+            seed => Command::Seed(Some(number(seed, u32::MAX)?)),
+        },
+        "press" => Command::Press(button(argument()?).ok_or(ParseError::BadArgument)?),
+        "screenshot" => Command::Screenshot,
+        "reboot" => match argument() {
+            Err(_) => Command::Reboot { bootloader: false },
+            Ok("bootloader") => Command::Reboot { bootloader: true },
+            Ok(_) => return Err(ParseError::BadArgument),
+        },
+        _ => return Err(ParseError::UnknownCommand),
+    };
+
+    match argument() {
+        Ok(_) => Err(ParseError::TooManyArguments),
+        Err(_) => Ok(command),
+    }
+}
+
+/// Collects typed characters into lines and parses them.
+#[derive(Default)]
+pub struct LineReader {
+    line: String<MAX_LINE>,
+    too_long: bool,
+}
+
+impl LineReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next byte, returns the command once its line is complete.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Command, ParseError>> {
+        match byte {
+            b'\r' | b'\n' => {
+                let parsed = if self.too_long {
+                    Some(Err(ParseError::TooLong))
+                } else {
+                    parse(&self.line)
+                };
+                self.line.clear();
+                self.too_long = false;
+                parsed
+            },
+            // Backspace and delete
+            0x08 | 0x7f => {
+                self.line.pop();
+                None
+            },
+            b' '..=b'~' => {
+                if self.line.push(byte as char).is_err() {
+                    self.too_long = true;
+                }
+                None
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    /// Everything `reader` made of `input`.
+    fn read(reader: &mut LineReader, input: &[u8]) -> Vec<Result<Command, ParseError>> {
+        input.iter().filter_map(|byte| reader.push(*byte)).collect()
+    }
+
+    #[test]
+    fn blank_lines() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("   "), None);
+    }
+
+    #[test]
+    fn unknown_command() {
+        assert_eq!(parse("hello"), Some(Err(ParseError::UnknownCommand)));
+        assert_eq!(parse("HELP"), Some(Err(ParseError::UnknownCommand)));
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        assert_eq!(parse("help me"), Some(Err(ParseError::TooManyArguments)));
+        assert_eq!(parse("set volume 1 2"), Some(Err(ParseError::TooManyArguments)));
+        assert_eq!(parse("reboot bootloader now"), Some(Err(ParseError::TooManyArguments)));
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse("help"), Some(Ok(Command::Help)));
+        assert_eq!(parse("  stats "), Some(Ok(Command::Stats)));
+        assert_eq!(parse("screenshot"), Some(Ok(Command::Screenshot)));
+
+        assert_eq!(parse("set contrast 0"), Some(Ok(Command::SetContrast(0))));
+        assert_eq!(parse("set contrast 127"), Some(Ok(Command::SetContrast(MAX_CONTRAST))));
+        assert_eq!(parse("set contrast 128"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("set contrast -1"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("set contrast"), Some(Err(ParseError::MissingArgument)));
+        assert_eq!(parse("set volume 3"), Some(Ok(Command::SetVolume(MAX_VOLUME))));
+        assert_eq!(parse("set volume 4"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("set volume loud"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("set brightness 1"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("set"), Some(Err(ParseError::MissingArgument)));
+
+        assert_eq!(parse("backlight on"), Some(Ok(Command::Backlight(true))));
+        assert_eq!(parse("backlight off"), Some(Ok(Command::Backlight(false))));
+        assert_eq!(parse("backlight dim"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("backlight"), Some(Err(ParseError::MissingArgument)));
+
+        assert_eq!(parse("seed 1234"), Some(Ok(Command::Seed(Some(1234)))));
+        assert_eq!(parse("seed 4294967295"), Some(Ok(Command::Seed(Some(u32::MAX)))));
+        assert_eq!(parse("seed 4294967296"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("seed off"), Some(Ok(Command::Seed(None))));
+        assert_eq!(parse("seed"), Some(Err(ParseError::MissingArgument)));
+
+        assert_eq!(parse("press select"), Some(Ok(Command::Press(0))));
+        assert_eq!(parse("press left"), Some(Ok(Command::Press(0))));
+        assert_eq!(parse("press down"), Some(Ok(Command::Press(1))));
+        assert_eq!(parse("press up"), Some(Ok(Command::Press(2))));
+        assert_eq!(parse("press 3"), Some(Ok(Command::Press(3))));
+        assert_eq!(parse("press 4"), Some(Err(ParseError::BadArgument)));
+        assert_eq!(parse("press"), Some(Err(ParseError::MissingArgument)));
+
+        assert_eq!(parse("reboot"), Some(Ok(Command::Reboot { bootloader: false })));
+        assert_eq!(parse("reboot bootloader"), Some(Ok(Command::Reboot { bootloader: true })));
+        assert_eq!(parse("reboot now"), Some(Err(ParseError::BadArgument)));
+    }
+
+    #[test]
+    fn line_endings() {
+        let mut reader = LineReader::new();
+        // The LF of a CR-LF is just an empty line
+        assert_eq!(read(&mut reader, b"help\r\nstats\r\n"), [Ok(Command::Help), Ok(Command::Stats)]);
+        assert_eq!(read(&mut reader, b"help\nstats\r"), [Ok(Command::Help), Ok(Command::Stats)]);
+        assert_eq!(read(&mut reader, b"\r\n\n"), []);
+    }
+
+    #[test]
+    fn backspace() {
+        let mut reader = LineReader::new();
+        assert_eq!(read(&mut reader, b"helq\x08p\r"), [Ok(Command::Help)]);
+        assert_eq!(read(&mut reader, b"statx\x7fs\r"), [Ok(Command::Stats)]);
+        // Nothing left to take back
+        assert_eq!(read(&mut reader, b"\x08\x08help\r"), [Ok(Command::Help)]);
+        // Other control characters are ignored
+        assert_eq!(read(&mut reader, b"he\x1blp\t\r"), [Ok(Command::Help)]);
+    }
+
+    #[test]
+    fn too_long() {
+        let mut reader = LineReader::new();
+        let mut line = Vec::new();
+        line.extend_from_slice(b"help");
+        line.resize(MAX_LINE, b' ');
+        line.push(b'\r');
+        assert_eq!(read(&mut reader, &line), [Ok(Command::Help)]);
+
+        line.pop();
+        line.extend_from_slice(b"x\r");
+        assert_eq!(read(&mut reader, &line), [Err(ParseError::TooLong)]);
+        // The next line starts afresh
+        assert_eq!(read(&mut reader, b"stats\r"), [Ok(Command::Stats)]);
+    }
+}
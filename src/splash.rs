@@ -0,0 +1,66 @@
+//! Boot splash: the gamepad logo drops in, bounces and gets a shine across it.
+//!
+//! This only draws the frames, the firmware plays them at power-up before
+//! the title screen.
+
+use embedded_graphics::Drawable;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{Point, Pixel};
+
+use crate::assets::LOGO;
+use crate::display::{Display, HEIGHT, WIDTH};
+use crate::sprite::Flip;
+
+pub const FRAME_MS: u32 = 30;
+pub const FRAMES: u32 = 50;
+const DROP_FRAMES: u32 = 14;
+const BOUNCE_FRAMES: u32 = 8;
+/// Rows the logo goes back up at the top of the bounce.
+const BOUNCE_HEIGHT: u32 = 4;
+const SHINE_START: u32 = 26;
+const SHINE_WIDTH: i32 = 4;
+/// Pixels the shine moves each frame.
+const SHINE_SPEED: i32 = 3;
+
+/// Top left corner of the logo once it lands.
+const REST: Point = Point::new(((WIDTH - LOGO.width) / 2) as i32, ((HEIGHT - LOGO.height) / 2) as i32);
+
+/// Top of the logo in `frame`.
+fn logo_y(frame: u32) -> i32 {
+    let top = -(LOGO.height as i32);
+    if frame < DROP_FRAMES {
+        // Falling faster and faster
+        let fall = (REST.y - top) as u32;
+        top + (fall * frame * frame / (DROP_FRAMES * DROP_FRAMES)) as i32
+    } else if frame < DROP_FRAMES + BOUNCE_FRAMES {
+        let t = frame - DROP_FRAMES;
+        let rise = 4 * BOUNCE_HEIGHT * t * (BOUNCE_FRAMES - t) / (BOUNCE_FRAMES * BOUNCE_FRAMES);
+        REST.y - rise as i32
+    } else {
+        REST.y
+    }
+}
+
+/// Draws `frame`, up to `FRAMES`, into the buffer. `Display::draw` sends it.
+pub fn render<D: Display>(pcd: &mut D, frame: u32) {
+    pcd.clear_buffer();
+    let pos = Point::new(REST.x, logo_y(frame));
+    LOGO.draw(pcd, pos, Flip::NONE);
+
+    // A diagonal band sweeping across, inverting the logo under it
+    if frame < SHINE_START {
+        return;
+    }
+    let band = (frame - SHINE_START) as i32 * SHINE_SPEED - LOGO.height as i32;
+    for y in 0..LOGO.height {
+        for x in 0..LOGO.width {
+            let Some(dark) = LOGO.pixel(x, y) else {
+                continue;
+            };
+            if (0..SHINE_WIDTH).contains(&(x as i32 + y as i32 / 2 - band)) {
+                let color = if dark { BinaryColor::Off } else { BinaryColor::On };
+                Pixel(pos + Point::new(x as i32, y as i32), color).draw(pcd).unwrap();
+            }
+        }
+    }
+}
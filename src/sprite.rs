@@ -0,0 +1,280 @@
+//! 1-bit sprites blitted straight into the `FrameBuffer`.
+//!
+//! A sprite is stored like the screen: in pages of 8 rows, one byte per
+//! column and page, with the top row of the page in the highest bit. Its
+//! pages are `width` bytes each, the last one padded with zeros. The
+//! images under `assets/sprites` get converted into this by `build.rs`,
+//! see `assets`.
+//!
+//! Drawing works a column at a time, into the pages of the screen it
+//! touches. Sprites at a `y` that is a multiple of 8 and not flipped
+//! vertically line up with the pages, their bytes go in as they are.
+
+use embedded_graphics::prelude::Point;
+
+use crate::display::{Display, FrameBuffer, HEIGHT, WIDTH};
+
+/// Tallest sprite there is room for in a column.
+pub const MAX_HEIGHT: usize = 64;
+const PAGES: usize = HEIGHT / 8;
+
+/// Mirroring applied while drawing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Flip {
+    pub horizontal: bool,
+    pub vertical: bool,
+}
+
+impl Flip {
+    pub const NONE: Flip = Flip { horizontal: false, vertical: false };
+    pub const HORIZONTAL: Flip = Flip { horizontal: true, vertical: false };
+    pub const VERTICAL: Flip = Flip { horizontal: false, vertical: true };
+    pub const BOTH: Flip = Flip { horizontal: true, vertical: true };
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite<'a> {
+    pub width: usize,
+    pub height: usize,
+    /// Dark pixels.
+    pub image: &'a [u8],
+    /// Pixels drawn at all, the rest leave the screen alone. Without a mask
+    /// only the dark pixels are drawn.
+    pub mask: Option<&'a [u8]>,
+}
+
+impl<'a> Sprite<'a> {
+    pub const fn new(width: usize, height: usize, image: &'a [u8], mask: Option<&'a [u8]>) -> Self {
+        assert!(height <= MAX_HEIGHT);
+        assert!(image.len() == width * height.div_ceil(8));
+        if let Some(mask) = mask {
+            assert!(mask.len() == image.len());
+        }
+        Self {
+            width,
+            height,
+            image,
+            mask,
+        }
+    }
+
+    pub fn pages(&self) -> usize {
+        self.height.div_ceil(8)
+    }
+
+    /// `Some(true)` for a dark pixel, `Some(false)` for a light one and
+    /// `None` where the sprite leaves the screen alone.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<bool> {
+        let index = y / 8 * self.width + x;
+        let bit = 0x80 >> (y % 8);
+        (self.mask_byte(index) & bit != 0).then_some(self.image[index] & bit != 0)
+    }
+
+    fn mask_byte(&self, index: usize) -> u8 {
+        match self.mask {
+            Some(mask) => mask[index],
+            None => self.image[index],
+        }
+    }
+
+    /// Image and mask of column `x`, with row `r` in bit `63 - r`.
+    fn column(&self, x: usize, flip: Flip) -> (u64, u64) {
+        let x = if flip.horizontal { self.width - 1 - x } else { x };
+        let (mut image, mut mask) = (0, 0);
+        for page in 0..self.pages() {
+            let index = page * self.width + x;
+            image |= (self.image[index] as u64) << (56 - 8 * page);
+            mask |= (self.mask_byte(index) as u64) << (56 - 8 * page);
+        }
+
+        if flip.vertical {
+            let shift = MAX_HEIGHT - self.height;
+            image = image.reverse_bits() << shift;
+            mask = mask.reverse_bits() << shift;
+        }
+        (image, mask)
+    }
+
+    /// Draws the sprite into the buffer with its top left corner at `pos`,
+    /// clipped to the screen. `Display::draw` sends it.
+    pub fn draw<D: Display>(&self, pcd: &mut D, pos: Point, flip: Flip) {
+        self.draw_into(pcd.buffer_mut(), pos, flip);
+    }
+
+    pub fn draw_into(&self, buffer: &mut FrameBuffer, pos: Point, flip: Flip) {
+        if pos.y.rem_euclid(8) == 0 && !flip.vertical {
+            self.draw_aligned(buffer, pos, flip.horizontal);
+            return;
+        }
+
+        for x in 0..self.width {
+            let screen_x = pos.x + x as i32;
+            if !(0..WIDTH as i32).contains(&screen_x) {
+                continue;
+            }
+
+            // Into the screen column, with row `y` in bit `63 - y`
+            let (image, mask) = self.column(x, flip);
+            let (image, mask) = match pos.y {
+                y if y >= MAX_HEIGHT as i32 || y <= -(MAX_HEIGHT as i32) => continue,
+                y if y >= 0 => (image >> y, mask >> y),
+                y => (image << -y, mask << -y),
+            };
+
+            for page in 0..PAGES {
+                let shift = 56 - 8 * page;
+                let mask = (mask >> shift) as u8;
+                if mask != 0 {
+                    blend(buffer, screen_x as usize, page, (image >> shift) as u8, mask);
+                }
+            }
+        }
+    }
+
+    /// The pages of the sprite line up with the ones of the screen.
+    fn draw_aligned(&self, buffer: &mut FrameBuffer, pos: Point, flip_horizontal: bool) {
+        let first_page = pos.y.div_euclid(8);
+        for page in 0..self.pages() {
+            let screen_page = first_page + page as i32;
+            if !(0..PAGES as i32).contains(&screen_page) {
+                continue;
+            }
+
+            for x in 0..self.width {
+                let screen_x = pos.x + x as i32;
+                if !(0..WIDTH as i32).contains(&screen_x) {
+                    continue;
+                }
+                let column = if flip_horizontal { self.width - 1 - x } else { x };
+                let index = page * self.width + column;
+                blend(buffer, screen_x as usize, screen_page as usize, self.image[index], self.mask_byte(index));
+            }
+        }
+    }
+}
+
+/// Sets the `mask` bits of page `page` (counted from the top) in column `x` to `image`.
+fn blend(buffer: &mut FrameBuffer, x: usize, page: usize, image: u8, mask: u8) {
+    // The panel is upside down, see `display::pixel_index`, but the top
+    // of each page still lands in the highest bit
+    let byte = &mut buffer[(PAGES - 1 - page) * WIDTH + (WIDTH - 1 - x)];
+    *byte = (*byte & !mask) | (image & mask);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use crate::display::{pixel_index, BUFFER_SIZE};
+
+    /// Scattered pixels, different for each `seed`.
+    fn noise(x: usize, y: usize, seed: u32) -> bool {
+        let hash = (x as u32).wrapping_mul(0x9e37_79b1) ^ (y as u32).wrapping_mul(0x85eb_ca6b) ^ seed;
+        hash.wrapping_mul(0x2c1b_3c6d) >> 31 != 0
+    }
+
+    /// Pages like `build.rs` makes them, from a pixel per row and column.
+    fn pack(width: usize, height: usize, pixel: impl Fn(usize, usize) -> bool) -> Vec<u8> {
+        let mut pages = vec![0; width * height.div_ceil(8)];
+        for y in 0..height {
+            for x in 0..width {
+                if pixel(x, y) {
+                    pages[y / 8 * width + x] |= 0x80 >> (y % 8);
+                }
+            }
+        }
+        pages
+    }
+
+    fn set(buffer: &mut FrameBuffer, x: usize, y: usize, on: bool) {
+        let (index, bit) = pixel_index(x, y);
+        if on {
+            buffer[index] |= bit;
+        } else {
+            buffer[index] &= !bit;
+        }
+    }
+
+    /// Something for the sprites to be drawn over.
+    fn background() -> FrameBuffer {
+        let mut buffer = [0; BUFFER_SIZE];
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                set(&mut buffer, x, y, noise(x, y, 7));
+            }
+        }
+        buffer
+    }
+
+    /// `draw_into` a pixel at a time.
+    fn reference(sprite: &Sprite, buffer: &mut FrameBuffer, pos: Point, flip: Flip) {
+        for y in 0..sprite.height {
+            for x in 0..sprite.width {
+                let (screen_x, screen_y) = (pos.x + x as i32, pos.y + y as i32);
+                if !(0..WIDTH as i32).contains(&screen_x) || !(0..HEIGHT as i32).contains(&screen_y) {
+                    continue;
+                }
+                let src_x = if flip.horizontal { sprite.width - 1 - x } else { x };
+                let src_y = if flip.vertical { sprite.height - 1 - y } else { y };
+                if let Some(dark) = sprite.pixel(src_x, src_y) {
+                    set(buffer, screen_x as usize, screen_y as usize, dark);
+                }
+            }
+        }
+    }
+
+    fn check(sprite: &Sprite) {
+        for flip in [Flip::NONE, Flip::HORIZONTAL, Flip::VERTICAL, Flip::BOTH] {
+            // Aligned and not, inside and clipped at every edge
+            for y in -(sprite.height as i32) - 1..=HEIGHT as i32 + 1 {
+                for x in (-(sprite.width as i32) - 1..=WIDTH as i32 + 1).step_by(3) {
+                    let pos = Point::new(x, y);
+                    let mut expected = background();
+                    reference(sprite, &mut expected, pos, flip);
+                    let mut actual = background();
+                    sprite.draw_into(&mut actual, pos, flip);
+                    assert!(actual == expected, "{}x{} at {:?}, {:?}", sprite.width, sprite.height, pos, flip);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn without_mask() {
+        for (width, height) in [(8, 8), (11, 13), (5, 20), (3, MAX_HEIGHT)] {
+            let image = pack(width, height, |x, y| noise(x, y, 1));
+            check(&Sprite::new(width, height, &image, None));
+        }
+    }
+
+    #[test]
+    fn with_mask() {
+        for (width, height) in [(8, 8), (11, 13), (5, 20), (3, MAX_HEIGHT)] {
+            let mask = pack(width, height, |x, y| noise(x, y, 2));
+            // Dark pixels only where they are drawn, like `build.rs` leaves them
+            let image = pack(width, height, |x, y| noise(x, y, 1) && noise(x, y, 2));
+            check(&Sprite::new(width, height, &image, Some(&mask)));
+        }
+    }
+
+    #[test]
+    fn pixels() {
+        // A 2x9 sprite: dark, light and transparent pixels
+        let image = [0x80, 0x00, 0x00, 0x80];
+        let mask = [0xc0, 0x80, 0x00, 0x80];
+        let sprite = Sprite::new(2, 9, &image, Some(&mask));
+        assert_eq!(sprite.pages(), 2);
+        assert_eq!(sprite.pixel(0, 0), Some(true));
+        assert_eq!(sprite.pixel(0, 1), Some(false));
+        assert_eq!(sprite.pixel(1, 0), Some(false));
+        assert_eq!(sprite.pixel(1, 1), None);
+        assert_eq!(sprite.pixel(0, 8), None);
+        assert_eq!(sprite.pixel(1, 8), Some(true));
+
+        // Without a mask only the dark ones are drawn
+        let sprite = Sprite::new(2, 9, &image, None);
+        assert_eq!(sprite.pixel(0, 1), None);
+    }
+}
@@ -0,0 +1,157 @@
+//! A small executor running the console as a few cooperative tasks.
+//!
+//! Tasks are futures, polled in turn by `run`. They wait for a `Timer`, or
+//! for something that another task or an interrupt changes and then calls
+//! `wake` for. Once every task is waiting, the `Platform` sleeps until the
+//! next timer is due or until something wakes it. Time only moves on
+//! between the rounds of polling, so the tasks of one round all see the same
+//! `now_ms`, which keeps the simulator repeatable.
+
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use bare_metal::Mutex;
+
+/// Tasks `run` can keep track of.
+pub const MAX_TASKS: usize = 32;
+
+/// What the executor needs from the hardware, or from the simulator.
+pub trait Platform {
+    /// Milliseconds since start, never going back.
+    fn now_ms(&mut self) -> u64;
+
+    /// Waits until `until_ms`, or for good if it's `None`, but returns as
+    /// soon as `is_woken`. Returning early does no harm, `run` just polls
+    /// everything again.
+    fn idle(&mut self, until_ms: Option<u64>);
+}
+
+struct State {
+    now_ms: u64,
+    /// Earliest timer the tasks wait for.
+    next_ms: Option<u64>,
+    woken: bool,
+}
+
+static STATE: Mutex<RefCell<State>> = Mutex::new(RefCell::new(State {
+    now_ms: 0,
+    next_ms: None,
+    woken: false,
+}));
+
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
+    critical_section::with(|cs| f(&mut STATE.borrow(cs).borrow_mut()))
+}
+
+/// Time of the current round of polling.
+pub fn now_ms() -> u64 {
+    with_state(|state| state.now_ms)
+}
+
+/// Has the tasks polled again, for anything they might wait on that just changed.
+pub fn wake() {
+    with_state(|state| state.woken = true);
+}
+
+pub fn is_woken() -> bool {
+    with_state(|state| state.woken)
+}
+
+/// Polls `tasks` until they all finish, sleeping while they all wait.
+pub fn run<P: Platform>(platform: &mut P, tasks: &mut [Pin<&mut dyn Future<Output = ()>>]) {
+    assert!(tasks.len() <= MAX_TASKS);
+    let all = ((1u64 << tasks.len()) - 1) as u32;
+    let mut done = 0u32;
+    // Nothing needs waking up individually, every round polls all the tasks
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        let now_ms = platform.now_ms();
+        with_state(|state| {
+            state.now_ms = now_ms;
+            state.next_ms = None;
+            state.woken = false;
+        });
+
+        for (index, task) in tasks.iter_mut().enumerate() {
+            if done & 1 << index == 0 && task.as_mut().poll(&mut cx).is_ready() {
+                done |= 1 << index;
+            }
+        }
+        if done == all {
+            return;
+        }
+
+        let (woken, next_ms) = with_state(|state| (state.woken, state.next_ms));
+        if !woken {
+            platform.idle(next_ms);
+        }
+    }
+}
+
+/// Ready once `now_ms` reaches the given time.
+pub struct Timer {
+    at_ms: u64,
+}
+
+impl Timer {
+    pub fn at_ms(at_ms: u64) -> Self {
+        Self { at_ms }
+    }
+
+    pub fn after_ms(ms: u32) -> Self {
+        Self::at_ms(now_ms() + ms as u64)
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<()> {
+        with_state(|state| {
+            if state.now_ms >= self.at_ms {
+                return Poll::Ready(());
+            }
+            state.next_ms = Some(state.next_ms.map_or(self.at_ms, |next_ms| next_ms.min(self.at_ms)));
+            Poll::Pending
+        })
+    }
+}
+
+pub async fn sleep_ms(ms: u32) {
+    Timer::after_ms(ms).await;
+}
+
+/// Timers at a steady rate, for frames and other periodic work.
+pub struct Ticker {
+    period_ms: u32,
+    next_ms: u64,
+}
+
+impl Ticker {
+    /// The first tick comes one period from now.
+    pub fn every(period_ms: u32) -> Self {
+        Self {
+            period_ms,
+            next_ms: now_ms() + period_ms as u64,
+        }
+    }
+
+    /// Waits for the next tick. A late tick comes right away, but the ones
+    /// missed meanwhile are skipped rather than caught up with.
+    pub fn tick(&mut self) -> Timer {
+        let timer = Timer::at_ms(self.next_ms);
+        self.next_ms = self.next_ms.max(now_ms()) + self.period_ms as u64;
+        timer
+    }
+}
+
+/// Waits until `ready` gives something, asking again on every round of polling.
+pub async fn until<T>(mut ready: impl FnMut() -> Option<T>) -> T {
+    poll_fn(|_| match ready() {
+        Some(value) => Poll::Ready(value),
+        None => Poll::Pending,
+    }).await
+}
@@ -0,0 +1,201 @@
+//! Backgrounds made of 8x8 sprites, scrolling by the pixel.
+
+use embedded_graphics::prelude::Point;
+
+use crate::display::{Display, HEIGHT, WIDTH};
+use crate::sprite::{Flip, Sprite};
+
+pub const TILE_SIZE: usize = 8;
+/// Cell of the map left blank.
+pub const EMPTY: u8 = 0xff;
+
+pub struct TileMap<'a> {
+    tiles: &'a [Sprite<'a>],
+    /// Indexes into `tiles`, row by row.
+    map: &'a [u8],
+    columns: usize,
+    /// Point of the map shown in the top left corner of the screen.
+    scroll: Point,
+}
+
+impl<'a> TileMap<'a> {
+    pub fn new(tiles: &'a [Sprite<'a>], map: &'a [u8], columns: usize) -> Self {
+        assert!(tiles.iter().all(|tile| tile.width == TILE_SIZE && tile.height == TILE_SIZE));
+        assert!(columns > 0 && map.len().is_multiple_of(columns));
+        Self {
+            tiles,
+            map,
+            columns,
+            scroll: Point::zero(),
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.map.len() / self.columns
+    }
+
+    /// `None` outside the map, or where it is `EMPTY`.
+    pub fn tile_at(&self, column: i32, row: i32) -> Option<u8> {
+        if column < 0 || row < 0 || column as usize >= self.columns || row as usize >= self.rows() {
+            return None;
+        }
+        Some(self.map[row as usize * self.columns + column as usize]).filter(|tile| *tile != EMPTY)
+    }
+
+    pub fn scroll(&self) -> Point {
+        self.scroll
+    }
+
+    pub fn set_scroll(&mut self, scroll: Point) {
+        self.scroll = scroll;
+    }
+
+    pub fn scroll_by(&mut self, offset: Point) {
+        self.scroll += offset;
+    }
+
+    /// Draws the tiles in view into the buffer, `Display::draw` sends it.
+    ///
+    /// Only the tiles are drawn, what is under blank and transparent parts stays.
+    pub fn draw<D: Display>(&self, pcd: &mut D) {
+        let size = TILE_SIZE as i32;
+        let first = Point::new(self.scroll.x.div_euclid(size), self.scroll.y.div_euclid(size));
+        let offset = Point::new(self.scroll.x.rem_euclid(size), self.scroll.y.rem_euclid(size));
+        let buffer = pcd.buffer_mut();
+
+        // One more tile each way for the ones partly in view, the 84 columns
+        // not being a whole number of tiles
+        for row in 0..=HEIGHT.div_ceil(TILE_SIZE) as i32 {
+            for column in 0..=WIDTH.div_ceil(TILE_SIZE) as i32 {
+                let Some(tile) = self.tile_at(first.x + column, first.y + row) else {
+                    continue;
+                };
+                let pos = Point::new(column * size, row * size) - offset;
+                self.tiles[tile as usize].draw_into(buffer, pos, Flip::NONE);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::vec::Vec;
+
+    use crate::display::{pixel_index, FrameBuffer, TestDisplay, BUFFER_SIZE};
+
+    /// Tile `tile`: a dark frame with its number in dots, see-through inside.
+    fn tile_pixel(tile: usize, x: usize, y: usize) -> Option<bool> {
+        let edge = x == 0 || y == 0 || x == TILE_SIZE - 1 || y == TILE_SIZE - 1;
+        if edge {
+            Some(true)
+        } else if y == 3 && x <= tile + 1 {
+            Some(x.is_multiple_of(2))
+        } else {
+            None
+        }
+    }
+
+    fn pack(pixel: impl Fn(usize, usize) -> bool) -> Vec<u8> {
+        let mut pages = vec![0; TILE_SIZE];
+        for y in 0..TILE_SIZE {
+            for x in 0..TILE_SIZE {
+                if pixel(x, y) {
+                    pages[y / 8 * TILE_SIZE + x] |= 0x80 >> (y % 8);
+                }
+            }
+        }
+        pages
+    }
+
+    /// Image and mask of each tile.
+    fn tile_data() -> Vec<(Vec<u8>, Vec<u8>)> {
+        (0..3)
+            .map(|tile| (pack(|x, y| tile_pixel(tile, x, y) == Some(true)), pack(|x, y| tile_pixel(tile, x, y).is_some())))
+            .collect()
+    }
+
+    fn tiles(data: &[(Vec<u8>, Vec<u8>)]) -> Vec<Sprite<'_>> {
+        data.iter().map(|(image, mask)| Sprite::new(TILE_SIZE, TILE_SIZE, image, Some(mask))).collect()
+    }
+
+    // 5x3, with blank cells
+    const COLUMNS: usize = 5;
+    const MAP: [u8; 15] = [
+        0, 1, 2, EMPTY, 0,
+        EMPTY, 2, 1, 0, 1,
+        2, 0, EMPTY, 1, 2,
+    ];
+
+    /// What `draw` should leave on a screen filled with `background`.
+    fn reference(scroll: Point, background: bool) -> FrameBuffer {
+        let mut buffer = [if background { 0xff } else { 0 }; BUFFER_SIZE];
+        let size = TILE_SIZE as i32;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let point = scroll + Point::new(x as i32, y as i32);
+                let (column, row) = (point.x.div_euclid(size), point.y.div_euclid(size));
+                if column < 0 || row < 0 || column >= COLUMNS as i32 || row >= (MAP.len() / COLUMNS) as i32 {
+                    continue;
+                }
+                let tile = MAP[row as usize * COLUMNS + column as usize];
+                if tile == EMPTY {
+                    continue;
+                }
+                let pixel = tile_pixel(tile as usize, point.x.rem_euclid(size) as usize, point.y.rem_euclid(size) as usize);
+                if let Some(dark) = pixel {
+                    let (index, bit) = pixel_index(x, y);
+                    if dark {
+                        buffer[index] |= bit;
+                    } else {
+                        buffer[index] &= !bit;
+                    }
+                }
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn map() {
+        let data = tile_data();
+        let tiles = tiles(&data);
+        let map = TileMap::new(&tiles, &MAP, COLUMNS);
+        assert_eq!((map.columns(), map.rows()), (5, 3));
+        assert_eq!(map.tile_at(1, 0), Some(1));
+        assert_eq!(map.tile_at(3, 0), None);
+        assert_eq!(map.tile_at(-1, 0), None);
+        assert_eq!(map.tile_at(0, 3), None);
+    }
+
+    #[test]
+    fn scrolls_and_clips() {
+        let data = tile_data();
+        let tiles = tiles(&data);
+        let mut map = TileMap::new(&tiles, &MAP, COLUMNS);
+
+        // From well before the map to past its end, by the pixel
+        for y in -(HEIGHT as i32) - 2..=26 {
+            for x in (-(WIDTH as i32) - 2..=42).step_by(3) {
+                map.set_scroll(Point::new(x, y));
+                for background in [false, true] {
+                    let mut display = TestDisplay {
+                        buffer: [if background { 0xff } else { 0 }; BUFFER_SIZE],
+                        ..Default::default()
+                    };
+                    map.draw(&mut display);
+                    assert!(display.buffer == reference(map.scroll(), background), "scrolled to {:?}", map.scroll());
+                }
+            }
+        }
+
+        map.set_scroll(Point::new(1, 2));
+        map.scroll_by(Point::new(-3, 4));
+        assert_eq!(map.scroll(), Point::new(-2, 6));
+    }
+}
@@ -0,0 +1,29 @@
+//! The asset converters and version details of `build.rs`, tested on the host.
+
+// Parts only `build.rs` uses
+#![allow(dead_code)]
+
+#[path = "../build/bdf.rs"]
+mod bdf;
+#[path = "../build/image.rs"]
+mod image;
+#[path = "../build/level.rs"]
+mod level;
+#[path = "../build/version.rs"]
+mod version;
+
+/// A level packed like `build.rs` does reads back the same in the game.
+#[test]
+fn levels_round_trip() {
+    for text in ["1.2\nF.A\n", "123\n", "9\n.\nB\n", "....\n..C.\n"] {
+        let level = level::Level::parse(text).unwrap();
+        let packed = level.packed();
+        let runtime = rp2040_game::level::Level::new(level.width, level.height, &packed);
+        for y in 0..level.height {
+            for x in 0..level.width {
+                assert_eq!(runtime.cell(x, y), level.cells[y * level.width + x], "{:?} at {},{}", text, x, y);
+            }
+        }
+        assert_eq!(runtime.cell(level.width, 0), 0);
+    }
+}
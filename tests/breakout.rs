@@ -0,0 +1,7 @@
+//! Ball, paddle and brick physics of the brick breaker, tested on the host.
+
+// Parts only the game uses
+#![allow(dead_code)]
+
+#[path = "../src/games/breakout/physics.rs"]
+mod physics;
@@ -0,0 +1,54 @@
+//! The simulator played from a script, every frame checked against the
+//! ones saved in `tests/golden`.
+//!
+//! After a change meant to alter the screens, save new ones with
+//!
+//! ```text
+//! cargo run --target x86_64-unknown-linux-gnu --no-default-features --features sim --bin sim -- scripts/snake.txt tests/golden/snake
+//! ```
+
+// `main` and the parts only it uses
+#![allow(dead_code)]
+
+#[path = "../src/bin/sim.rs"]
+mod sim;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `frame_NNNNN.pbm` files in `dir`, in order.
+fn frames(dir: &Path) -> Vec<PathBuf> {
+    let mut frames: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "pbm"))
+        .collect();
+    frames.sort();
+    frames
+}
+
+fn check_script(name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let text = fs::read_to_string(root.join("scripts").join(format!("{}.txt", name))).unwrap();
+    let script = sim::parse_script(&text).unwrap();
+
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden").join(name);
+    // Frames of an earlier run would look like extra ones
+    let _ = fs::remove_dir_all(&out_dir);
+    sim::run_script(script, &out_dir);
+
+    let expected = frames(&root.join("tests/golden").join(name));
+    let actual = frames(&out_dir);
+    assert!(!expected.is_empty());
+    for (expected, actual) in expected.iter().zip(&actual) {
+        assert_eq!(expected.file_name(), actual.file_name());
+        assert!(fs::read(expected).unwrap() == fs::read(actual).unwrap(), "{} differs", actual.display());
+    }
+    assert_eq!(expected.len(), actual.len(), "frame count");
+}
+
+// The simulator is a single thread with global state, one script per test binary
+#[test]
+fn snake_from_the_menu() {
+    check_script("snake");
+}